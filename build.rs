@@ -0,0 +1,73 @@
+//! Compiles `slowa.txt` for the `embedded-dict` feature, so the binary carries the word
+//! list already parsed instead of parsing the text on every start.
+
+use std::path::Path;
+
+/// Must match `MAGIC`, `FORMAT_VERSION` and the flags in src/compiled_dict.rs.
+const MAGIC: &[u8; 8] = b"PLSKDICT";
+const FORMAT_VERSION: u32 = 2;
+const COMMON: u8 = 1;
+const RANKED: u8 = 4;
+
+fn main() {
+    println!("cargo:rerun-if-changed=slowa.txt");
+    if std::env::var_os("CARGO_FEATURE_EMBEDDED_DICT").is_none() {
+        return;
+    }
+
+    let content = std::fs::read_to_string("slowa.txt").expect("read slowa.txt");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let out = Path::new(&out_dir).join("slowa.bin");
+    std::fs::write(out, compile(&content)).expect("write the compiled word list");
+}
+
+/// The word list in the compiled format, read the same way as `parse_words` reads it.
+fn compile(content: &str) -> Vec<u8> {
+    let mut records = Vec::new();
+    let mut count: u32 = 0;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let (entry, flags) = match trimmed.strip_prefix('*') {
+            Some(entry) => (entry, COMMON),
+            None => (trimmed, 0),
+        };
+        let (word, rank) = split_rank(entry);
+        let len = u16::try_from(word.len()).expect("word shorter than 64 KiB");
+        let flags = if rank.is_some() {
+            flags | RANKED
+        } else {
+            flags
+        };
+        records.push(flags);
+        records.extend_from_slice(&len.to_le_bytes());
+        records.extend_from_slice(word.as_bytes());
+        if let Some(rank) = rank {
+            records.extend_from_slice(&rank.to_le_bytes());
+        }
+        count += 1;
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.extend_from_slice(&records);
+    bytes
+}
+
+/// The word and frequency rank of an entry, as `split_rank` in src/dictionary.rs.
+fn split_rank(entry: &str) -> (&str, Option<u32>) {
+    let ranked = entry
+        .rsplit_once(char::is_whitespace)
+        .and_then(|(word, rank)| {
+            let rank = rank.parse::<u32>().ok().filter(|&rank| rank > 0)?;
+            Some((word.trim_end(), rank))
+        });
+    match ranked {
+        Some((word, rank)) => (word, Some(rank)),
+        None => (entry, None),
+    }
+}
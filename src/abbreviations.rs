@@ -0,0 +1,97 @@
+/// A common Polish abbreviation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Abbreviation {
+    pub text: &'static str,
+    /// Shortened forms ("prof." for "profesor") take a period; contractions keeping the
+    /// last letter of the full word ("dr" for "doktor") are written without one.
+    pub needs_period: bool,
+}
+
+const fn period(text: &'static str) -> Abbreviation {
+    Abbreviation {
+        text,
+        needs_period: true,
+    }
+}
+
+const fn contraction(text: &'static str) -> Abbreviation {
+    Abbreviation {
+        text,
+        needs_period: false,
+    }
+}
+
+/// Abbreviations shared by the spell checker and typography rules.
+/// Single letters ("r.", "w.") are left out, since they are also ordinary words.
+pub const ABBREVIATIONS: &[Abbreviation] = &[
+    period("ang"),
+    period("bm"),
+    period("cdn"),
+    period("cz"),
+    period("doc"),
+    period("dyr"),
+    period("godz"),
+    period("inż"),
+    period("itd"),
+    period("itp"),
+    period("jw"),
+    period("lek"),
+    period("łac"),
+    period("np"),
+    period("pkt"),
+    period("pl"),
+    period("płk"),
+    period("por"),
+    period("pol"),
+    period("pn"),
+    period("prof"),
+    period("przyp"),
+    period("ps"),
+    period("red"),
+    period("rys"),
+    period("str"),
+    period("tab"),
+    period("tel"),
+    period("tj"),
+    period("tzn"),
+    period("tzw"),
+    period("ul"),
+    period("wyd"),
+    period("zob"),
+    contraction("dr"),
+    contraction("mgr"),
+    contraction("nr"),
+    contraction("wg"),
+    contraction("zł"),
+];
+
+/// Look up an abbreviation (case-insensitive).
+pub fn find(word: &[char]) -> Option<Abbreviation> {
+    let word: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+    ABBREVIATIONS.iter().copied().find(|a| a.text == word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_find() {
+        assert!(find(&chars("prof")).unwrap().needs_period);
+        assert!(find(&chars("Tzw")).unwrap().needs_period);
+        assert!(!find(&chars("mgr")).unwrap().needs_period);
+        assert_eq!(find(&chars("profesor")), None);
+    }
+
+    #[test]
+    fn test_list_is_lowercase_and_unique() {
+        for (i, a) in ABBREVIATIONS.iter().enumerate() {
+            assert_eq!(a.text, a.text.to_lowercase());
+            assert!(!ABBREVIATIONS[i + 1..].iter().any(|b| b.text == a.text));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Links: a scheme with "//" ("https://", "ftp://") or "www." and everything up to
+/// whitespace or a bracket or quote around the link.
+static URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(?:[a-z][a-z0-9+.-]*://|www\.)[^\s<>()\[\]{}"'`]+"#).unwrap()
+});
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").unwrap());
+
+/// Paths: absolute or relative to the home or current directory ("/etc/hosts",
+/// "~/.config", "../src"), with at least two separators ("docs/pl/index"), ending in a
+/// file name with an extension ("src/main.rs"), or starting with a Windows drive.
+static PATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        (?:~|\.\.?)?/[\w.~-]+(?:/[\w.~-]*)*
+        | [\w.-]+(?:/[\w.~-]+){2,}/?
+        | [\w.-]+(?:/[\w.-]+)*/[\w-]+\.[[:alnum:]]{1,5}\b
+        | [A-Za-z]:\\\S*",
+    )
+    .unwrap()
+});
+
+const SENTENCE_PUNCTUATION: [char; 6] = ['.', ',', ';', ':', '!', '?'];
+
+/// Character ranges of the URLs, e-mail addresses and file paths in `source`, whose
+/// parts ("github", "gmail") aren't words to spell-check.
+pub fn find(source: &[char]) -> Vec<Range<usize>> {
+    let text: String = source.iter().collect();
+    let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+    let char_index = |offset: usize| offsets.partition_point(|&o| o < offset);
+
+    let mut found = Vec::new();
+    for pattern in [&URL, &EMAIL, &PATH] {
+        for matched in pattern.find_iter(&text) {
+            // Only whole tokens: "lub" in "i/lub" is not a path
+            let preceding = text[..matched.start()].chars().next_back();
+            if preceding.is_some_and(char::is_alphanumeric) {
+                continue;
+            }
+            // Sentence punctuation after a link isn't part of it
+            let trimmed = matched.as_str().trim_end_matches(SENTENCE_PUNCTUATION);
+            let range = char_index(matched.start())..char_index(matched.start() + trimmed.len());
+            // The path of a link, or a link's user, is already covered
+            let overlaps =
+                |other: &Range<usize>| other.start < range.end && range.start < other.end;
+            if !found.iter().any(overlaps) {
+                found.push(range);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str) -> Vec<String> {
+        let source: Vec<char> = text.chars().collect();
+        find(&source)
+            .into_iter()
+            .map(|range| source[range].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_urls_and_emails() {
+        assert_eq!(
+            found("Zobacz [repo](https://github.com/kaarrot/polski-ls), www.sjp.pl."),
+            vec!["https://github.com/kaarrot/polski-ls", "www.sjp.pl"]
+        );
+        assert_eq!(
+            found("Pisz na jan.kowalski+ls@poczta.onet.pl!"),
+            vec!["jan.kowalski+ls@poczta.onet.pl"]
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(
+            found("Edytuj ~/.config/polski-ls/slownik.txt, src/main.rs i C:\\Users\\Żaneta."),
+            vec![
+                "~/.config/polski-ls/slownik.txt",
+                "src/main.rs",
+                "C:\\Users\\Żaneta"
+            ]
+        );
+        assert_eq!(
+            found("docs/pl/index oraz /etc/hosts"),
+            vec!["docs/pl/index", "/etc/hosts"]
+        );
+        // Words joined by a slash are not paths
+        assert!(found("i/lub, km/h, wiersz/proza").is_empty());
+    }
+}
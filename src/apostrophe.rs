@@ -0,0 +1,55 @@
+/// Apostrophes written between a foreign word and its Polish ending ("iPhone'a",
+/// "Johnny’ego"), typewriter and typographic.
+const APOSTROPHES: [char; 2] = ['\'', '’'];
+
+/// Case endings written after an apostrophe. Polish adds one when the base ends in a
+/// letter that isn't pronounced ("Joyce'a") or is pronounced unlike Polish ("Johnny'ego").
+const ENDINGS: &[&str] = &[
+    "a", "ach", "ami", "e", "ego", "em", "emu", "go", "i", "m", "mu", "om", "owi", "owie", "u",
+    "y", "ą", "ę", "ów",
+];
+
+/// Whether `ch` is an apostrophe.
+pub fn is_apostrophe(ch: char) -> bool {
+    APOSTROPHES.contains(&ch)
+}
+
+/// Whether `letters` (any case) is an ending written after an apostrophe.
+pub fn is_ending(letters: &[char]) -> bool {
+    let lower: String = letters.iter().flat_map(|c| c.to_lowercase()).collect();
+    ENDINGS.contains(&lower.as_str())
+}
+
+/// The base of an inflected foreign word ("iPhone" in "iPhone'a") and the rest from the
+/// apostrophe on, or `None` if `word` isn't one.
+pub fn split(word: &[char]) -> Option<(&[char], &[char])> {
+    let at = word.iter().rposition(|&ch| is_apostrophe(ch))?;
+    let (base, ending) = word.split_at(at);
+    let base_ends_in_letter = base.last().is_some_and(|c| c.is_alphabetic());
+    (base_ends_in_letter && is_ending(&ending[1..])).then_some((base, ending))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_str(word: &str) -> Option<(String, String)> {
+        let chars: Vec<char> = word.chars().collect();
+        split(&chars).map(|(base, ending)| (base.iter().collect(), ending.iter().collect()))
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            split_str("Johnny'ego"),
+            Some(("Johnny".to_string(), "'ego".to_string()))
+        );
+        assert_eq!(
+            split_str("iPhone’A"),
+            Some(("iPhone".to_string(), "’A".to_string()))
+        );
+        assert_eq!(split_str("rock'n"), None);
+        assert_eq!(split_str("'a"), None);
+        assert_eq!(split_str("kot"), None);
+    }
+}
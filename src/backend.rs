@@ -1,69 +1,878 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use tokio::sync::Mutex;
-use tower_lsp_server::jsonrpc::Result as JsonResult;
+use dashmap::DashMap;
+use notify::RecommendedWatcher;
+use tokio::sync::{Mutex, RwLock};
+use tower_lsp_server::jsonrpc::{Error as JsonError, ErrorCode, Result as JsonResult};
 use tower_lsp_server::lsp_types::{
-    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
-    Command, CompletionItem, CompletionItemKind, CompletionList, CompletionOptions, CompletionParams,
-    CompletionResponse, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
-    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
-    ExecuteCommandOptions, ExecuteCommandParams, InitializeParams, InitializeResult,
-    InitializedParams, MessageType, Position, Range, ServerCapabilities, ServerInfo, TextEdit,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, Uri, WorkspaceEdit,
+    ClientInfo, CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand,
+    CodeActionParams, CodeActionProviderCapability, CodeActionResponse, CodeDescription, Command,
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionList,
+    CompletionOptions, CompletionParams, CompletionResponse, CompletionTextEdit, Diagnostic,
+    DiagnosticOptions, DiagnosticServerCapabilities, DiagnosticSeverity,
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentDiagnosticParams, DocumentDiagnosticReport,
+    DocumentDiagnosticReportResult, DocumentHighlight, DocumentHighlightKind,
+    DocumentHighlightParams, Documentation, ExecuteCommandOptions, ExecuteCommandParams,
+    FileSystemWatcher, FullDocumentDiagnosticReport, GlobPattern, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams,
+    MarkupContent, MarkupKind, MessageType, NumberOrString, OneOf, Position, PrepareRenameResponse,
+    ProgressToken, Range, Registration, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, RelativePattern, RenameOptions, RenameParams,
+    ServerCapabilities, ServerInfo, ShowDocumentParams, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, TextEdit,
+    UnchangedDocumentDiagnosticReport, Unregistration, Uri, WorkspaceEdit,
 };
-use tower_lsp_server::{Client, LanguageServer};
+use tower_lsp_server::{Client, LanguageServer, UriExt};
 
-use crate::dictionary::{Dictionary, SimpleDictionary};
-use crate::pos_conv::LineIndex;
+use crate::abbreviations;
+use crate::addresses;
+use crate::apostrophe;
+use crate::bigrams::Bigrams;
+use crate::casing::{apply_capitalization, apply_occurrence_casing};
+use crate::commas;
+use crate::config::{
+    read_settings_file, AlphanumericWords, DictionarySettings, FileSettings, FrontMatterSettings,
+    MenuPlacement, ScoringWeights, Settings, SettingsLayers, PROJECT_CONFIG_FILE,
+    USER_CONFIG_FILE,
+};
+use crate::diagnostics::{self, Category, DiagnosticData};
+use crate::dictionary::{
+    base_letter, same_word, user_config_dir, Dictionary, DictionaryOptions, FuzzyMatchResult,
+    SharedDictionary, SimpleDictionary, WordInfo,
+};
+use crate::expansions::Expansions;
+use crate::front_matter;
+use crate::gettext;
+use crate::glossary::Glossary;
+use crate::ignore::{FileFilter, IgnoreFile};
+use crate::keyboard::weighted_distance;
+use crate::langdetect::polish_paragraphs;
+use crate::latency::{CompletionBudget, DEFAULT_CANDIDATE_CAP};
+use crate::latex;
+use crate::morphology::{Analysis, Morphology};
+use crate::numerals::{case_at, number_at, to_words, Case};
+use crate::org;
+use crate::pos_conv::{LineIndex, PositionEncoding};
+use crate::progress::{send_partial_result, ProgressReporter, LARGE_DOCUMENT};
+use crate::reflow::{paragraph_at, reflow};
+use crate::repeated_words;
+use crate::replacements::Replacements;
+use crate::rules::Rules;
+use crate::scheduler::{Debouncer, DiagnosticsPublisher, DiagnosticsQueue};
+use crate::status::{CheckStats, StatusNotification};
+use crate::symbols;
+use crate::typography;
+use crate::usage_stats::UsageStats;
 
 const CMD_ADD_TO_DICTIONARY: &str = "polski-ls.addToDictionary";
+const CMD_ADD_TO_PROJECT_DICTIONARY: &str = "polski-ls.addToProjectDictionary";
+const CMD_IGNORE_IN_FILE: &str = "polski-ls.ignoreInFile";
+const CMD_IGNORE_FOR_SESSION: &str = "polski-ls.ignoreForSession";
+const CMD_CLEAR_SESSION_IGNORES: &str = "polski-ls.clearSessionIgnores";
+const CMD_RELOAD_DICTIONARIES: &str = "polski-ls.reloadDictionaries";
+const CMD_OPEN_USER_DICTIONARY: &str = "polski-ls.openUserDictionary";
+const CMD_RELOAD_CONFIG: &str = "polski-ls.reloadConfig";
+const CMD_REFLOW_PARAGRAPH: &str = "polski-ls.reflowParagraph";
+const CMD_COMPLETION_ACCEPTED: &str = "polski-ls.completionAccepted";
+const CMD_TOGGLE: &str = "polski-ls.toggle";
+const CMD_TOGGLE_GLOBAL: &str = "polski-ls.toggleGlobal";
+const CMD_CHECK_DOCUMENT: &str = "polski-ls.checkDocument";
+
+/// `source` in the data of completions found in the document rather than the dictionary.
+const DOCUMENT_SOURCE: &str = "document";
+/// `source` in the data of completions found in other open documents.
+const OPEN_DOCUMENTS_SOURCE: &str = "openDocuments";
+/// `source` in the data of phrase completions, which also carry `phrasePrefix`: the
+/// index of the typed text they complete in `CompletionContext::phrase_prefixes`.
+const PHRASE_SOURCE: &str = "phrase";
+/// `source` in the data of abbreviations, which also carry their `expansion`.
+const ABBREVIATION_SOURCE: &str = "abbreviation";
+
+/// Code action kind for fixing every misspelling in a file at once.
+const FIX_ALL_KIND: &str = "source.fixAll.polski-ls";
+/// Kind of the action binding every hanging conjunction to the next word; run with the
+/// other fix-all actions, e.g. on save.
+const HANGING_CONJUNCTIONS_KIND: &str = "source.fixAll.polski-ls.hangingConjunctions";
+
+/// Number of documents checked concurrently after a bulk open.
+const BACKGROUND_CHECKS: usize = 2;
+
+/// Registration id for the dictionary file watchers, so they can be replaced.
+const DICTIONARY_WATCHERS_ID: &str = "polski-ls-dictionaries";
+const WATCHED_FILES_METHOD: &str = "workspace/didChangeWatchedFiles";
+
+/// Minimum time between two diagnostic publishes for one document, so at most four
+/// per second reach the client.
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Percentage points between progress reports for a large document.
+const PROGRESS_STEP: u32 = 5;
+
+/// Completions with fewer words starting with the prefix than this also get fuzzy
+/// matches, which cover typos in the prefix but scan the whole dictionary.
+const MIN_PREFIX_HITS: usize = 10;
+
+/// Clients that select the first completion as soon as the menu opens, so a space
+/// committing it would replace words that were already typed correctly.
+const PRESELECTING_CLIENTS: &[&str] = &["Visual Studio Code", "VSCodium", "Zed"];
+
+/// Parts of identifiers shorter than this aren't checked: "do" in "doZrobienia" or "Id"
+/// in "klientId" are too short to tell.
+const MIN_IDENTIFIER_PART: usize = 3;
+
+/// Most words a phrase completion looks back over before the one being typed.
+const MAX_PHRASE_WORDS: usize = 5;
 
 /// Document state stored for each open file.
+#[derive(Clone)]
 struct DocumentState {
     source: Vec<char>,
     line_index: LineIndex,
+    language_id: String,
+    /// Untitled scratch buffer without a language hint: only check Polish paragraphs.
+    detect_language: bool,
+    /// Words ignored in this document only (lowercase), dropped when it is closed.
+    ignored_words: HashSet<String>,
+    /// Checking turned off for this document with `polski-ls.toggle`, until it is closed.
+    disabled: bool,
+    /// `polski-ls.checkDocument` asked for this text to be checked, even in on-demand
+    /// mode or over the length limit. Dropped by the next edit.
+    check_requested: bool,
+    /// Version from the client, published with the diagnostics for this text.
+    version: Option<i32>,
+    /// Set once a newer version replaces this text or the document is closed, so
+    /// in-flight checks of it stop instead of publishing stale ranges.
+    superseded: Arc<AtomicBool>,
+    /// Lowercase word -> spelling of its first occurrence and how often it occurs, built
+    /// the first time another document completes from this text.
+    word_index: OnceLock<BTreeMap<String, (String, usize)>>,
+}
+
+impl DocumentState {
+    fn new(uri: &Uri, source: Vec<char>, line_index: LineIndex, language_id: String) -> Self {
+        let detect_language = uri.scheme().is_some_and(|scheme| scheme.as_str() == "untitled")
+            && matches!(language_id.as_str(), "" | "plaintext");
+        Self {
+            source,
+            line_index,
+            language_id,
+            detect_language,
+            ignored_words: HashSet::new(),
+            disabled: false,
+            check_requested: false,
+            version: None,
+            superseded: Arc::new(AtomicBool::new(false)),
+            word_index: OnceLock::new(),
+        }
+    }
+
+    /// Mark this text as outdated, stopping checks that are still running on it.
+    fn supersede(&self) {
+        self.superseded.store(true, Ordering::Relaxed);
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.superseded.load(Ordering::Relaxed)
+    }
+
+    /// Check whether the user chose to ignore this word in the document.
+    fn ignores(&self, word: &[char]) -> bool {
+        self.ignored_words.contains(&lowercase(word))
+    }
+
+    /// Check whether the character at `idx` belongs to text that should be spell-checked.
+    fn is_checked(&self, regions: &[std::ops::Range<usize>], idx: usize) -> bool {
+        !self.detect_language || regions.iter().any(|range| range.contains(&idx))
+    }
+
+    /// Every word of the text, for completing it in other documents.
+    fn word_index(&self) -> &BTreeMap<String, (String, usize)> {
+        self.word_index.get_or_init(|| {
+            let mut index = BTreeMap::new();
+            for (word, _, _) in extract_words(&self.source) {
+                if word.iter().any(|c| c.is_alphabetic()) {
+                    let entry = index
+                        .entry(lowercase(&word))
+                        .or_insert_with(|| (word.iter().collect(), 0));
+                    entry.1 += 1;
+                }
+            }
+            index
+        })
+    }
+
+    /// Markup that isn't prose, like LaTeX commands and math, Org source blocks, the
+    /// source texts of a translation catalog, Markdown front matter or the code around
+    /// comments and strings.
+    fn markup(&self, front_matter: &FrontMatterSettings) -> Vec<std::ops::Range<usize>> {
+        #[cfg(feature = "tree-sitter")]
+        if let Some(code) = crate::source_code::non_prose(&self.language_id, &self.source) {
+            return code;
+        }
+        if latex::is_latex(&self.language_id) {
+            latex::non_prose(&self.source)
+        } else if org::is_org(&self.language_id) {
+            org::non_prose(&self.source)
+        } else if gettext::is_po(&self.language_id) {
+            gettext::non_prose(&self.source)
+        } else if front_matter::is_markdown(&self.language_id) {
+            front_matter::non_prose(&self.source, &front_matter.checked_keys)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Whether straight quotes in the text are prose, rather than LaTeX shorthands,
+    /// catalog syntax or string delimiters in source code.
+    fn has_prose_quotes(&self) -> bool {
+        self.has_prose_dashes() && !gettext::is_po(&self.language_id)
+    }
+
+    /// Whether hyphens in the text are prose, rather than LaTeX dashes ("--") or
+    /// operators in source code.
+    fn has_prose_dashes(&self) -> bool {
+        #[cfg(feature = "tree-sitter")]
+        if crate::source_code::has_grammar(&self.language_id) {
+            return false;
+        }
+        !latex::is_latex(&self.language_id)
+    }
+
+    /// How the text writes a non-breaking space: `~` in LaTeX, the character elsewhere.
+    fn non_breaking_space(&self) -> &'static str {
+        if latex::is_latex(&self.language_id) {
+            "~"
+        } else {
+            "\u{a0}"
+        }
+    }
+
+    /// Paragraphs detected as Polish, for documents that need language detection.
+    fn polish_regions(&self) -> Vec<std::ops::Range<usize>> {
+        if self.detect_language {
+            polish_paragraphs(&self.source)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Spell-checking state shared between request handlers and background workers.
+#[derive(Clone)]
+struct SpellChecker {
+    dictionary: SharedDictionary,
+    settings: Arc<RwLock<Settings>>,
+    stats: Arc<Mutex<CheckStats>>,
+    /// Words ignored in every document until the server exits (lowercase).
+    session_ignored: Arc<RwLock<HashSet<String>>>,
+    /// Rules for errors spanning words, such as "wogóle" for "w ogóle".
+    replacements: Arc<Replacements>,
+    /// Grammar and style rules from reguly.toml files.
+    rules: Arc<Rules>,
+    progress: ProgressReporter,
+}
+
+impl SpellChecker {
+    /// Remember how many words were flagged in a document and send the updated status.
+    async fn record(&self, client: &Client, uri: &Uri, flagged: usize) {
+        self.stats.lock().await.record(uri, flagged);
+        self.send_status(client).await;
+    }
+
+    /// Drop a closed document from the statistics and send the updated status.
+    async fn forget(&self, client: &Client, uri: &Uri) {
+        self.stats.lock().await.forget(uri);
+        self.send_status(client).await;
+    }
+
+    async fn send_status(&self, client: &Client) {
+        let dictionary = self.dictionary.load();
+        let (size, backend) = (dictionary.word_count(), dictionary.backend_name());
+
+        let status = self.stats.lock().await.status(size, backend);
+        client.send_notification::<StatusNotification>(status).await;
+    }
+
+    /// Check spelling and return diagnostics for unknown words. Stops early once `doc` is
+    /// superseded; callers drop the partial result then.
+    async fn check(&self, doc: &DocumentState) -> Vec<Diagnostic> {
+        // Against the empty stand-in every word would be flagged
+        self.dictionary.loaded().await;
+        let source = &doc.source;
+        let line_index = &doc.line_index;
+        let words = extract_words(source);
+        let regions = doc.polish_regions();
+        // Words in links, e-mail addresses, paths ("github") and markup are not prose
+        let mut not_prose = addresses::find(source);
+        not_prose.extend(doc.markup(&self.settings.read().await.front_matter));
+        let not_prose = merge_spans(not_prose);
+        let in_markup = |start: usize, end: usize| {
+            let next = not_prose.partition_point(|span| span.end <= start);
+            not_prose.get(next).is_some_and(|span| span.start < end)
+        };
+        // The part of a word outside them, like "plików" in "\nplików" of a .po file
+        let outside_markup = |start: usize, end: usize| {
+            let mut word = start..end;
+            let first = not_prose.partition_point(|span| span.end <= word.start);
+            if let Some(span) = not_prose.get(first).filter(|span| span.start <= word.start) {
+                word.start = span.end;
+            }
+            let last = not_prose.partition_point(|span| span.end < word.end);
+            if let Some(span) = not_prose.get(last).filter(|span| span.start < word.end) {
+                word.end = span.start;
+            }
+            (word.start < word.end).then_some((word.start, word.end))
+        };
+        let mut diagnostics = Vec::new();
+        // Suggestions per lowercase word, so repeated typos are matched once
+        let mut suggestion_cache: HashMap<String, Vec<String>> = HashMap::new();
+
+        let settings = self.settings.read().await;
+        let (rules, scoring) = (settings.rules, settings.scoring);
+        let max_suggestions = settings.code_actions.max_suggestions;
+        let min_word_length = settings.diagnostics.min_word_length;
+        let (categories, default_severity) = (settings.categories, settings.diagnostics.severity);
+        // Severity of the findings with a code, or `None` if their category is turned off
+        let severity_of =
+            |code: &str| categories.get(Category::of(code)).severity(default_severity);
+        let alphanumeric_words = settings.diagnostics.alphanumeric_words;
+        let rule_findings = rule_findings(doc, &settings);
+        drop(settings);
+        let session_ignored = self.session_ignored.read().await.clone();
+
+        let word_range = |start_idx: usize, end_idx: usize| Range {
+            start: line_index.index_to_position(source, start_idx),
+            end: line_index.index_to_position(source, end_idx),
+        };
+
+        // Large documents take a while; let the editor show how far along we are
+        let progress = if source.len() >= LARGE_DOCUMENT {
+            self.progress.begin_percentage("Checking spelling").await
+        } else {
+            None
+        };
+        let mut reported = 0;
+
+        // Words inside a replacement match are left to it: the rule says what to write
+        let mut replaced = Vec::new();
+        let replacement_severity =
+            severity_of(diagnostics::REPLACEMENT_RULE).filter(|_| rules.replacements);
+        if let Some(replacement_severity) = replacement_severity {
+            for found in self.replacements.find(source) {
+                if !doc.is_checked(&regions, found.start) || in_markup(found.start, found.end) {
+                    continue;
+                }
+                replaced.push(found.start..found.end);
+                let matched: String = source[found.start..found.end].iter().collect();
+                let mut diagnostic = spelling_diagnostic(
+                    word_range(found.start, found.end),
+                    replacement_severity,
+                    diagnostics::REPLACEMENT_RULE,
+                    format!("Incorrect form: '{}' (write '{}')", matched, found.replacement),
+                    &found.replacement,
+                );
+                let suggestions = vec![found.replacement];
+                diagnostic.data = Some(DiagnosticData { suggestions }.to_value());
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        // Editing leftovers like "się się"; the fix deletes the duplicate
+        let repetition_severity =
+            severity_of(diagnostics::REPEATED_WORD).filter(|_| rules.repeated_words);
+        if let Some(repetition_severity) = repetition_severity {
+            for repetition in repeated_words::find(source) {
+                let (start, end) = (repetition.start, repetition.end);
+                if !doc.is_checked(&regions, end - 1) || in_markup(start, end) {
+                    continue;
+                }
+                let mut diagnostic = spelling_diagnostic(
+                    word_range(start, end),
+                    repetition_severity,
+                    diagnostics::REPEATED_WORD,
+                    format!("Repeated word: '{}'", repetition.word),
+                    &repetition.word,
+                );
+                let suggestions = vec![String::new()];
+                diagnostic.data = Some(DiagnosticData { suggestions }.to_value());
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        // Grammar and style rules from reguly.toml, reported under their own ids
+        if rules.pattern_rules {
+            for found in self.rules.find(source) {
+                let severity = categories.get(found.category).severity(default_severity);
+                let Some(severity) = severity else {
+                    continue;
+                };
+                if !doc.is_checked(&regions, found.start) || in_markup(found.start, found.end) {
+                    continue;
+                }
+                let range = word_range(found.start, found.end);
+                let code = found.code();
+                let diagnostic = rule_diagnostic(range, severity, &found.id, found.message);
+                let suggestions = found.replacements;
+                diagnostics.push(Diagnostic {
+                    code: Some(NumberOrString::String(code)),
+                    data: Some(DiagnosticData { suggestions }.to_value()),
+                    ..diagnostic
+                });
+            }
+        }
+
+        // Typesetting and punctuation: spacing, dashes, quotes, commas
+        for finding in rule_findings {
+            let Some(severity) = severity_of(finding.code) else {
+                continue;
+            };
+            // The characters around the spaces may belong to markup, like the colons of
+            // Org tags
+            let (start, end) = (finding.start, finding.end);
+            if !doc.is_checked(&regions, start) || in_markup(start.saturating_sub(1), end + 1) {
+                continue;
+            }
+            let range = word_range(finding.start, finding.end);
+            let mut diagnostic = rule_diagnostic(range, severity, finding.code, finding.message);
+            // Hanging conjunctions are bound by their own bulk action, not fix-all spelling
+            if finding.code != diagnostics::HANGING_CONJUNCTION {
+                let suggestions = vec![finding.replacement];
+                diagnostic.data = Some(DiagnosticData { suggestions }.to_value());
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        // One snapshot and one batched lookup for the whole document
+        let dictionary = self.dictionary.load();
+        let (words, spans): (Vec<Vec<char>>, Vec<(usize, usize)>) = words
+            .into_iter()
+            .filter_map(|(word, start, end)| {
+                let (start, end) = outside_markup(start, end)?;
+                let word = if end - start < word.len() {
+                    source[start..end].to_vec()
+                } else {
+                    word
+                };
+                if !word.iter().any(|c| c.is_numeric()) {
+                    return Some((word, (start, end)));
+                }
+                // Numbers and words mixing digits and letters ("MP3") are skipped, or only
+                // their letters are checked
+                if alphanumeric_words == AlphanumericWords::Skip {
+                    return None;
+                }
+                let (core_start, core_end) = alphabetic_core(&word)?;
+                let core = word[core_start..core_end].to_vec();
+                Some((core, (start + core_start, start + core_end)))
+            })
+            .unzip();
+        let mut known = dictionary.contains_many(&words);
+        for (known, word) in known.iter_mut().zip(&words) {
+            if !*known && word.iter().any(|&c| c == '-' || apostrophe::is_apostrophe(c)) {
+                *known = dictionary.contains_compound(word);
+            }
+        }
+
+        // Unknown identifiers ("mojaFunkcjaPomocnicza") are checked by their parts, leaving
+        // out parts too short to tell
+        let mut checked = (Vec::new(), Vec::new(), Vec::new());
+        for ((word, (start, end)), known) in words.into_iter().zip(spans).zip(known) {
+            let parts = camel_case_parts(&word);
+            if known || parts.len() < 2 {
+                checked.0.push(word);
+                checked.1.push((start, end));
+                checked.2.push(known);
+                continue;
+            }
+            for (part_start, part_end) in parts {
+                if part_end - part_start >= MIN_IDENTIFIER_PART {
+                    let part = word[part_start..part_end].to_vec();
+                    checked.2.push(dictionary.contains(&part));
+                    checked.0.push(part);
+                    checked.1.push((start + part_start, start + part_end));
+                }
+            }
+        }
+        let (words, spans, known) = checked;
+        let total = words.len().max(1);
+
+        for (index, (word_chars, &(start_idx, end_idx))) in words.iter().zip(&spans).enumerate() {
+            if doc.is_superseded() {
+                break;
+            }
+            if let Some(progress) = &progress {
+                let percentage = (index * 100 / total) as u32;
+                if percentage >= reported + PROGRESS_STEP {
+                    progress.report(percentage).await;
+                    reported = percentage;
+                }
+            }
+            if doc.ignores(word_chars) || session_ignored.contains(&lowercase(word_chars)) {
+                continue;
+            }
+            if replaced.iter().any(|span| span.contains(&start_idx)) {
+                continue;
+            }
+
+            // Abbreviations like "prof." or "mgr" are not misspellings
+            if let Some(abbreviation) = abbreviations::find(word_chars) {
+                let has_period = source.get(end_idx) == Some(&'.');
+                if has_period || !abbreviation.needs_period {
+                    continue;
+                }
+                let code = diagnostics::MISSING_ABBREVIATION_PERIOD;
+                if rules.missing_abbreviation_period
+                    && doc.is_checked(&regions, start_idx)
+                    && !known[index]
+                {
+                    if let Some(severity) = severity_of(code) {
+                        let word: String = word_chars.iter().collect();
+                        diagnostics.push(spelling_diagnostic(
+                            word_range(start_idx, end_idx),
+                            severity,
+                            code,
+                            format!("Missing period after abbreviation: '{}'", word),
+                            &word,
+                        ));
+                        continue;
+                    }
+                }
+            }
+
+            // Roman numerals ("XXI wiek") and units ("230 kV") are symbols, not words
+            if symbols::is_symbol(word_chars) {
+                continue;
+            }
+
+            // Skip short words - too many false positives
+            if !rules.unknown_words || word_chars.len() < min_word_length {
+                continue;
+            }
+
+            // Skip text detected as another language in scratch buffers
+            if !doc.is_checked(&regions, start_idx) {
+                continue;
+            }
+
+            if !known[index] {
+                let word: String = word_chars.iter().collect();
+                let suggestions = suggestion_cache
+                    .entry(lowercase(word_chars))
+                    .or_insert_with(|| suggest(word_chars, &dictionary, &scoring, max_suggestions))
+                    .clone();
+
+                let (code, message) = match suggestions.first() {
+                    Some(suggestion) if diagnostics::restores_diacritics(&word, suggestion) => (
+                        diagnostics::MISSING_DIACRITICS,
+                        format!(
+                            "Missing diacritics: '{}' (did you mean '{}'?)",
+                            word, suggestion
+                        ),
+                    ),
+                    _ => (
+                        diagnostics::UNKNOWN_WORD,
+                        format!("Unknown word: '{}'", word),
+                    ),
+                };
+                let Some(severity) = severity_of(code) else {
+                    continue;
+                };
+                let range = word_range(start_idx, end_idx);
+                let mut diagnostic = spelling_diagnostic(range, severity, code, message, &word);
+                diagnostic.data = Some(DiagnosticData { suggestions }.to_value());
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        if let Some(progress) = progress {
+            progress.finish().await;
+        }
+        diagnostics::dedup(diagnostics)
+    }
+}
+
+/// What `main` needs from the server after it stops serving: whether the session
+/// ended properly and the state `shutdown` would have saved.
+#[derive(Clone)]
+pub struct Session {
+    shutdown_received: Arc<AtomicBool>,
+    usage: Arc<Mutex<UsageStats>>,
+}
+
+impl Session {
+    /// Exit code for a client that went away: LSP asks for 1 unless `shutdown` came first.
+    pub fn exit_code(&self) -> ExitCode {
+        if self.shutdown_received.load(Ordering::Relaxed) {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
+    /// Save what `shutdown` saves, for a server stopped by a signal instead.
+    pub async fn flush(&self) {
+        self.usage.lock().await.save();
+    }
 }
 
 /// LSP Backend implementation.
 pub struct Backend {
     client: Client,
-    documents: Mutex<HashMap<Uri, DocumentState>>,
-    dictionary: Arc<Mutex<SimpleDictionary>>,
+    /// Open documents, sharded so requests on different files don't wait for each other.
+    documents: Arc<DashMap<Uri, Arc<DocumentState>>>,
+    dictionary: SharedDictionary,
+    /// Where the baseline word list comes from, kept for reloading.
+    dictionary_options: DictionaryOptions,
+    checker: SpellChecker,
+    /// Documents waiting for a background check: bulk opens, and edits once typing pauses.
+    open_queue: Arc<DiagnosticsQueue<Arc<DocumentState>>>,
+    /// Merges and rate-limits the diagnostics pushed for each document.
+    publisher: DiagnosticsPublisher<(Vec<Diagnostic>, Option<i32>)>,
+    /// Delays checks of edited documents until typing pauses.
+    debouncer: Debouncer,
+    completion_budget: CompletionBudget,
+    settings: Arc<RwLock<Settings>>,
+    /// Lemmas and parts of speech for hover; read-only after startup.
+    morphology: Morphology,
+    /// Optional PL→EN translations for hover; empty unless the user provides a glossary.
+    glossary: Glossary,
+    /// Optional bigram counts ranking completions by the preceding word.
+    bigrams: Bigrams,
+    /// Abbreviations offered as completions expanding them.
+    expansions: Expansions,
+    /// How often the user accepted each completion, persisted across sessions.
+    usage: Arc<Mutex<UsageStats>>,
+    /// The client sent `shutdown`, so a following `exit` ends the session normally.
+    shutdown_received: Arc<AtomicBool>,
+    /// Client requests diagnostics itself (textDocument/diagnostic) and can be asked to
+    /// re-request them (workspace/diagnostic/refresh), so we don't push them.
+    pull_diagnostics: AtomicBool,
+    /// Client shows completion label details (edit distance, source) beside the label.
+    label_details: AtomicBool,
+    /// Checking turned off for every document with `polski-ls.toggleGlobal`.
+    disabled: AtomicBool,
+    /// Patterns from the workspace `.polski-ls-ignore`, loaded on initialize.
+    ignore_file: Mutex<Option<IgnoreFile>>,
+    /// Raw settings from the client and `.polski-ls.toml`, merged into `settings`.
+    settings_layers: Mutex<SettingsLayers>,
+    /// Include/exclude patterns from the `files` settings.
+    file_filter: Mutex<Option<FileFilter>>,
+    /// Documents the user was told are too long for automatic checks.
+    too_long_reported: Mutex<HashSet<Uri>>,
+    /// Set when the client lets us register file watchers; true if it also understands
+    /// relative glob patterns.
+    file_watching: OnceLock<bool>,
+    /// Native watcher on `slownik.txt`, used when the client can't watch files for us.
+    user_dictionary_watcher: Mutex<Option<RecommendedWatcher>>,
+    /// First workspace folder, if the client opened one.
+    workspace_root: OnceLock<PathBuf>,
+    /// Column unit negotiated with the client on initialize.
+    position_encoding: OnceLock<PositionEncoding>,
 }
 
 impl Backend {
-    pub fn new(client: Client) -> Self {
+    pub fn new(client: Client, options: &DictionaryOptions) -> Self {
+        // Building the dictionary takes a while; do it in the background so initialize
+        // is answered right away. Checks wait for it in `SpellChecker::check`.
+        let dictionary = SharedDictionary::loading();
+        {
+            let dictionary = dictionary.clone();
+            let options = options.clone();
+            tokio::task::spawn_blocking(move || {
+                dictionary.finish_loading(SimpleDictionary::with_user_extensions(&options));
+                eprintln!(
+                    "[POLSKI-LS] Dictionary loaded: {} words",
+                    dictionary.load().word_count()
+                );
+            });
+        }
+        let settings = Arc::new(RwLock::new(Settings::default()));
+        let checker = SpellChecker {
+            dictionary: dictionary.clone(),
+            settings: Arc::clone(&settings),
+            stats: Arc::new(Mutex::new(CheckStats::default())),
+            session_ignored: Arc::new(RwLock::new(HashSet::new())),
+            replacements: Arc::new(Replacements::load()),
+            rules: Arc::new(Rules::load()),
+            progress: ProgressReporter::new(client.clone()),
+        };
+        let publisher = {
+            let client = client.clone();
+            DiagnosticsPublisher::new(PUBLISH_INTERVAL, move |uri, (diagnostics, version)| {
+                let client = client.clone();
+                Box::pin(async move {
+                    client.publish_diagnostics(uri, diagnostics, version).await;
+                })
+            })
+        };
+        let open_queue = {
+            let client = client.clone();
+            let checker = checker.clone();
+            let publisher = publisher.clone();
+            DiagnosticsQueue::new(BACKGROUND_CHECKS, move |uri, doc: Arc<DocumentState>| {
+                let client = client.clone();
+                let checker = checker.clone();
+                let publisher = publisher.clone();
+                Box::pin(async move {
+                    let diagnostics = checker.check(&doc).await;
+                    if doc.is_superseded() {
+                        eprintln!("[POLSKI-LS] Dropping stale diagnostics for {:?}", uri);
+                        return;
+                    }
+                    eprintln!(
+                        "[POLSKI-LS] Publishing {} queued diagnostics for {:?}",
+                        diagnostics.len(),
+                        uri
+                    );
+                    let flagged = diagnostics.len();
+                    publisher.publish(uri.clone(), (diagnostics, doc.version));
+                    checker.record(&client, &uri, flagged).await;
+                })
+            })
+        };
+
         Self {
             client,
-            documents: Mutex::new(HashMap::new()),
-            dictionary: Arc::new(Mutex::new(SimpleDictionary::with_user_extensions())),
+            documents: Arc::new(DashMap::new()),
+            dictionary,
+            dictionary_options: options.clone(),
+            checker,
+            open_queue: Arc::new(open_queue),
+            publisher,
+            debouncer: Debouncer::default(),
+            completion_budget: CompletionBudget::default(),
+            settings,
+            morphology: Morphology::load(options),
+            glossary: Glossary::load(),
+            bigrams: Bigrams::load(),
+            expansions: Expansions::load(),
+            usage: Arc::new(Mutex::new(UsageStats::load())),
+            shutdown_received: Arc::new(AtomicBool::new(false)),
+            pull_diagnostics: AtomicBool::new(false),
+            label_details: AtomicBool::new(false),
+            disabled: AtomicBool::new(false),
+            ignore_file: Mutex::new(None),
+            settings_layers: Mutex::new(SettingsLayers::default()),
+            file_filter: Mutex::new(None),
+            too_long_reported: Mutex::new(HashSet::new()),
+            file_watching: OnceLock::new(),
+            user_dictionary_watcher: Mutex::new(None),
+            workspace_root: OnceLock::new(),
+            position_encoding: OnceLock::new(),
+        }
+    }
+
+    /// Handle for finishing the session once the server stops serving.
+    pub fn session(&self) -> Session {
+        Session {
+            shutdown_received: Arc::clone(&self.shutdown_received),
+            usage: Arc::clone(&self.usage),
         }
     }
 
+    /// The current text of an open document. The map guard is released right away, so
+    /// callers can hold on to the document across awaits.
+    fn document(&self, uri: &Uri) -> JsonResult<Arc<DocumentState>> {
+        self.documents
+            .get(uri)
+            .map(|doc_state| Arc::clone(&doc_state))
+            .ok_or_else(|| document_not_open(uri))
+    }
+
     /// Generate completions for the given position.
     async fn generate_completions(
         &self,
         uri: &Uri,
         position: Position,
     ) -> JsonResult<Vec<CompletionItem>> {
-        let documents = self.documents.lock().await;
-
-        let Some(doc_state) = documents.get(uri) else {
+        let Some(context) = self.completion_context(uri, position).await? else {
             return Ok(Vec::new());
         };
 
+        let limits = self.completion_budget.limits();
+        let scored = self
+            .rank_completions(&context, context.max_edit_distance, limits.candidate_cap)
+            .await;
+        Ok(completion_items(
+            &context,
+            scored.into_iter().take(limits.max_results),
+            0,
+        ))
+    }
+
+    /// Send completions for the given position as partial results: the closest matches
+    /// first, then the wider fuzzy search. Returns how many items were sent.
+    async fn stream_completions(
+        &self,
+        uri: &Uri,
+        position: Position,
+        token: &ProgressToken,
+    ) -> JsonResult<usize> {
+        let Some(context) = self.completion_context(uri, position).await? else {
+            return Ok(0);
+        };
+
+        let limits = self.completion_budget.limits();
+        let max_edit_distance = context.max_edit_distance;
+        let close = self
+            .rank_completions(&context, max_edit_distance.saturating_sub(1), limits.candidate_cap)
+            .await;
+        let items = completion_items(&context, close.into_iter().take(limits.max_results), 0);
+        let sent: HashSet<String> = items.iter().map(|item| item.label.clone()).collect();
+        // The first batch is a list so the client knows to ask again as the prefix grows
+        let list = CompletionList {
+            is_incomplete: true,
+            items,
+        };
+        send_partial_result(&self.client, token, list).await;
+
+        let rest = self
+            .rank_completions(&context, max_edit_distance, limits.candidate_cap)
+            .await
+            .into_iter()
+            .filter(|(word, _, _)| !sent.contains(word))
+            .take(limits.max_results.saturating_sub(sent.len()));
+        let items = completion_items(&context, rest, sent.len());
+        let count = sent.len() + items.len();
+        if !items.is_empty() {
+            send_partial_result(&self.client, token, items).await;
+        }
+        Ok(count)
+    }
+
+    /// Find the word being typed at `position`, or `None` where nothing should be
+    /// completed.
+    async fn completion_context(
+        &self,
+        uri: &Uri,
+        position: Position,
+    ) -> JsonResult<Option<CompletionContext>> {
+        let doc_state = self.document(uri)?;
+
         let source = &doc_state.source;
         let line_index = &doc_state.line_index;
 
         // Check for race condition (completion before didChange)
         if line_index.is_position_out_of_bounds(source, position) {
-            return Ok(Vec::new());
+            return Ok(None);
         }
 
         // Convert position to character index
         let cursor_idx = line_index.position_to_index(source, position);
 
+        // Don't complete Polish words inside foreign text in scratch buffers
+        if !doc_state.is_checked(&doc_state.polish_regions(), cursor_idx.saturating_sub(1)) {
+            return Ok(None);
+        }
+
         // Find word start by scanning backward
         let mut word_start = cursor_idx;
         while word_start > 0 {
@@ -77,565 +886,3767 @@ impl Backend {
         // Extract prefix
         let prefix: Vec<char> = source[word_start..cursor_idx].to_vec();
 
-        let prefix_string: String = prefix.iter().collect();
+        // Text typed since each of the preceding words, for phrases ("w związku z t")
+        let phrase_prefixes: Vec<(Vec<char>, Range)> = if prefix.is_empty() {
+            Vec::new()
+        } else {
+            phrase_starts(source, word_start)
+                .into_iter()
+                .map(|start| {
+                    let range = Range {
+                        start: line_index.index_to_position(source, start),
+                        end: position,
+                    };
+                    (source[start..cursor_idx].to_vec(), range)
+                })
+                .collect()
+        };
+
+        // Abbreviations may contain periods ("m.in") and be completed after them ("np.")
+        let mut abbreviation_start = word_start;
+        while abbreviation_start > 0
+            && matches!(source[abbreviation_start - 1], ch if is_word_char(ch) || ch == '.')
+        {
+            abbreviation_start -= 1;
+        }
+        let abbreviation_prefix = source[abbreviation_start..cursor_idx].to_vec();
 
         // For very short prefixes, just check if we should offer "add to dictionary"
-        if prefix.len() < 2 {
+        let (min_prefix, max_edit_distance) = {
+            let settings = &self.settings.read().await.completion;
+            (settings.min_prefix_length, settings.max_edit_distance)
+        };
+        if prefix.len() < min_prefix
+            && phrase_prefixes.is_empty()
+            && abbreviation_prefix.len() < min_prefix
+        {
             eprintln!("[POLSKI-LS] prefix too short: {} chars", prefix.len());
-            return Ok(Vec::new());
+            return Ok(None);
         }
 
+        let prefix_string: String = prefix.iter().collect();
         eprintln!("[POLSKI-LS] looking up prefix: '{}'", prefix_string);
 
-        // Get fuzzy matches from dictionary
-        let max_edit_distance = if prefix.len() <= 3 { 1 } else { 2 };
-        let dictionary = self.dictionary.lock().await;
-        let fuzzy_matches = dictionary.fuzzy_match(&prefix, max_edit_distance, 200);
-        drop(dictionary);
-
-        // Score and sort matches
-        let mut scored: Vec<(String, f32)> = fuzzy_matches
-            .into_iter()
-            .map(|m| {
-                let word_str: String = m.word.iter().collect();
-                let word = apply_capitalization(&prefix, &word_str);
-                let score = calculate_completion_score(&prefix, &m.word, m.edit_distance, m.is_common);
-                (word, score)
-            })
-            .collect();
+        let (document_words, open_document_words) = if prefix.len() < min_prefix {
+            (Vec::new(), Vec::new())
+        } else {
+            (
+                document_words(source, &prefix, word_start),
+                self.open_document_words(uri, &prefix),
+            )
+        };
 
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        // Longer prefixes tolerate more typos
+        let tolerated = if prefix.len() <= 3 { 1 } else { 2 };
+        let max_edit_distance = max_edit_distance.min(tolerated);
 
         // Calculate word start position for text_edit
         let word_start_position = line_index.index_to_position(source, word_start);
+        Ok(Some(CompletionContext {
+            document_words,
+            open_document_words,
+            phrase_prefixes,
+            abbreviation_prefix: (
+                abbreviation_prefix,
+                Range {
+                    start: line_index.index_to_position(source, abbreviation_start),
+                    end: position,
+                },
+            ),
+            max_edit_distance,
+            min_prefix,
+            label_details: self.label_details.load(Ordering::Relaxed),
+            prefix,
+            previous_word: previous_word(source, word_start),
+            range: Range {
+                start: word_start_position,
+                end: position,
+            },
+        }))
+    }
 
-        // Convert to CompletionItems
-        let items: Vec<CompletionItem> = scored
-            .into_iter()
-            .take(50)
-            .enumerate()
-            .map(|(idx, (word, _score))| CompletionItem {
-                label: word.clone(),
-                kind: Some(CompletionItemKind::TEXT),
-                detail: Some("Polish".to_string()),
-                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                    range: Range {
-                        start: word_start_position,
-                        end: position,
-                    },
-                    new_text: word,
-                })),
-                filter_text: Some(prefix_string.clone()),
-                sort_text: Some(format!("{:05}", idx + 1)),
-                ..Default::default()
-            })
+    /// Words of the open documents other than `uri` that start with `prefix` and are
+    /// longer than it, ignoring case, with how often each occurs in them.
+    fn open_document_words(&self, uri: &Uri, prefix: &[char]) -> Vec<(String, usize)> {
+        // Release the map guards before indexing, which takes a while for long texts
+        let others: Vec<Arc<DocumentState>> = self
+            .documents
+            .iter()
+            .filter(|entry| entry.key() != uri)
+            .map(|entry| Arc::clone(entry.value()))
             .collect();
 
-        Ok(items)
+        let key = lowercase(prefix);
+        let mut words: BTreeMap<&str, (&str, usize)> = BTreeMap::new();
+        for doc in &others {
+            let matching = doc
+                .word_index()
+                .range(key.clone()..)
+                .take_while(|(lower, _)| lower.starts_with(&key))
+                .filter(|(lower, _)| lower.len() > key.len());
+            for (lower, (word, count)) in matching {
+                words.entry(lower).or_insert((word, 0)).1 += count;
+            }
+        }
+        words
+            .into_values()
+            .map(|(word, count)| (word.to_string(), count))
+            .collect()
     }
 
-    /// Check spelling and publish diagnostics for unknown words.
-    async fn publish_diagnostics(&self, uri: &Uri, source: &[char], line_index: &LineIndex) {
-        let words = extract_words(source);
-        let mut diagnostics = Vec::new();
+    /// Dictionary words starting with the typed prefix, plus those within
+    /// `max_edit_distance` of it when there are few, other words of the open documents
+    /// starting with it, and dictionary phrases starting with the text typed since one of
+    /// the preceding words, best first, with the data completionItem/resolve needs.
+    async fn rank_completions(
+        &self,
+        context: &CompletionContext,
+        max_edit_distance: u8,
+        candidate_cap: usize,
+    ) -> Vec<(String, f32, serde_json::Value)> {
+        let prefix = &context.prefix;
 
-        for (word_chars, start_idx, end_idx) in words {
-            // Skip short words (1-2 chars) - too many false positives
-            if word_chars.len() < 3 {
-                continue;
+        // Exact prefix hits come from the sorted index; fuzzy matching is the fallback
+        let weights = self.settings.read().await.scoring;
+        let dictionary = self.dictionary.load();
+        let usage = self.usage.lock().await;
+        let mut matches = Vec::new();
+        // Only phrases are completed after a single letter ("w związku z t")
+        if prefix.len() >= context.min_prefix {
+            matches = dictionary.prefix_match(prefix, candidate_cap);
+            // Words the prefix was typed for without diacritics ("zolt" for "żółty") rank
+            // like exact prefix hits
+            let mut found: HashSet<Vec<char>> = matches.iter().map(|m| m.word.clone()).collect();
+            let mut restored = dictionary.diacritic_prefix_match(prefix, candidate_cap);
+            restored.retain(|m| found.insert(m.word.clone()));
+            matches.append(&mut restored);
+            if matches.len() < MIN_PREFIX_HITS {
+                let mut fuzzy_matches =
+                    dictionary.fuzzy_match(prefix, max_edit_distance, candidate_cap);
+                fuzzy_matches.retain(|m| !found.contains(&m.word));
+                matches.append(&mut fuzzy_matches);
             }
+        }
+
+        // Bonuses for what the user writes: after the preceding word, and picked before
+        let learned_score = |key: &str| {
+            let affinity = context
+                .previous_word
+                .as_ref()
+                .map_or(0.0, |previous| self.bigrams.affinity(previous, key));
+            weights.context_bonus * affinity + weights.usage_bonus * usage.weight(key)
+        };
+        let mut known: HashSet<String> = matches.iter().map(|m| lowercase(&m.word)).collect();
+
+        // Score and sort matches
+        let mut scored: Vec<(String, f32, serde_json::Value)> = matches
+            .into_iter()
+            .map(|m| {
+                let word_str: String = m.word.iter().collect();
+                let word = apply_capitalization(prefix, &word_str);
+                let score = calculate_completion_score(
+                    prefix,
+                    &m.word,
+                    m.edit_distance,
+                    frequency_weight(&m),
+                    &weights,
+                ) + learned_score(&lowercase(&m.word));
+                let origin = match dictionary.word_info(&m.word) {
+                    Some(WordInfo { is_user: true, .. }) => Some("user"),
+                    Some(WordInfo { is_project: true, .. }) => Some("project"),
+                    _ => None,
+                };
+                // Everything completionItem/resolve needs, so it doesn't query the dictionary
+                let data = serde_json::json!({
+                    "word": word_str,
+                    "editDistance": m.edit_distance,
+                    "common": m.is_common,
+                    "rank": m.rank,
+                    "dictionary": origin,
+                });
+                (word, score, data)
+            })
+            .collect();
 
-            // Skip words that are all digits
-            if word_chars.iter().all(|c| c.is_ascii_digit()) {
+        // Names and jargon used elsewhere in this or another open document, which the
+        // dictionary lacks; a word in both documents is offered from this one
+        let this_document = context
+            .document_words
+            .iter()
+            .map(|entry| (entry, DOCUMENT_SOURCE, weights.document_bonus));
+        let open_documents = context
+            .open_document_words
+            .iter()
+            .map(|entry| (entry, OPEN_DOCUMENTS_SOURCE, weights.open_documents_bonus));
+        for ((word, count), source, bonus) in this_document.chain(open_documents) {
+            let chars: Vec<char> = word.chars().collect();
+            let key = lowercase(&chars);
+            if !known.insert(key.clone()) {
                 continue;
             }
+            let score = calculate_completion_score(prefix, &chars, 0, 0.0, &weights)
+                + bonus
+                + learned_score(&key);
+            let data = serde_json::json!({
+                "word": word,
+                "editDistance": 0,
+                "source": source,
+                "occurrences": count,
+            });
+            scored.push((apply_capitalization(prefix, word), score, data));
+        }
 
-            let dictionary = self.dictionary.lock().await;
-            if !dictionary.contains(&word_chars) {
-                let word: String = word_chars.iter().collect();
-                let start_pos = line_index.index_to_position(source, start_idx);
-                let end_pos = line_index.index_to_position(source, end_idx);
+        // Abbreviations expand to what they stand for; they are as common as words get
+        let (typed, _) = &context.abbreviation_prefix;
+        if typed.len() >= context.min_prefix {
+            for (abbreviation, expansion) in self.expansions.matching(typed) {
+                let chars: Vec<char> = abbreviation.chars().collect();
+                let score = calculate_completion_score(typed, &chars, 0, 1.0, &weights)
+                    + learned_score(&lowercase(&chars));
+                let data = serde_json::json!({
+                    "word": abbreviation,
+                    "editDistance": 0,
+                    "source": ABBREVIATION_SOURCE,
+                    "expansion": expansion,
+                });
+                scored.push((abbreviation.to_string(), score, data));
+            }
+        }
 
-                diagnostics.push(Diagnostic {
-                    range: Range {
-                        start: start_pos,
-                        end: end_pos,
-                    },
-                    severity: Some(DiagnosticSeverity::HINT),
-                    source: Some("polski-ls".to_string()),
-                    message: format!("Unknown word: '{}'", word),
-                    ..Default::default()
+        // Phrases replace everything typed since their first word, so the longer the
+        // typed text they continue, the better they score
+        for (idx, (typed, _)) in context.phrase_prefixes.iter().enumerate() {
+            for m in dictionary.phrase_match(typed, candidate_cap) {
+                let phrase: String = m.word.iter().collect();
+                let score =
+                    calculate_completion_score(typed, &m.word, 0, frequency_weight(&m), &weights);
+                let data = serde_json::json!({
+                    "word": phrase,
+                    "editDistance": 0,
+                    "common": m.is_common,
+                    "rank": m.rank,
+                    "source": PHRASE_SOURCE,
+                    "phrasePrefix": idx,
                 });
+                scored.push((apply_capitalization(typed, &phrase), score, data));
             }
         }
 
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored
+    }
+
+    /// Check spelling and publish diagnostics for unknown words.
+    /// Clients using pull diagnostics ask for them instead, so nothing is pushed to them.
+    async fn publish_diagnostics(&self, uri: &Uri, doc: &DocumentState) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) || self.is_skipped(uri, doc).await {
+            return;
+        }
+
+        let diagnostics = self.checker.check(doc).await;
+        if doc.is_superseded() {
+            eprintln!("[POLSKI-LS] Dropping stale diagnostics for {:?}", uri);
+            return;
+        }
+
         eprintln!(
             "[POLSKI-LS] Publishing {} diagnostics for {:?}",
             diagnostics.len(),
             uri
         );
-        self.client.publish_diagnostics(uri.clone(), diagnostics, None).await;
+        let flagged = diagnostics.len();
+        self.publisher
+            .publish(uri.clone(), (diagnostics, doc.version));
+        self.checker.record(&self.client, uri, flagged).await;
     }
-}
 
-/// Extract words from source text with their start and end indices.
-fn extract_words(source: &[char]) -> Vec<(Vec<char>, usize, usize)> {
-    let mut words = Vec::new();
-    let mut i = 0;
+    /// Whether automatic checks skip `doc` because it is ignored, too long or waiting for
+    /// an on-demand check. Results published before it became skipped are cleared.
+    async fn is_skipped(&self, uri: &Uri, doc: &DocumentState) -> bool {
+        if !self.is_ignored(uri, doc).await
+            && !self.is_too_long(uri, doc).await
+            && !self.awaits_request(doc).await
+        {
+            return false;
+        }
+
+        // The file may have been excluded, or grown, since it was last checked
+        if self.checker.stats.lock().await.contains(uri) {
+            self.publisher.publish(uri.clone(), (vec![], None));
+            self.checker.forget(&self.client, uri).await;
+        }
+        true
+    }
+
+    /// Re-check an edited document once typing pauses for `diagnostics.debounceMs`, on
+    /// the background queue so the handler returns immediately.
+    async fn schedule_diagnostics(&self, uri: &Uri, doc: &Arc<DocumentState>) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) || self.is_skipped(uri, doc).await {
+            return;
+        }
+
+        let delay = self.settings.read().await.diagnostics.debounce();
+        if delay.is_zero() {
+            self.open_queue.push(uri.clone(), doc.clone());
+            return;
+        }
+        let open_queue = Arc::clone(&self.open_queue);
+        let doc = Arc::clone(doc);
+        self.debouncer
+            .schedule(uri.clone(), delay, move |uri| open_queue.push(uri, doc));
+    }
+
+    /// Queue the first check of a newly opened document instead of running it inline,
+    /// so opening many files at once doesn't block the handler on each of them.
+    async fn queue_diagnostics(&self, uri: &Uri, doc: &Arc<DocumentState>) {
+        if self.pull_diagnostics.load(Ordering::Relaxed)
+            || self.is_ignored(uri, doc).await
+            || self.is_too_long(uri, doc).await
+            || self.awaits_request(doc).await
+        {
+            return;
+        }
+
+        self.open_queue.push(uri.clone(), doc.clone());
+        eprintln!(
+            "[POLSKI-LS] {} document(s) waiting for diagnostics",
+            self.open_queue.len()
+        );
+    }
+
+    /// Handle the add-to-dictionary commands: persist the word in the user or project
+    /// dictionary and refresh diagnostics.
+    async fn add_to_dictionary(
+        &self,
+        arguments: &[serde_json::Value],
+        project: bool,
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let word = string_argument(arguments, "word")?;
+        let uri = string_argument(arguments, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| JsonError::invalid_params(format!("Invalid 'uri' argument: {}", e)))?;
+
+        // Words added to the stand-in would be lost once loading finishes
+        self.dictionary.loaded().await;
+        let target = if project { "project dictionary" } else { "dictionary" };
+        eprintln!("[POLSKI-LS] Adding word to {}: '{}'", target, word);
+
+        // Add word to dictionary
+        let added = self.dictionary.update(|dictionary| {
+            if project {
+                dictionary.add_project_word(word)
+            } else {
+                dictionary.add_user_word(word)
+            }
+        });
+        if let Err(e) = added {
+            eprintln!("[POLSKI-LS] Error adding word to dictionary: {}", e);
+            let message = format!("Failed to add word to {}: {}", target, e);
+            self.client
+                .show_message(MessageType::ERROR, message.clone())
+                .await;
+            return Err(JsonError {
+                code: ErrorCode::InternalError,
+                message: message.into(),
+                data: None,
+            });
+        }
+
+        // Show success message
+        self.client
+            .show_message(MessageType::INFO, format!("Added '{}' to {}", word, target))
+            .await;
+
+        // Refresh diagnostics for the document
+        self.refresh_diagnostics(&uri).await;
+
+        Ok(None)
+    }
+
+    /// Handle the ignore-in-file command: stop flagging the word in one open document.
+    async fn ignore_in_file(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let word = string_argument(arguments, "word")?;
+        let uri = string_argument(arguments, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| JsonError::invalid_params(format!("Invalid 'uri' argument: {}", e)))?;
+
+        let Some(mut doc_state) = self.documents.get_mut(&uri) else {
+            return Err(document_not_open(&uri));
+        };
+        Arc::make_mut(&mut doc_state)
+            .ignored_words
+            .insert(word.to_lowercase());
+        drop(doc_state);
+
+        eprintln!("[POLSKI-LS] Ignoring '{}' in {:?}", word, uri);
+        self.refresh_diagnostics(&uri).await;
+
+        Ok(None)
+    }
+
+    /// Handle the toggle command: turn checking of one open document off, clearing its
+    /// diagnostics, or back on.
+    async fn toggle(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let uri = string_argument(arguments, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| JsonError::invalid_params(format!("Invalid 'uri' argument: {}", e)))?;
+
+        let Some(mut doc_state) = self.documents.get_mut(&uri) else {
+            return Err(document_not_open(&uri));
+        };
+        let disabled = &mut Arc::make_mut(&mut doc_state).disabled;
+        *disabled = !*disabled;
+        let enabled = !*disabled;
+        drop(doc_state);
+
+        eprintln!("[POLSKI-LS] Checking {} for {:?}", on_off(enabled), uri);
+        let message = format!("Spell checking {} for this document", on_off(enabled));
+        self.client.show_message(MessageType::INFO, message).await;
+        self.refresh_diagnostics(&uri).await;
+        Ok(Some(serde_json::json!({ "enabled": enabled })))
+    }
+
+    /// Handle the check command: check an open document now, even in on-demand mode or
+    /// over the length limit.
+    async fn check_document(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let uri = string_argument(arguments, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| JsonError::invalid_params(format!("Invalid 'uri' argument: {}", e)))?;
+
+        let Some(mut doc_state) = self.documents.get_mut(&uri) else {
+            return Err(document_not_open(&uri));
+        };
+        Arc::make_mut(&mut doc_state).check_requested = true;
+        drop(doc_state);
+
+        eprintln!("[POLSKI-LS] Checking {:?} on request", uri);
+        self.refresh_diagnostics(&uri).await;
+
+        Ok(None)
+    }
+
+    /// Handle the global toggle command: turn checking of every document off, clearing
+    /// their diagnostics, or back on.
+    async fn toggle_global(&self) -> JsonResult<Option<serde_json::Value>> {
+        let enabled = self.disabled.fetch_xor(true, Ordering::Relaxed);
+        eprintln!("[POLSKI-LS] Checking {} for all documents", on_off(enabled));
+        let message = format!("Spell checking {}", on_off(enabled));
+        self.client.show_message(MessageType::INFO, message).await;
+        self.refresh_all_diagnostics().await;
+        Ok(Some(serde_json::json!({ "enabled": enabled })))
+    }
+
+    /// Handle the ignore-for-session command: stop flagging the word in every document
+    /// without touching the user dictionary.
+    async fn ignore_for_session(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let word = string_argument(arguments, "word")?;
+        eprintln!("[POLSKI-LS] Ignoring '{}' for this session", word);
+
+        let mut session_ignored = self.checker.session_ignored.write().await;
+        session_ignored.insert(word.to_lowercase());
+        drop(session_ignored);
+
+        self.refresh_all_diagnostics().await;
+        Ok(None)
+    }
+
+    /// Handle the clear-session-ignores command: flag session-ignored words again.
+    async fn clear_session_ignores(&self) -> JsonResult<Option<serde_json::Value>> {
+        let mut session_ignored = self.checker.session_ignored.write().await;
+        let count = session_ignored.len();
+        session_ignored.clear();
+        drop(session_ignored);
+
+        eprintln!("[POLSKI-LS] Cleared {} session-ignored word(s)", count);
+        self.refresh_all_diagnostics().await;
+        Ok(None)
+    }
+
+    /// Resolve the settings again after `update` changes one of the layers, then follow
+    /// them with the dictionaries and file filter. Invalid settings leave everything as is.
+    async fn update_settings(
+        &self,
+        update: impl FnOnce(&mut SettingsLayers),
+    ) -> Result<(), serde_json::Error> {
+        let mut layers = self.settings_layers.lock().await;
+        let mut updated = layers.clone();
+        update(&mut updated);
+        let settings = updated.resolve()?;
+        *layers = updated;
+        drop(layers);
+
+        let dictionaries_changed = self.settings.read().await.dictionaries != settings.dictionaries;
+        if dictionaries_changed {
+            self.dictionary.update(|dictionary| {
+                self.load_configured_dictionaries(dictionary, &settings.dictionaries)
+            });
+        }
+        self.update_file_filter(&settings.files).await;
+        self.completion_budget.set_max_results(settings.completion.max_results);
+        *self.settings.write().await = settings;
+        if dictionaries_changed {
+            self.watch_dictionaries(true).await;
+        }
+        Ok(())
+    }
+
+    /// Read `~/.config/polski-ls/config.toml`, reporting problems to the user.
+    async fn read_user_config(&self) -> serde_json::Value {
+        match user_config_dir() {
+            Some(dir) => self.read_config_file(&dir.join(USER_CONFIG_FILE)).await,
+            None => serde_json::Value::Null,
+        }
+    }
+
+    /// Read `.polski-ls.toml` from the workspace root, reporting problems to the user.
+    async fn read_project_config(&self) -> serde_json::Value {
+        match self.workspace_root.get() {
+            Some(root) => self.read_config_file(&root.join(PROJECT_CONFIG_FILE)).await,
+            None => serde_json::Value::Null,
+        }
+    }
+
+    /// Read a settings file, returning `null` when there is no usable file.
+    async fn read_config_file(&self, path: &Path) -> serde_json::Value {
+        match read_settings_file(path) {
+            Ok(Some(value)) => {
+                eprintln!("[POLSKI-LS] Loaded config: {:?}", path);
+                value
+            }
+            Ok(None) => serde_json::Value::Null,
+            Err(e) => {
+                eprintln!("[POLSKI-LS] ERROR: {:?}: {}", path, e);
+                let message = format!("Ignoring {}: {}", path.display(), e);
+                self.client.show_message(MessageType::WARNING, message).await;
+                serde_json::Value::Null
+            }
+        }
+    }
+
+    /// Handle the reloadConfig command: re-read the user and project settings files,
+    /// then re-check every open document.
+    async fn reload_config(&self) -> JsonResult<Option<serde_json::Value>> {
+        let user = self.read_user_config().await;
+        let project = self.read_project_config().await;
+        let updated = self
+            .update_settings(|layers| {
+                layers.user = user;
+                layers.project = project;
+            })
+            .await;
+        if let Err(e) = updated {
+            return Err(internal_error(&format!("Invalid polski-ls settings: {}", e)));
+        }
+
+        eprintln!("[POLSKI-LS] Reloaded configuration files");
+        self.refresh_all_diagnostics().await;
+        Ok(None)
+    }
+
+    /// Handle the completionAccepted command the client runs after inserting a completion:
+    /// count the word so it ranks higher from now on.
+    async fn completion_accepted(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let word = string_argument(arguments, "word")?;
+        self.usage.lock().await.record(word);
+        Ok(None)
+    }
+
+    /// Rebuild the include/exclude filter from the `files` settings.
+    async fn update_file_filter(&self, files: &FileSettings) {
+        let root = self.workspace_root.get().map(PathBuf::as_path);
+        let (filter, errors) = FileFilter::new(root, &files.include, &files.exclude);
+        for error in errors {
+            eprintln!("[POLSKI-LS] ERROR: files: {}", error);
+        }
+        *self.file_filter.lock().await = Some(filter);
+    }
+
+    /// Load the project and extra dictionaries named in `settings` into `dictionary`.
+    fn load_configured_dictionaries(
+        &self,
+        dictionary: &mut SimpleDictionary,
+        settings: &DictionarySettings,
+    ) {
+        let project = self
+            .workspace_root
+            .get()
+            .map(|root| root.join(&settings.project));
+        load_dictionaries(dictionary, project, &self.extra_dictionary_paths(settings));
+    }
+
+    /// Paths of the extra dictionaries; relative ones need a workspace root.
+    fn extra_dictionary_paths(&self, settings: &DictionarySettings) -> Vec<PathBuf> {
+        let root = self.workspace_root.get();
+        settings
+            .extra
+            .iter()
+            .map(PathBuf::from)
+            .filter_map(|path| match root {
+                _ if path.is_absolute() => Some(path),
+                Some(root) => Some(root.join(path)),
+                None => None,
+            })
+            .collect()
+    }
+
+    /// Every word list file named in `settings`: the project dictionary and the extras.
+    fn dictionary_paths(&self, settings: &DictionarySettings) -> Vec<PathBuf> {
+        let project = self
+            .workspace_root
+            .get()
+            .map(|root| root.join(&settings.project));
+        project
+            .into_iter()
+            .chain(self.extra_dictionary_paths(settings))
+            .collect()
+    }
+
+    /// Ask the client to report external edits to the user and configured word lists.
+    /// With `replace`, the previous registration is dropped first so renamed
+    /// dictionaries stop being watched.
+    async fn watch_dictionaries(&self, replace: bool) {
+        let Some(&relative) = self.file_watching.get() else {
+            return;
+        };
+        if replace {
+            let unregistration = Unregistration {
+                id: DICTIONARY_WATCHERS_ID.to_string(),
+                method: WATCHED_FILES_METHOD.to_string(),
+            };
+            if let Err(e) = self
+                .client
+                .unregister_capability(vec![unregistration])
+                .await
+            {
+                eprintln!("[POLSKI-LS] ERROR: Failed to drop file watchers: {}", e);
+            }
+        }
+
+        let settings = self.settings.read().await.dictionaries.clone();
+        let mut watchers = Vec::new();
+        if let Some(dir) = user_config_dir() {
+            watchers.extend(file_watcher(&dir, "*.txt", relative));
+        }
+        for path in self.dictionary_paths(&settings) {
+            let name = path.file_name().and_then(|name| name.to_str());
+            if let (Some(dir), Some(name)) = (path.parent(), name) {
+                watchers.extend(file_watcher(dir, name, relative));
+            }
+        }
+
+        eprintln!(
+            "[POLSKI-LS] Watching {} dictionary patterns",
+            watchers.len()
+        );
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        let registration = Registration {
+            id: DICTIONARY_WATCHERS_ID.to_string(),
+            method: WATCHED_FILES_METHOD.to_string(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            eprintln!("[POLSKI-LS] ERROR: Failed to register file watchers: {}", e);
+        }
+    }
+
+    /// Fallback for clients that can't watch files: watch `slownik.txt` ourselves and
+    /// merge words added to it outside the editor, then re-check open documents.
+    async fn watch_user_dictionary(&self) {
+        let dictionary = self.dictionary.clone();
+        let documents = Arc::clone(&self.documents);
+        let open_queue = Arc::clone(&self.open_queue);
+        let checker = self.checker.clone();
+        let client = self.client.clone();
+        let pull_diagnostics = self.pull_diagnostics.load(Ordering::Relaxed);
+        let runtime = tokio::runtime::Handle::current();
+
+        let on_change = move || {
+            let dictionary = dictionary.clone();
+            let documents = Arc::clone(&documents);
+            let open_queue = Arc::clone(&open_queue);
+            let checker = checker.clone();
+            let client = client.clone();
+            runtime.spawn(async move {
+                if dictionary.update(SimpleDictionary::merge_user_dictionary) == 0 {
+                    return;
+                }
+                if pull_diagnostics {
+                    if let Err(e) = client.workspace_diagnostic_refresh().await {
+                        eprintln!("[POLSKI-LS] Diagnostic refresh failed: {}", e);
+                    }
+                    checker.send_status(&client).await;
+                    return;
+                }
+
+                let documents = open_documents(&documents);
+                // Only documents with published results; ignored and too long ones
+                // were never recorded, so they stay unchecked
+                let stats = checker.stats.lock().await;
+                for (uri, doc_state) in documents {
+                    if stats.contains(&uri) {
+                        open_queue.push(uri, doc_state);
+                    }
+                }
+            });
+        };
+
+        let watcher = self.dictionary.load().watch_user_dictionary(on_change);
+        *self.user_dictionary_watcher.lock().await = watcher;
+    }
+
+    /// Handle the reloadDictionaries command: re-read the baseline, user and project word
+    /// lists from scratch, then re-check every open document.
+    async fn reload_dictionaries(&self) -> JsonResult<Option<serde_json::Value>> {
+        // Otherwise the initial load could finish afterwards and undo the reload
+        self.dictionary.loaded().await;
+        let progress = self.checker.progress.begin("Loading dictionaries").await;
+        let settings = self.settings.read().await.dictionaries.clone();
+        let project = self
+            .workspace_root
+            .get()
+            .map(|root| root.join(&settings.project));
+        let extra = self.extra_dictionary_paths(&settings);
+        let options = self.dictionary_options.clone();
+        // Reading and indexing the word lists blocks for a while, like the initial load
+        let dictionary = tokio::task::spawn_blocking(move || {
+            let mut dictionary = SimpleDictionary::with_user_extensions(&options);
+            load_dictionaries(&mut dictionary, project, &extra);
+            dictionary
+        })
+        .await
+        .map_err(|e| internal_error(&format!("Loading dictionaries failed: {}", e)))?;
+        let word_count = dictionary.word_count();
+        self.dictionary.replace(dictionary);
+        eprintln!("[POLSKI-LS] Reloaded dictionaries: {} words", word_count);
+        if let Some(progress) = progress {
+            progress
+                .finish_with_message(format!("{} words", word_count))
+                .await;
+        }
+
+        self.refresh_all_diagnostics().await;
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            // Nothing is published in pull mode, so report the new size directly
+            self.checker.send_status(&self.client).await;
+        }
+        Ok(Some(serde_json::json!({ "wordCount": word_count })))
+    }
+
+    /// Handle the openUserDictionary command: ask the client to open slownik.txt, creating
+    /// it first so there is something to show.
+    async fn open_user_dictionary(&self) -> JsonResult<Option<serde_json::Value>> {
+        let dictionary = self.dictionary.load();
+        let path = dictionary.user_dict_path().map(Path::to_path_buf);
+
+        let Some(path) = path else {
+            return Err(internal_error(
+                "User dictionary path not configured. Config directory could not be determined.",
+            ));
+        };
+        if !path.exists() {
+            let created = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::File::create(&path));
+            if let Err(e) = created {
+                return Err(internal_error(&format!("Failed to create {:?}: {}", path, e)));
+            }
+        }
+        let Some(uri) = Uri::from_file_path(&path) else {
+            return Err(internal_error(&format!("Invalid dictionary path: {:?}", path)));
+        };
+
+        eprintln!("[POLSKI-LS] Opening user dictionary: {:?}", path);
+        let params = ShowDocumentParams {
+            uri,
+            external: Some(false),
+            take_focus: Some(true),
+            selection: None,
+        };
+        let shown = self.client.show_document(params).await?;
+        Ok(Some(serde_json::Value::Bool(shown)))
+    }
+
+    /// Handle the reflowParagraph command: re-wrap the paragraph on the given line to the
+    /// configured width. The edit is applied through workspace/applyEdit and also returned.
+    async fn reflow_paragraph(
+        &self,
+        arguments: &[serde_json::Value],
+    ) -> JsonResult<Option<serde_json::Value>> {
+        let uri = string_argument(arguments, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| JsonError::invalid_params(format!("Invalid 'uri' argument: {}", e)))?;
+        let line = arguments
+            .first()
+            .and_then(|arg| arg.get("line"))
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| JsonError::invalid_params("Missing 'line' argument"))?;
+        let width = self.settings.read().await.reflow.line_width;
+
+        let doc_state = self.document(&uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, Position::new(line, 0));
+        let Some(paragraph) = paragraph_at(source, idx) else {
+            return Ok(None);
+        };
+
+        let new_text = reflow(&source[paragraph.clone()], width);
+        let old_text: String = source[paragraph.clone()].iter().collect();
+        if new_text == old_text {
+            return Ok(None);
+        }
+
+        let mut changes = HashMap::new();
+        changes.insert(
+            uri.clone(),
+            vec![TextEdit {
+                range: Range {
+                    start: line_index.index_to_position(source, paragraph.start),
+                    end: line_index.index_to_position(source, paragraph.end),
+                },
+                new_text,
+            }],
+        );
+
+        let edit = WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        };
+        if let Err(e) = self.client.apply_edit(edit.clone()).await {
+            eprintln!("[POLSKI-LS] Error applying reflow edit: {}", e);
+        }
+
+        Ok(serde_json::to_value(edit).ok())
+    }
+
+    /// Build a line index using the negotiated position encoding.
+    fn line_index(&self, source: &[char]) -> LineIndex {
+        let encoding = self.position_encoding.get().copied().unwrap_or_default();
+        LineIndex::new(source, encoding)
+    }
+
+    /// Check whether the document's language, the workspace ignore file or the `files`
+    /// settings exclude it from checking.
+    async fn is_ignored(&self, uri: &Uri, doc: &DocumentState) -> bool {
+        if doc.disabled || self.disabled.load(Ordering::Relaxed) {
+            eprintln!("[POLSKI-LS] Ignoring {:?}: checking turned off", uri);
+            return true;
+        }
+        if !self.settings.read().await.files.allows_language(&doc.language_id) {
+            eprintln!("[POLSKI-LS] Ignoring {:?}: language '{}'", uri, doc.language_id);
+            return true;
+        }
+
+        let Some(path) = uri.to_file_path() else {
+            return false;
+        };
+        let ignore_file = self.ignore_file.lock().await;
+        if ignore_file.as_ref().is_some_and(|ignore| ignore.is_ignored(&path)) {
+            eprintln!("[POLSKI-LS] Ignoring {:?} per {}", uri, crate::ignore::IGNORE_FILE_NAME);
+            return true;
+        }
+        drop(ignore_file);
+
+        let file_filter = self.file_filter.lock().await;
+        let excluded = file_filter.as_ref().is_some_and(|filter| filter.is_excluded(&path));
+        if excluded {
+            eprintln!("[POLSKI-LS] Ignoring {:?} per files settings", uri);
+        }
+        excluded
+    }
+
+    /// Check whether a document is too long to be checked automatically, telling the user
+    /// the first time. Quick fixes and fix-all still work on it.
+    async fn is_too_long(&self, uri: &Uri, doc: &DocumentState) -> bool {
+        let settings = self.settings.read().await.diagnostics;
+        if doc.check_requested || !settings.exceeds_length(doc.source.len()) {
+            return false;
+        }
+
+        if self.too_long_reported.lock().await.insert(uri.clone()) {
+            eprintln!(
+                "[POLSKI-LS] Skipping automatic checks for {:?}: {} characters",
+                uri,
+                doc.source.len()
+            );
+            let message = format!(
+                "Document too long for automatic spell checking ({} characters, limit {})",
+                doc.source.len(),
+                settings.max_document_length
+            );
+            self.client.show_message(MessageType::INFO, message).await;
+        }
+        true
+    }
+
+    /// Whether `doc` is only checked on request (`diagnostics.onDemand`) and none was made
+    /// since its last edit.
+    async fn awaits_request(&self, doc: &DocumentState) -> bool {
+        self.settings.read().await.diagnostics.on_demand && !doc.check_requested
+    }
+
+    /// Ask the client to re-pull diagnostics, or re-push them for one document.
+    async fn refresh_diagnostics(&self, uri: &Uri) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            if let Err(e) = self.client.workspace_diagnostic_refresh().await {
+                eprintln!("[POLSKI-LS] Diagnostic refresh failed: {}", e);
+            }
+            return;
+        }
+
+        if let Ok(doc_state) = self.document(uri) {
+            self.publish_diagnostics(uri, &doc_state).await;
+        }
+    }
+
+    /// Quick fixes for the word at `range`: suggestions plus "Add to dictionary".
+    async fn quick_fixes(
+        &self,
+        uri: &Uri,
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> JsonResult<Vec<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+
+        // Find the word at the cursor position
+        let start_idx = line_index.position_to_index(source, range.start);
+
+        // Find word boundaries
+        let Some((word_start, word_end)) = word_bounds(source, start_idx) else {
+            return Ok(Vec::new());
+        };
+
+        let word: Vec<char> = source[word_start..word_end].to_vec();
+        let word_string: String = word.iter().collect();
+
+        // Check if word is unknown
+        let dictionary = self.dictionary.load();
+        if dictionary.contains_compound(&word)
+            || doc_state.ignores(&word)
+            || self.checker.session_ignored.read().await.contains(&lowercase(&word))
+        {
+            return Ok(Vec::new());
+        }
+
+        let word_range = Range {
+            start: line_index.index_to_position(source, word_start),
+            end: line_index.index_to_position(source, word_end),
+        };
+
+        // Prefer the suggestions published with the diagnostic; match again only for
+        // clients that don't send it back
+        let settings = self.settings.read().await.clone();
+        let published = diagnostics
+            .iter()
+            .filter(|d| d.range == word_range && d.source.as_deref() == Some("polski-ls"))
+            .find_map(DiagnosticData::from_diagnostic);
+        let suggestions = match published {
+            Some(data) => data.suggestions,
+            None => {
+                eprintln!("[POLSKI-LS] Generating suggestions for: '{}'", word_string);
+                let max_suggestions = settings.code_actions.max_suggestions;
+                suggest(&word, &dictionary, &settings.scoring, max_suggestions)
+            }
+        };
+
+        if suggestions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut actions: Vec<CodeActionOrCommand> = Vec::new();
+
+        let mut word_commands = vec![(
+            format!("Add '{}' to dictionary", word_string),
+            CMD_ADD_TO_DICTIONARY,
+        )];
+        if self.workspace_root.get().is_some() {
+            word_commands.push((
+                format!("Add '{}' to project dictionary", word_string),
+                CMD_ADD_TO_PROJECT_DICTIONARY,
+            ));
+        }
+        word_commands.push((
+            format!("Ignore '{}' in this file", word_string),
+            CMD_IGNORE_IN_FILE,
+        ));
+        word_commands.push((
+            format!("Ignore '{}' for this session", word_string),
+            CMD_IGNORE_FOR_SESSION,
+        ));
+
+        let word_actions = word_commands.into_iter().map(|(title, command)| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: None,
+                edit: None,
+                command: Some(Command {
+                    title,
+                    command: command.to_string(),
+                    arguments: Some(vec![serde_json::json!({
+                        "word": word_string,
+                        "uri": uri.to_string()
+                    })]),
+                }),
+                ..Default::default()
+            })
+        });
+
+        for (rank, suggestion) in suggestions.iter().enumerate() {
+            let suggestion = apply_occurrence_casing(source, word_start, word_end, suggestion);
+
+            let mut changes = HashMap::new();
+            changes.insert(
+                uri.clone(),
+                vec![TextEdit {
+                    range: word_range,
+                    new_text: suggestion.clone(),
+                }],
+            );
+
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Change to '{}'", suggestion),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: None,
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    ..Default::default()
+                }),
+                is_preferred: Some(rank == 0),
+                ..Default::default()
+            }));
+        }
+
+        match settings.code_actions.add_to_dictionary {
+            MenuPlacement::Top => {
+                actions.splice(0..0, word_actions);
+            }
+            MenuPlacement::Bottom => actions.extend(word_actions),
+        }
+
+        Ok(actions)
+    }
+
+    /// Quick fixes for replacement rule matches touching `range`. Rules often match words
+    /// that are all known ("na prawdę"), which the word-based fixes leave alone.
+    async fn replacement_fixes(
+        &self,
+        uri: &Uri,
+        range: Range,
+    ) -> JsonResult<Vec<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+        if !self.settings.read().await.rules.replacements {
+            return Ok(Vec::new());
+        }
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let start_idx = line_index.position_to_index(source, range.start);
+        let end_idx = line_index.position_to_index(source, range.end);
+
+        let actions = self
+            .checker
+            .replacements
+            .find(source)
+            .into_iter()
+            .filter(|found| found.start <= end_idx && start_idx <= found.end)
+            .map(|found| {
+                let new_text =
+                    apply_occurrence_casing(source, found.start, found.end, &found.replacement);
+                let range = Range {
+                    start: line_index.index_to_position(source, found.start),
+                    end: line_index.index_to_position(source, found.end),
+                };
+                quick_fix(uri, format!("Change to '{}'", new_text), range, new_text)
+            })
+            .collect();
+        Ok(actions)
+    }
+
+    /// Quick fixes applying the corrections of each grammar rule match touching `range`.
+    async fn pattern_rule_fixes(
+        &self,
+        uri: &Uri,
+        range: Range,
+    ) -> JsonResult<Vec<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+        if !self.settings.read().await.rules.pattern_rules {
+            return Ok(Vec::new());
+        }
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let start_idx = line_index.position_to_index(source, range.start);
+        let end_idx = line_index.position_to_index(source, range.end);
+
+        let actions = self
+            .checker
+            .rules
+            .find(source)
+            .into_iter()
+            .filter(|found| found.start <= end_idx && start_idx <= found.end)
+            .flat_map(|found| {
+                let range = Range {
+                    start: line_index.index_to_position(source, found.start),
+                    end: line_index.index_to_position(source, found.end),
+                };
+                found.replacements.into_iter().map(move |replacement| {
+                    let new_text =
+                        apply_occurrence_casing(source, found.start, found.end, &replacement);
+                    quick_fix(uri, format!("Change to '{}'", new_text), range, new_text)
+                })
+            })
+            .collect();
+        Ok(actions)
+    }
+
+    /// Quick fixes deleting the duplicate of each repeated word touching `range`.
+    async fn repeated_word_fixes(
+        &self,
+        uri: &Uri,
+        range: Range,
+    ) -> JsonResult<Vec<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+        if !self.settings.read().await.rules.repeated_words {
+            return Ok(Vec::new());
+        }
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let start_idx = line_index.position_to_index(source, range.start);
+        let end_idx = line_index.position_to_index(source, range.end);
+
+        let actions = repeated_words::find(source)
+            .into_iter()
+            .filter(|repetition| repetition.start <= end_idx && start_idx <= repetition.end)
+            .map(|repetition| {
+                let range = Range {
+                    start: line_index.index_to_position(source, repetition.start),
+                    end: line_index.index_to_position(source, repetition.end),
+                };
+                let title = format!("Remove repeated '{}'", repetition.word);
+                quick_fix(uri, title, range, String::new())
+            })
+            .collect();
+        Ok(actions)
+    }
+
+    /// Quick fixes for the typography and comma problems touching `range`.
+    async fn rule_fixes(
+        &self,
+        uri: &Uri,
+        range: Range,
+    ) -> JsonResult<Vec<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+        let findings = rule_findings(&doc_state, &*self.settings.read().await);
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let start_idx = line_index.position_to_index(source, range.start);
+        let end_idx = line_index.position_to_index(source, range.end);
+
+        let actions = findings
+            .into_iter()
+            .filter(|finding| finding.start <= end_idx && start_idx <= finding.end)
+            .map(|finding| {
+                let range = Range {
+                    start: line_index.index_to_position(source, finding.start),
+                    end: line_index.index_to_position(source, finding.end),
+                };
+                let title = match finding.code {
+                    diagnostics::DOUBLE_SPACE => "Use a single space",
+                    diagnostics::SPACE_BEFORE_PUNCTUATION => "Remove the space",
+                    diagnostics::STRAIGHT_QUOTES => "Use Polish quotes",
+                    diagnostics::HYPHEN_AS_DASH | diagnostics::DOUBLE_HYPHEN => "Use a dash (–)",
+                    diagnostics::HANGING_CONJUNCTION => "Use a non-breaking space",
+                    diagnostics::MISSING_COMMA => "Add a comma",
+                    _ => "Add a space",
+                };
+                quick_fix(uri, title.to_string(), range, finding.replacement)
+            })
+            .collect();
+        Ok(actions)
+    }
+
+    /// Rewrite the number at `range` in words, e.g. "12" → "dwanaście".
+    async fn number_to_words(
+        &self,
+        uri: &Uri,
+        range: Range,
+    ) -> JsonResult<Option<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, range.start);
+        let Some((start, end, value)) = number_at(source, idx) else {
+            return Ok(None);
+        };
+
+        let words = to_words(value, case_at(source, start));
+        let new_text = apply_occurrence_casing(source, start, end, &words);
+        let mut changes = HashMap::new();
+        changes.insert(
+            uri.clone(),
+            vec![TextEdit {
+                range: Range {
+                    start: line_index.index_to_position(source, start),
+                    end: line_index.index_to_position(source, end),
+                },
+                new_text: new_text.clone(),
+            }],
+        );
+
+        Ok(Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Write out as '{}'", new_text),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })))
+    }
+
+    /// The source.fixAll action: replace every misspelling with its top suggestion.
+    async fn fix_all(&self, uri: &Uri) -> JsonResult<Option<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+
+        if self.is_ignored(uri, &doc_state).await {
+            return Ok(None);
+        }
+
+        let diagnostics = self.checker.check(&doc_state).await;
+        let edits = fix_all_edits(&doc_state, &diagnostics);
+
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        let title = format!("Fix all spelling ({} words)", edits.len());
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone(), edits);
+
+        Ok(Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::new(FIX_ALL_KIND)),
+            edit: Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })))
+    }
+
+    /// Bind every hanging conjunction reported in the document to the next word with a
+    /// non-breaking space, in one edit.
+    async fn bind_hanging_conjunctions(
+        &self,
+        uri: &Uri,
+    ) -> JsonResult<Option<CodeActionOrCommand>> {
+        let doc_state = self.document(uri)?;
+
+        if !self.settings.read().await.rules.hanging_conjunctions
+            || self.is_ignored(uri, &doc_state).await
+        {
+            return Ok(None);
+        }
+
+        let code = diagnostics::tagged_code(diagnostics::HANGING_CONJUNCTION);
+        let nbsp = doc_state.non_breaking_space();
+        let edits: Vec<TextEdit> = self
+            .checker
+            .check(&doc_state)
+            .await
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String(code.clone())))
+            .map(|d| TextEdit {
+                range: d.range,
+                new_text: nbsp.to_string(),
+            })
+            .collect();
+
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        let title = format!("Bind single-letter words ({} spaces)", edits.len());
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone(), edits);
+
+        Ok(Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::new(HANGING_CONJUNCTIONS_KIND)),
+            edit: Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })))
+    }
+
+    /// Re-check every open document, e.g. after the settings changed.
+    async fn refresh_all_diagnostics(&self) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            if let Err(e) = self.client.workspace_diagnostic_refresh().await {
+                eprintln!("[POLSKI-LS] Diagnostic refresh failed: {}", e);
+            }
+            return;
+        }
+
+        for (uri, doc_state) in open_documents(&self.documents) {
+            self.publish_diagnostics(&uri, &doc_state).await;
+        }
+    }
+}
+
+/// The word being completed: what was typed so far and the range a completion replaces.
+struct CompletionContext {
+    prefix: Vec<char>,
+    /// Most typos tolerated in the prefix.
+    max_edit_distance: u8,
+    /// Letters of a word that must be typed before single words are completed.
+    min_prefix: usize,
+    /// Whether items carry label details.
+    label_details: bool,
+    /// The word before the one being typed, lowercase, for the bigram model.
+    previous_word: Option<String>,
+    /// Other words of the document starting with the prefix, with their occurrence counts.
+    document_words: Vec<(String, usize)>,
+    /// Words of the other open documents starting with the prefix, with their counts.
+    open_document_words: Vec<(String, usize)>,
+    /// Text typed since each of the few preceding words, nearest first, with the range
+    /// a phrase continuing it replaces.
+    phrase_prefixes: Vec<(Vec<char>, Range)>,
+    /// Text typed since the start of the word, including periods ("m.in"), with its range.
+    abbreviation_prefix: (Vec<char>, Range),
+    range: Range,
+}
+
+/// The word ending before `word_start`, lowercase, if only whitespace separates them.
+fn previous_word(source: &[char], word_start: usize) -> Option<String> {
+    let end = source[..word_start]
+        .iter()
+        .rposition(|ch| !ch.is_whitespace())
+        .map_or(0, |idx| idx + 1);
+    let start = source[..end]
+        .iter()
+        .rposition(|&ch| !is_word_char(ch))
+        .map_or(0, |idx| idx + 1);
+    (start < end).then(|| source[start..end].iter().flat_map(|c| c.to_lowercase()).collect())
+}
+
+/// Starts of the up to `MAX_PHRASE_WORDS` words before `word_start` that each follow the
+/// next one after a single space, nearest first.
+fn phrase_starts(source: &[char], word_start: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut start = word_start;
+    while starts.len() < MAX_PHRASE_WORDS
+        && start >= 2
+        && source[start - 1] == ' '
+        && is_word_char(source[start - 2])
+    {
+        start -= 1;
+        while start > 0 && is_word_char(source[start - 1]) {
+            start -= 1;
+        }
+        starts.push(start);
+    }
+    starts
+}
+
+/// Words of `source` that start with `prefix` and are longer than it, ignoring case, with
+/// how often each occurs, skipping the word being typed at `word_start`. A word keeps the
+/// spelling of its first occurrence.
+fn document_words(source: &[char], prefix: &[char], word_start: usize) -> Vec<(String, usize)> {
+    let mut words: Vec<(String, usize)> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for (word, start, _) in extract_words(source) {
+        if start == word_start
+            || word.len() <= prefix.len()
+            || !same_word(&word[..prefix.len()], prefix)
+            || !word.iter().any(|c| c.is_alphabetic())
+        {
+            continue;
+        }
+        let idx = *indices.entry(lowercase(&word)).or_insert_with(|| {
+            words.push((word.iter().collect(), 0));
+            words.len() - 1
+        });
+        words[idx].1 += 1;
+    }
+    words
+}
+
+/// Turn ranked words into completion items, numbering their sort order from
+/// `first_index` so later batches sort after earlier ones.
+fn completion_items(
+    context: &CompletionContext,
+    scored: impl IntoIterator<Item = (String, f32, serde_json::Value)>,
+    first_index: usize,
+) -> Vec<CompletionItem> {
+    let typed_word = (context.prefix.clone(), context.range);
+    scored
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (word, _score, data))| {
+            // Phrases and abbreviations replace more than the word being typed
+            let phrase_prefix = data["phrasePrefix"]
+                .as_u64()
+                .and_then(|idx| context.phrase_prefixes.get(idx as usize));
+            let (typed, range) = match data["source"].as_str() {
+                Some(ABBREVIATION_SOURCE) => &context.abbreviation_prefix,
+                _ => phrase_prefix.unwrap_or(&typed_word),
+            };
+            let mut new_text = word.clone();
+            // Words only found in open documents stand out from dictionary words
+            let (kind, detail) = match data["source"].as_str() {
+                Some(DOCUMENT_SOURCE) => (CompletionItemKind::REFERENCE, "In this document"),
+                Some(OPEN_DOCUMENTS_SOURCE) => {
+                    (CompletionItemKind::REFERENCE, "In another open document")
+                }
+                Some(PHRASE_SOURCE) => (CompletionItemKind::TEXT, "Polish phrase"),
+                // The abbreviation is shown, its expansion inserted
+                Some(ABBREVIATION_SOURCE) => {
+                    let expansion = data["expansion"].as_str().unwrap_or_default();
+                    new_text = apply_capitalization(typed, expansion);
+                    (CompletionItemKind::SNIPPET, expansion)
+                }
+                _ => (CompletionItemKind::TEXT, "Polish"),
+            };
+            CompletionItem {
+                label: word,
+                label_details: context.label_details.then(|| label_details(&data)),
+                kind: Some(kind),
+                detail: Some(detail.to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: *range,
+                    new_text,
+                })),
+                filter_text: Some(typed.iter().collect()),
+                sort_text: Some(format!("{:05}", first_index + idx + 1)),
+                // Run by the client once the item is inserted, so the word is learned
+                command: Some(Command {
+                    title: "Record completion".to_string(),
+                    command: CMD_COMPLETION_ACCEPTED.to_string(),
+                    arguments: Some(vec![serde_json::json!({ "word": data["word"] })]),
+                }),
+                data: Some(data),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// What is shown beside a completion's label: how many edits away from the typed text
+/// it is, where it comes from and a star for common words.
+fn label_details(data: &serde_json::Value) -> CompletionItemLabelDetails {
+    let detail = match data["editDistance"].as_u64().unwrap_or(0) {
+        0 => None,
+        1 => Some(" ~1 edit".to_string()),
+        distance => Some(format!(" ~{} edits", distance)),
+    };
+    let source = match data["source"].as_str() {
+        Some(DOCUMENT_SOURCE) => "this document",
+        Some(OPEN_DOCUMENTS_SOURCE) => "open document",
+        Some(PHRASE_SOURCE) => "phrase",
+        Some(ABBREVIATION_SOURCE) => "abbreviation",
+        _ => match data["dictionary"].as_str() {
+            Some("user") => "user dictionary",
+            Some("project") => "project dictionary",
+            _ => "dictionary",
+        },
+    };
+    let description = if data["common"].as_bool() == Some(true) {
+        format!("★ {}", source)
+    } else {
+        source.to_string()
+    };
+    CompletionItemLabelDetails {
+        detail,
+        description: Some(description),
+    }
+}
+
+/// Markdown shown when hovering a word: lemmas with parts of speech and dictionary status.
+fn hover_markdown(
+    word: &str,
+    analyses: &[Analysis],
+    translations: &[&str],
+    info: Option<WordInfo>,
+) -> String {
+    let mut lines = vec![format!("**{}**", word), String::new()];
+
+    for analysis in analyses {
+        lines.push(format!(
+            "- Lemma: **{}** ({})",
+            analysis.lemma, analysis.part_of_speech
+        ));
+    }
+
+    if !translations.is_empty() {
+        lines.push(format!("- English: *{}*", translations.join("; ")));
+    }
+
+    lines.push(match info {
+        Some(WordInfo { is_user: true, .. }) => "- In your dictionary".to_string(),
+        Some(WordInfo { is_project: true, .. }) => "- In project dictionary".to_string(),
+        Some(WordInfo { is_common: true, .. }) => "- Common word".to_string(),
+        Some(_) => "- Known word".to_string(),
+        None => "- Not in dictionary".to_string(),
+    });
+    if let Some(WordInfo { rank: Some(rank), .. }) = info {
+        lines.push(format!("- Frequency rank: {}", rank));
+    }
+
+    lines.join("\n")
+}
+
+/// Markdown shown when hovering a number: its Polish word form, declined when the
+/// preceding preposition requires it.
+fn number_markdown(number: &str, value: u64, case: Case) -> String {
+    let mut lines = vec![format!("**{}**", number), String::new()];
+    lines.push(format!("- In words: *{}*", to_words(value, case)));
+    if case != Case::Nominative {
+        lines.push(format!("- Nominative: *{}*", to_words(value, Case::Nominative)));
+    }
+    lines.join("\n")
+}
+
+/// Markdown details for a completion item, built from the data attached in `completion`.
+fn completion_documentation(data: &serde_json::Value) -> String {
+    let word = data.get("word").and_then(|v| v.as_str()).unwrap_or_default();
+    let common = data.get("common").and_then(|v| v.as_bool()).unwrap_or(false);
+    let rank = data.get("rank").and_then(|v| v.as_u64());
+    let edit_distance = data.get("editDistance").and_then(|v| v.as_u64()).unwrap_or(0);
+    let occurrences = data.get("occurrences").and_then(|v| v.as_u64());
+    if let Some(expansion) = data.get("expansion").and_then(|v| v.as_str()) {
+        return format!("**{}**\n\n- Abbreviation of *{}*", word, expansion);
+    }
+    let found_in = match data.get("source").and_then(|v| v.as_str()) {
+        Some(OPEN_DOCUMENTS_SOURCE) => "other open documents",
+        _ => "this document",
+    };
+
+    let mut lines = vec![format!("**{}**", word), String::new()];
+    lines.push(match (occurrences, rank) {
+        (Some(count), _) => format!("- Not in dictionary; used {} times in {}", count, found_in),
+        (None, Some(rank)) => format!("- Frequency: rank {}", rank),
+        (None, None) if common => "- Frequency: common word".to_string(),
+        (None, None) => "- Frequency: less common word".to_string(),
+    });
+    if edit_distance > 0 {
+        lines.push(format!("- Edit distance from typed text: {}", edit_distance));
+    }
+    lines.push(format!("- Definition: [sjp.pl]({})", sjp_url(word)));
+    lines.join("\n")
+}
+
+/// A spelling finding with a stable `code` whose description links to the word on sjp.pl,
+/// so users can check whether it is really wrong.
+fn spelling_diagnostic(
+    range: Range,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+    word: &str,
+) -> Diagnostic {
+    Diagnostic {
+        code_description: sjp_url(word)
+            .parse()
+            .ok()
+            .map(|href| CodeDescription { href }),
+        ..rule_diagnostic(range, severity, code, message)
+    }
+}
+
+/// A finding with a stable `code`, tagged with its category.
+fn rule_diagnostic(
+    range: Range,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostics::tagged_code(code))),
+        source: Some("polski-ls".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// Problems found by the typography and comma rules enabled in `settings`, in document
+/// order.
+fn rule_findings(doc: &DocumentState, settings: &Settings) -> Vec<typography::Finding> {
+    let mut findings = Vec::new();
+    if settings.rules.spacing {
+        findings.extend(typography::spacing(&doc.source));
+    }
+    if settings.rules.dashes && doc.has_prose_dashes() {
+        findings.extend(typography::dashes(&doc.source));
+    }
+    if settings.rules.hanging_conjunctions {
+        let nbsp = doc.non_breaking_space();
+        findings.extend(typography::hanging_conjunctions(&doc.source, nbsp));
+    }
+    if settings.rules.quotes && doc.has_prose_quotes() {
+        let nested = settings.typography.nested_quotes;
+        findings.extend(typography::quotes(&doc.source, nested));
+    }
+    if settings.rules.commas {
+        findings.extend(commas::missing(&doc.source));
+    }
+    findings.sort_by_key(|finding| finding.start);
+    findings
+}
+
+/// A preferred quick fix replacing `range` of the document with `new_text`.
+fn quick_fix(uri: &Uri, title: String, range: Range, new_text: String) -> CodeActionOrCommand {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), vec![TextEdit { range, new_text }]);
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }),
+        is_preferred: Some(true),
+        ..Default::default()
+    })
+}
+
+/// Link to the word's entry in the sjp.pl dictionary.
+fn sjp_url(word: &str) -> String {
+    let mut url = String::from("https://sjp.pl/");
+    for byte in word.to_lowercase().bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// A watcher for `pattern` inside `dir`. Clients without relative pattern support get
+/// an absolute glob instead.
+fn file_watcher(dir: &Path, pattern: &str, relative: bool) -> Option<FileSystemWatcher> {
+    let glob_pattern = if relative {
+        GlobPattern::Relative(RelativePattern {
+            base_uri: OneOf::Right(Uri::from_file_path(dir)?),
+            pattern: pattern.to_string(),
+        })
+    } else {
+        GlobPattern::String(dir.join(pattern).to_str()?.replace('\\', "/"))
+    };
+    Some(FileSystemWatcher {
+        glob_pattern,
+        kind: None,
+    })
+}
+
+/// Whether `path` is a user word list (`*.txt` in the config directory) or one of the
+/// configured dictionary files.
+fn is_dictionary_file(path: &Path, user_dir: Option<&Path>, dictionaries: &[PathBuf]) -> bool {
+    let in_user_dir = user_dir.is_some_and(|dir| path.parent() == Some(dir));
+    let is_txt = path.extension().is_some_and(|ext| ext == "txt");
+    (in_user_dir && is_txt) || dictionaries.iter().any(|dictionary| dictionary == path)
+}
+
+/// Add the project dictionary, if any, and the extra word lists to `dictionary`.
+fn load_dictionaries(
+    dictionary: &mut SimpleDictionary,
+    project: Option<PathBuf>,
+    extra: &[PathBuf],
+) {
+    if let Some(project) = project {
+        dictionary.load_project_dictionary(project);
+    }
+    dictionary.load_extra_dictionaries(extra);
+}
+
+/// Sort `spans` and join the overlapping or touching ones, so they can be binary searched.
+fn merge_spans(mut spans: Vec<std::ops::Range<usize>>) -> Vec<std::ops::Range<usize>> {
+    spans.sort_unstable_by_key(|span| span.start);
+    let mut merged: Vec<std::ops::Range<usize>> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Snapshot of the open documents, taken without holding any map guard across an await.
+fn open_documents(documents: &DashMap<Uri, Arc<DocumentState>>) -> Vec<(Uri, Arc<DocumentState>)> {
+    documents
+        .iter()
+        .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+        .collect()
+}
+
+/// How a toggled state is reported: "on" or "off".
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Error for a request about a document the client never opened.
+fn document_not_open(uri: &Uri) -> JsonError {
+    JsonError::invalid_params(format!("Document not open: {}", uri.as_str()))
+}
+
+/// Error for a pull diagnostic request whose document changed mid-check; the client
+/// retries with the new text.
+fn server_cancelled() -> JsonError {
+    JsonError {
+        code: ErrorCode::ServerError(-32802),
+        message: "Document changed during the check".into(),
+        data: Some(serde_json::json!({ "retriggerRequest": true })),
+    }
+}
+
+/// Error reported back to the client when a command fails on our side.
+fn internal_error(message: &str) -> JsonError {
+    JsonError {
+        code: ErrorCode::InternalError,
+        message: message.to_string().into(),
+        data: None,
+    }
+}
+
+/// Read a string field from the first (object) argument of a command.
+fn string_argument<'a>(arguments: &'a [serde_json::Value], key: &str) -> JsonResult<&'a str> {
+    arguments
+        .first()
+        .and_then(|arg| arg.get(key))
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| JsonError::invalid_params(format!("Missing string argument '{}'", key)))
+}
+
+/// Workspace root from the first workspace folder, falling back to the deprecated root URI.
+fn workspace_root(params: &InitializeParams) -> Option<PathBuf> {
+    #[allow(deprecated)]
+    let root_uri = params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(params.root_uri.as_ref());
+
+    root_uri
+        .and_then(|uri| uri.to_file_path())
+        .map(|path| path.into_owned())
+}
+
+/// Characters that accept the selected completion: the configured ones, or a space for
+/// a natural spell-checker flow unless the client preselects completions. `None` when
+/// there are none.
+fn commit_characters(
+    configured: Option<Vec<String>>,
+    client: Option<&ClientInfo>,
+) -> Option<Vec<String>> {
+    let preselecting =
+        client.is_some_and(|client| PRESELECTING_CLIENTS.contains(&client.name.as_str()));
+    let characters = configured.unwrap_or_else(|| {
+        if preselecting {
+            Vec::new()
+        } else {
+            vec![" ".to_string()]
+        }
+    });
+    Some(characters).filter(|characters| !characters.is_empty())
+}
+
+/// Result ID for a pull diagnostics report: equal diagnostics give equal IDs,
+/// so the client can be told the report is unchanged.
+fn diagnostics_result_id(diagnostics: &[Diagnostic]) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(diagnostics)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Extract words from source text with their start and end indices.
+fn extract_words(source: &[char]) -> Vec<(Vec<char>, usize, usize)> {
+    let mut words = Vec::new();
+    let mut i = 0;
+
+    while i < source.len() {
+        // Skip non-word characters
+        if !is_word_char_at(source, i) {
+            i += 1;
+            continue;
+        }
+
+        // Found start of a word
+        let start = i;
+        while i < source.len() && is_word_char_at(source, i) {
+            i += 1;
+        }
+        let end = i;
+
+        let word: Vec<char> = source[start..end].to_vec();
+        words.push((word, start, end));
+    }
+
+    words
+}
+
+/// Find the word touching `idx` (the cursor may be just after its last character).
+fn word_bounds(source: &[char], idx: usize) -> Option<(usize, usize)> {
+    let idx = idx.min(source.len());
+
+    let mut word_start = idx;
+    while word_start > 0 && is_word_char_at(source, word_start - 1) {
+        word_start -= 1;
+    }
+
+    let mut word_end = idx;
+    while word_end < source.len() && is_word_char_at(source, word_end) {
+        word_end += 1;
+    }
+
+    // Of "COVID-19" only the letters are looked up, fixed and renamed
+    if let Some((core_start, core_end)) = alphabetic_core(&source[word_start..word_end]) {
+        word_end = word_start + core_end;
+        word_start += core_start;
+    }
+
+    (word_start < word_end).then_some((word_start, word_end))
+}
+
+/// Ranges of the parts of a camelCase or PascalCase identifier ("moja", "Funkcja" in
+/// "mojaFunkcja"; "HTTP", "Serwer" in "HTTPSerwer"). A word without humps is one part,
+/// and so is a name with a one-letter prefix ("iPhone", "eBay").
+fn camel_case_parts(word: &[char]) -> Vec<(usize, usize)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 2..word.len() {
+        let hump = word[i - 1].is_lowercase() && word[i].is_uppercase();
+        // The last capital of an acronym starts the next part
+        let acronym_end = word[i - 1].is_uppercase()
+            && word[i].is_uppercase()
+            && word.get(i + 1).is_some_and(|c| c.is_lowercase());
+        if hump || acronym_end {
+            parts.push((start, i));
+            start = i;
+        }
+    }
+    parts.push((start, word.len()));
+    parts
+}
+
+/// The letters of a word that are spell-checked when it mixes digits and letters: the
+/// word without its leading and trailing digits and hyphens ("COVID" in "COVID-19",
+/// "latek" in "5-latek"), as a range within it. `None` when no letters are left or
+/// digits remain between them ("H2O").
+fn alphabetic_core(word: &[char]) -> Option<(usize, usize)> {
+    let edge = |c: &char| c.is_numeric() || *c == '-';
+    let start = word.iter().position(|c| !edge(c))?;
+    let end = word.iter().rposition(|c| !edge(c))? + 1;
+    (!word[start..end].iter().any(|c| c.is_numeric())).then_some((start, end))
+}
+
+/// Edits applying the top suggestion published with each of `diagnostics`. Findings
+/// without suggestions, such as a missing abbreviation period, are left alone.
+fn fix_all_edits(doc: &DocumentState, diagnostics: &[Diagnostic]) -> Vec<TextEdit> {
+    let source = &doc.source;
+    let line_index = &doc.line_index;
+
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let data = DiagnosticData::from_diagnostic(diagnostic)?;
+            let suggestion = data.suggestions.first()?;
+            let start = line_index.position_to_index(source, diagnostic.range.start);
+            let end = line_index.position_to_index(source, diagnostic.range.end);
+            Some(TextEdit {
+                range: diagnostic.range,
+                new_text: apply_occurrence_casing(source, start, end, suggestion),
+            })
+        })
+        .collect()
+}
+
+/// Best corrections for an unknown word, in dictionary casing. Restoring diacritics
+/// counts as an exact match, so those corrections come first.
+fn suggest(
+    word: &[char],
+    dictionary: &SimpleDictionary,
+    weights: &ScoringWeights,
+    max_suggestions: usize,
+) -> Vec<String> {
+    let max_edit_distance = if word.len() <= 3 { 1 } else { 2 };
+    let mut matches = dictionary.diacritic_match(word, DEFAULT_CANDIDATE_CAP);
+    let restored: HashSet<Vec<char>> = matches.iter().map(|m| m.word.clone()).collect();
+    let mut fuzzy_matches = dictionary.fuzzy_match(word, max_edit_distance, DEFAULT_CANDIDATE_CAP);
+    fuzzy_matches.retain(|m| !restored.contains(&m.word));
+    matches.append(&mut fuzzy_matches);
+
+    // A compound is corrected part by part ("biało-czerwny"), and an inflected foreign
+    // word by its base ("iPhoen'a"), unless it is a dictionary entry itself
+    let mut suggestions = if let Some((base, ending)) = apostrophe::split(word) {
+        let ending: String = ending.iter().collect();
+        suggest(base, dictionary, weights, max_suggestions)
+            .into_iter()
+            .map(|base| base + &ending)
+            .collect()
+    } else if word.contains(&'-') {
+        compound_suggestions(word, dictionary, weights, max_suggestions)
+    } else {
+        Vec::new()
+    };
+    for m in rank_suggestions(word, matches, weights) {
+        let suggestion: String = m.word.iter().collect();
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    }
+    suggestions.truncate(max_suggestions);
+    suggestions
+}
+
+/// Corrections of a hyphenated compound with unknown parts: each suggestion for the
+/// first unknown part, with the best one for any other. Known parts are kept.
+fn compound_suggestions(
+    word: &[char],
+    dictionary: &SimpleDictionary,
+    weights: &ScoringWeights,
+    max_suggestions: usize,
+) -> Vec<String> {
+    let parts: Vec<&[char]> = word.split(|&c| c == '-').collect();
+    let Some(first_unknown) = parts.iter().position(|part| !dictionary.contains(part)) else {
+        return Vec::new();
+    };
+    let mut corrections = Vec::new();
+    for part in &parts {
+        if dictionary.contains(part) {
+            corrections.push(vec![part.iter().collect::<String>()]);
+        } else {
+            let suggestions = suggest(part, dictionary, weights, max_suggestions);
+            if suggestions.is_empty() {
+                return Vec::new();
+            }
+            corrections.push(suggestions);
+        }
+    }
+
+    corrections[first_unknown]
+        .iter()
+        .map(|alternative| {
+            let parts: Vec<&str> = corrections
+                .iter()
+                .enumerate()
+                .map(|(idx, part)| if idx == first_unknown { alternative } else { &part[0] })
+                .map(String::as_str)
+                .collect();
+            parts.join("-")
+        })
+        .collect()
+}
+
+/// Lowercase form of a word, used as the key of ignore lists.
+fn lowercase(word: &[char]) -> String {
+    word.iter().flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// Start and end indices of every case-insensitive occurrence of `word`.
+fn occurrences(source: &[char], word: &[char]) -> Vec<(usize, usize)> {
+    extract_words(source)
+        .into_iter()
+        .filter(|(candidate, _, _)| same_word(candidate, word))
+        .map(|(_, start, end)| (start, end))
+        .collect()
+}
+
+/// Edits replacing every occurrence of `word` with `new_name`, keeping each one's casing.
+fn rename_edits(
+    source: &[char],
+    line_index: &LineIndex,
+    word: &[char],
+    new_name: &str,
+) -> Vec<TextEdit> {
+    occurrences(source, word)
+        .into_iter()
+        .map(|(start, end)| TextEdit {
+            range: Range {
+                start: line_index.index_to_position(source, start),
+                end: line_index.index_to_position(source, end),
+            },
+            new_text: apply_occurrence_casing(source, start, end, new_name),
+        })
+        .collect()
+}
+
+/// Check if a character is part of a word (including Polish diacritics).
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric()
+        || matches!(
+            ch,
+            'ą' | 'ć' | 'ę' | 'ł' | 'ń' | 'ó' | 'ś' | 'ź' | 'ż'
+                | 'Ą' | 'Ć' | 'Ę' | 'Ł' | 'Ń' | 'Ó' | 'Ś' | 'Ź' | 'Ż'
+        )
+}
+
+/// True if `source[idx]` is part of a word: a word character, a hyphen joining a compound
+/// ("biało-czerwony", "Bielsko-Biała") or letters to digits ("COVID-19", "5-latek"), or
+/// an apostrophe between a letter and a Polish ending ("iPhone'a", "Johnny'ego").
+fn is_word_char_at(source: &[char], idx: usize) -> bool {
+    let ch = source[idx];
+    if is_word_char(ch) {
+        return true;
+    }
+    let after_letter = idx > 0 && source[idx - 1].is_alphabetic();
+    if ch == '-' {
+        let Some(&next) = source.get(idx + 1) else {
+            return false;
+        };
+        let after_word_char = idx > 0 && is_word_char(source[idx - 1]);
+        return after_word_char && is_word_char(next) && (after_letter || next.is_alphabetic());
+    }
+    if apostrophe::is_apostrophe(ch) && after_letter {
+        let rest = &source[idx + 1..];
+        let ending = rest.iter().take_while(|&&c| is_word_char(c)).count();
+        return apostrophe::is_ending(&rest[..ending]);
+    }
+    false
+}
+
+/// Order fuzzy matches best first using the completion score; ties keep dictionary order.
+fn rank_suggestions(
+    word: &[char],
+    matches: Vec<FuzzyMatchResult>,
+    weights: &ScoringWeights,
+) -> Vec<FuzzyMatchResult> {
+    let mut scored: Vec<(f32, FuzzyMatchResult)> = matches
+        .into_iter()
+        .map(|m| {
+            let frequency = frequency_weight(&m);
+            let score =
+                calculate_completion_score(word, &m.word, m.edit_distance, frequency, weights);
+            (score, m)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    scored.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Frequency ranks from this one on earn no share of the common word bonus.
+const RANK_SCALE: f32 = 100_000.0;
+
+/// Share of the common word bonus a match earns: graded by its frequency rank when the
+/// word list gives one, otherwise all of it for common words and none for the rest.
+fn frequency_weight(m: &FuzzyMatchResult) -> f32 {
+    match m.rank {
+        Some(rank) => (1.0 - (rank as f32).ln() / RANK_SCALE.ln()).clamp(0.0, 1.0),
+        None if m.is_common => 1.0,
+        None => 0.0,
+    }
+}
+
+/// Calculate completion score for ranking; `frequency` is the share of the common word
+/// bonus the candidate earns (see `frequency_weight`).
+fn calculate_completion_score(
+    query: &[char],
+    candidate: &[char],
+    edit_distance: u8,
+    frequency: f32,
+    weights: &ScoringWeights,
+) -> f32 {
+    let mut score = 100.0;
+
+    // Edit distance penalty, smaller for letters typed on a neighbouring key
+    if edit_distance > 0 {
+        let costs = weights.keyboard_layout.costs();
+        let typo_cost = weighted_distance(query, candidate, costs).min(f32::from(edit_distance));
+        score -= distance_penalty(typo_cost, &weights.distance_penalties);
+    }
+
+    // Letters match up to case and diacritics
+    let same_letter = |q: &char, c: &char| {
+        let fold = |ch: &char| ch.to_lowercase().next().map(base_letter);
+        fold(q) == fold(c)
+    };
+
+    // First letter match bonus
+    if !query.is_empty() && !candidate.is_empty() {
+        if same_letter(&query[0], &candidate[0]) {
+            score += weights.first_letter_bonus;
+        } else {
+            score -= weights.first_letter_penalty;
+        }
+    }
+
+    // Prefix match bonus
+    let prefix_match_len = query
+        .iter()
+        .zip(candidate.iter())
+        .take_while(|(q, c)| same_letter(q, c))
+        .count();
+    score += (prefix_match_len as f32) * weights.prefix_weight;
+
+    // Frequency bonus
+    score += weights.common_bonus * frequency;
+
+    score
+}
+
+/// Penalty for a possibly fractional edit distance, interpolated between the penalties
+/// for 1, 2 and 3+ edits.
+fn distance_penalty(distance: f32, penalties: &[f32; 3]) -> f32 {
+    let steps = [0.0, penalties[0], penalties[1], penalties[2]];
+    let distance = distance.clamp(0.0, 3.0);
+    let lower = (distance.floor() as usize).min(2);
+    steps[lower] + (steps[lower + 1] - steps[lower]) * (distance - lower as f32)
+}
+
+impl LanguageServer for Backend {
+    async fn initialize(&self, params: InitializeParams) -> JsonResult<InitializeResult> {
+        eprintln!("[POLSKI-LS] initialize called");
+
+        // Without refresh support nothing could tell the client to re-pull after a
+        // dictionary or settings change, so such clients get pushed diagnostics
+        let refresh_support = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.diagnostic.as_ref())
+            .and_then(|diagnostics| diagnostics.refresh_support)
+            .unwrap_or(false);
+        let pull_diagnostics = refresh_support
+            && params
+                .capabilities
+                .text_document
+                .as_ref()
+                .is_some_and(|text_document| text_document.diagnostic.is_some());
+        eprintln!("[POLSKI-LS] pull diagnostics: {}", pull_diagnostics);
+        self.pull_diagnostics.store(pull_diagnostics, Ordering::Relaxed);
+
+        let label_details = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.completion.as_ref())
+            .and_then(|completion| completion.completion_item.as_ref())
+            .and_then(|item| item.label_details_support)
+            .unwrap_or(false);
+        self.label_details.store(label_details, Ordering::Relaxed);
+
+        let work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        self.checker.progress.set_enabled(work_done_progress);
+
+        let watched_files = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files)
+            .filter(|watched_files| watched_files.dynamic_registration == Some(true));
+        if let Some(watched_files) = watched_files {
+            let relative = watched_files.relative_pattern_support == Some(true);
+            let _ = self.file_watching.set(relative);
+        }
+
+        let position_encoding = PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|general| general.position_encodings.as_deref()),
+        );
+        eprintln!("[POLSKI-LS] position encoding: {:?}", position_encoding);
+        let _ = self.position_encoding.set(position_encoding);
+
+        if let Some(root) = workspace_root(&params) {
+            eprintln!("[POLSKI-LS] Workspace root: {:?}", root);
+            *self.ignore_file.lock().await = IgnoreFile::load(&root);
+            let _ = self.workspace_root.set(root);
+        }
+
+        // Same settings as didChangeConfiguration, for clients that configure servers
+        // at startup (VS Code extension settings, Neovim init_options)
+        let mut layers = self.settings_layers.lock().await;
+        layers.user = self.read_user_config().await;
+        layers.project = self.read_project_config().await;
+        layers.client = params.initialization_options.clone().unwrap_or_default();
+        let settings = match layers.resolve() {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[POLSKI-LS] ERROR: Invalid initializationOptions: {}", e);
+                let message = format!("Invalid polski-ls initializationOptions: {}", e);
+                self.client.show_message(MessageType::WARNING, message).await;
+                layers.client = serde_json::Value::Null;
+                layers.resolve().unwrap_or_default()
+            }
+        };
+        drop(layers);
+
+        self.dictionary.update(|dictionary| {
+            self.load_configured_dictionaries(dictionary, &settings.dictionaries)
+        });
+        self.update_file_filter(&settings.files).await;
+        self.completion_budget.set_max_results(settings.completion.max_results);
+        let commit_characters = commit_characters(
+            settings.completion.commit_characters.clone(),
+            params.client_info.as_ref(),
+        );
+        *self.settings.write().await = settings;
+
+        Ok(InitializeResult {
+            server_info: Some(ServerInfo {
+                name: "polski-ls".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            capabilities: ServerCapabilities {
+                position_encoding: Some(position_encoding.kind()),
+                completion_provider: Some(CompletionOptions {
+                    resolve_provider: Some(true),
+                    // Trigger on all letters including Polish diacritics
+                    trigger_characters: Some(
+                        "aąbcćdeęfghijklłmnńoópqrsśtuvwxyzźżAĄBCĆDEĘFGHIJKLŁMNŃOÓPQRSŚTUVWXYZŹŻ"
+                            .chars()
+                            .map(String::from)
+                            .collect(),
+                    ),
+                    all_commit_characters: commit_characters,
+                    work_done_progress_options: Default::default(),
+                    completion_item: None,
+                }),
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
+                        will_save: None,
+                        will_save_wait_until: None,
+                        save: None,
+                    },
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::new(FIX_ALL_KIND),
+                            CodeActionKind::new(HANGING_CONJUNCTIONS_KIND),
+                        ]),
+                        ..Default::default()
+                    },
+                )),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        CMD_ADD_TO_DICTIONARY.to_string(),
+                        CMD_ADD_TO_PROJECT_DICTIONARY.to_string(),
+                        CMD_IGNORE_IN_FILE.to_string(),
+                        CMD_IGNORE_FOR_SESSION.to_string(),
+                        CMD_CLEAR_SESSION_IGNORES.to_string(),
+                        CMD_RELOAD_DICTIONARIES.to_string(),
+                        CMD_OPEN_USER_DICTIONARY.to_string(),
+                        CMD_RELOAD_CONFIG.to_string(),
+                        CMD_REFLOW_PARAGRAPH.to_string(),
+                        CMD_COMPLETION_ACCEPTED.to_string(),
+                        CMD_TOGGLE.to_string(),
+                        CMD_TOGGLE_GLOBAL.to_string(),
+                        CMD_CHECK_DOCUMENT.to_string(),
+                    ],
+                    work_done_progress_options: Default::default(),
+                }),
+                diagnostic_provider: pull_diagnostics.then(|| {
+                    DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                        identifier: Some("polski-ls".to_string()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: false,
+                        work_done_progress_options: Default::default(),
+                    })
+                }),
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        eprintln!("[POLSKI-LS] initialized - server ready!");
+        self.client
+            .log_message(MessageType::INFO, "polski-ls initialized!")
+            .await;
+        if !self.dictionary.is_loaded() {
+            let progress = self.checker.progress.begin("Loading dictionaries").await;
+            // The user dictionary's location is only known once it is loaded
+            self.dictionary.loaded().await;
+            if let Some(progress) = progress {
+                let word_count = self.dictionary.load().word_count();
+                progress
+                    .finish_with_message(format!("{} words", word_count))
+                    .await;
+            }
+        }
+        if self.file_watching.get().is_some() {
+            self.watch_dictionaries(false).await;
+        } else {
+            self.watch_user_dictionary().await;
+        }
+    }
+
+    async fn shutdown(&self) -> JsonResult<()> {
+        self.shutdown_received.store(true, Ordering::Relaxed);
+        self.usage.lock().await.save();
+        Ok(())
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        eprintln!("[POLSKI-LS] did_change_configuration");
+        if params.settings.is_null() {
+            return;
+        }
+
+        match self
+            .update_settings(|layers| layers.client = params.settings)
+            .await
+        {
+            // Rules, severity or the dictionaries may have changed
+            Ok(()) => self.refresh_all_diagnostics().await,
+            Err(e) => {
+                eprintln!("[POLSKI-LS] ERROR: Invalid settings: {}", e);
+                let message = format!("Invalid polski-ls settings: {}", e);
+                self.client.show_message(MessageType::WARNING, message).await;
+            }
+        }
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let settings = self.settings.read().await.dictionaries.clone();
+        let dictionaries = self.dictionary_paths(&settings);
+        let user_dir = user_config_dir();
+        let user_dict_path = self
+            .dictionary
+            .load()
+            .user_dict_path()
+            .map(Path::to_path_buf);
+        let changed: Vec<PathBuf> = params
+            .changes
+            .iter()
+            .filter_map(|change| change.uri.to_file_path())
+            .map(|path| path.into_owned())
+            .filter(|path| is_dictionary_file(path, user_dir.as_deref(), &dictionaries))
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        // "Add to dictionary" writes slownik.txt itself; new words in it are merged
+        // like the native watcher does instead of rebuilding everything
+        if changed.iter().all(|path| Some(path) == user_dict_path.as_ref()) {
+            if self.dictionary.update(SimpleDictionary::merge_user_dictionary) > 0 {
+                self.refresh_all_diagnostics().await;
+                if self.pull_diagnostics.load(Ordering::Relaxed) {
+                    self.checker.send_status(&self.client).await;
+                }
+            }
+            return;
+        }
+
+        eprintln!("[POLSKI-LS] Dictionary files changed on disk, reloading");
+        if let Err(e) = self.reload_dictionaries().await {
+            eprintln!("[POLSKI-LS] ERROR: Dictionary reload failed: {}", e.message);
+        }
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        eprintln!("[POLSKI-LS] did_open: {:?}", params.text_document.uri);
+        let uri = params.text_document.uri;
+        let source: Vec<char> = params.text_document.text.chars().collect();
+        let line_index = self.line_index(&source);
+        let language_id = params.text_document.language_id;
+        let mut doc_state = DocumentState::new(&uri, source, line_index, language_id);
+        doc_state.version = Some(params.text_document.version);
+        let doc_state = Arc::new(doc_state);
+
+        // Most recently opened documents are checked first, in the background
+        self.queue_diagnostics(&uri, &doc_state).await;
+
+        if let Some(previous) = self.documents.insert(uri, doc_state) {
+            previous.supersede();
+        }
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        eprintln!("[POLSKI-LS] did_change: {:?}", params.text_document.uri);
+        if params.content_changes.is_empty() {
+            return;
+        }
+
+        let uri = params.text_document.uri;
+        // didChange carries no languageId; keep the one from didOpen, the ignored words and
+        // whether checking is turned off
+        let previous = self
+            .documents
+            .get(&uri)
+            .map(|doc_state| DocumentState::clone(&doc_state));
+        let (mut source, mut line_index, language_id, ignored_words, disabled) = match previous {
+            Some(doc_state) => (
+                doc_state.source,
+                doc_state.line_index,
+                doc_state.language_id,
+                doc_state.ignored_words,
+                doc_state.disabled,
+            ),
+            None => (
+                Vec::new(),
+                self.line_index(&[]),
+                String::new(),
+                HashSet::new(),
+                false,
+            ),
+        };
+
+        // Incremental changes patch the text and line index in place; a change without a
+        // range replaces the whole document
+        for change in params.content_changes {
+            match change.range {
+                Some(range) => line_index.apply_edit(&mut source, range, &change.text),
+                None => {
+                    source = change.text.chars().collect();
+                    line_index = self.line_index(&source);
+                }
+            }
+        }
+
+        let mut doc_state = DocumentState::new(&uri, source, line_index, language_id);
+        doc_state.ignored_words = ignored_words;
+        doc_state.disabled = disabled;
+        doc_state.version = Some(params.text_document.version);
+        let doc_state = Arc::new(doc_state);
+        let previous = self.documents.insert(uri.clone(), Arc::clone(&doc_state));
+        // Checks still running on the old text would publish outdated ranges
+        if let Some(previous) = previous {
+            previous.supersede();
+        }
+
+        // A queued first check is superseded by the debounced one
+        self.open_queue.remove(&uri);
+        self.schedule_diagnostics(&uri, &doc_state).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.debouncer.cancel(&params.text_document.uri);
+        self.open_queue.remove(&params.text_document.uri);
+
+        if let Some((_, previous)) = self.documents.remove(&params.text_document.uri) {
+            previous.supersede();
+        }
+
+        // Clear diagnostics for closed document
+        self.publisher
+            .publish(params.text_document.uri.clone(), (vec![], None));
+        let mut too_long_reported = self.too_long_reported.lock().await;
+        too_long_reported.remove(&params.text_document.uri);
+        drop(too_long_reported);
+
+        self.checker.forget(&self.client, &params.text_document.uri).await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> JsonResult<Option<CompletionResponse>> {
+        let pos = params.text_document_position.position;
+        eprintln!("[POLSKI-LS] completion: pos={}:{}", pos.line, pos.character);
+
+        let started = Instant::now();
+        let uri = &params.text_document_position.text_document.uri;
+        if let Some(token) = &params.partial_result_params.partial_result_token {
+            let count = self.stream_completions(uri, pos, token).await?;
+            self.completion_budget.record(started.elapsed());
+            eprintln!("[POLSKI-LS] streamed {} completions", count);
+            // Everything was sent as partial results; the response itself stays empty
+            return Ok(Some(CompletionResponse::Array(Vec::new())));
+        }
+
+        let items = self.generate_completions(uri, pos).await?;
+        self.completion_budget.record(started.elapsed());
+
+        eprintln!("[POLSKI-LS] returning {} completions", items.len());
+        if !items.is_empty() {
+            let labels: Vec<_> = items.iter().take(5).map(|i| &i.label).collect();
+            eprintln!("[POLSKI-LS] top 5: {:?}", labels);
+        }
+
+        if items.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(CompletionResponse::List(CompletionList {
+                is_incomplete: true,
+                items,
+            })))
+        }
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> JsonResult<DocumentDiagnosticReportResult> {
+        let uri = &params.text_document.uri;
+        eprintln!("[POLSKI-LS] diagnostic: {:?}", uri);
+
+        let doc_state = self.document(uri)?;
+        let ignored = self.is_ignored(uri, &doc_state).await
+            || self.is_too_long(uri, &doc_state).await
+            || self.awaits_request(&doc_state).await;
 
-    while i < source.len() {
-        // Skip non-word characters
-        if !is_word_char(source[i]) {
-            i += 1;
-            continue;
+        let diagnostics = if ignored {
+            Vec::new()
+        } else {
+            self.checker.check(&doc_state).await
+        };
+        if doc_state.is_superseded() {
+            // The client asks again for the new text
+            return Err(server_cancelled());
         }
+        let result_id = diagnostics_result_id(&diagnostics);
+        self.checker.record(&self.client, uri, diagnostics.len()).await;
 
-        // Found start of a word
-        let start = i;
-        while i < source.len() && is_word_char(source[i]) {
-            i += 1;
+        let report = if params.previous_result_id.as_deref() == Some(result_id.as_str()) {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            })
+        } else {
+            eprintln!("[POLSKI-LS] Reporting {} diagnostics", diagnostics.len());
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items: diagnostics,
+                },
+            })
+        };
+
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn completion_resolve(&self, mut item: CompletionItem) -> JsonResult<CompletionItem> {
+        if let Some(data) = &item.data {
+            item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: completion_documentation(data),
+            }));
         }
-        let end = i;
+        Ok(item)
+    }
 
-        let word: Vec<char> = source[start..end].to_vec();
-        words.push((word, start, end));
+    async fn hover(&self, params: HoverParams) -> JsonResult<Option<Hover>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, position);
+
+        if let Some((start, end, value)) = number_at(source, idx) {
+            let number: String = source[start..end].iter().collect();
+            return Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: number_markdown(&number, value, case_at(source, start)),
+                }),
+                range: Some(Range {
+                    start: line_index.index_to_position(source, start),
+                    end: line_index.index_to_position(source, end),
+                }),
+            }));
+        }
+
+        let Some((word_start, word_end)) = word_bounds(source, idx) else {
+            return Ok(None);
+        };
+
+        let word: Vec<char> = source[word_start..word_end].to_vec();
+        let range = Range {
+            start: line_index.index_to_position(source, word_start),
+            end: line_index.index_to_position(source, word_end),
+        };
+
+        let info = self.dictionary.load().word_info(&word);
+        let analyses = self.morphology.analyze(&word);
+        let translations = self.glossary.translate(&word, analyses);
+        let word_string: String = word.iter().collect();
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: hover_markdown(&word_string, analyses, &translations, info),
+            }),
+            range: Some(range),
+        }))
     }
 
-    words
-}
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> JsonResult<Option<Vec<DocumentHighlight>>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
 
-/// Check if a character is part of a word (including Polish diacritics).
-fn is_word_char(ch: char) -> bool {
-    ch.is_alphanumeric()
-        || matches!(
-            ch,
-            'ą' | 'ć' | 'ę' | 'ł' | 'ń' | 'ó' | 'ś' | 'ź' | 'ż'
-                | 'Ą' | 'Ć' | 'Ę' | 'Ł' | 'Ń' | 'Ó' | 'Ś' | 'Ź' | 'Ż'
-        )
-}
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, position);
+        let Some((word_start, word_end)) = word_bounds(source, idx) else {
+            return Ok(None);
+        };
+        let word: Vec<char> = source[word_start..word_end].to_vec();
 
-/// Apply capitalization from original word to suggestion.
-/// If original starts with uppercase, capitalize first letter of suggestion.
-fn apply_capitalization(original: &[char], suggestion: &str) -> String {
-    let starts_uppercase = original.first().map_or(false, |c| c.is_uppercase());
-    if starts_uppercase {
-        let mut chars: Vec<char> = suggestion.chars().collect();
-        if let Some(first) = chars.first_mut() {
-            *first = first.to_uppercase().next().unwrap_or(*first);
+        // Only misspellings are highlighted, to show how far a typo has spread
+        if self.dictionary.load().contains_compound(&word) {
+            return Ok(None);
         }
-        chars.into_iter().collect()
-    } else {
-        suggestion.to_string()
+
+        let highlights = occurrences(source, &word)
+            .into_iter()
+            .map(|(start, end)| DocumentHighlight {
+                range: Range {
+                    start: line_index.index_to_position(source, start),
+                    end: line_index.index_to_position(source, end),
+                },
+                kind: Some(DocumentHighlightKind::TEXT),
+            })
+            .collect();
+
+        Ok(Some(highlights))
     }
-}
 
-/// Calculate completion score for ranking.
-fn calculate_completion_score(
-    query: &[char],
-    candidate: &[char],
-    edit_distance: u8,
-    is_common: bool,
-) -> f32 {
-    let mut score = 100.0;
+    async fn code_action(&self, params: CodeActionParams) -> JsonResult<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        let range = params.range;
 
-    // Edit distance penalty
-    score -= match edit_distance {
-        0 => 0.0,
-        1 => 20.0,
-        2 => 50.0,
-        _ => 100.0,
-    };
+        eprintln!(
+            "[POLSKI-LS] code_action: {:?} at {}:{}-{}:{}",
+            uri, range.start.line, range.start.character, range.end.line, range.end.character
+        );
 
-    // First letter match bonus
-    if !query.is_empty() && !candidate.is_empty() {
-        if query[0].eq_ignore_ascii_case(&candidate[0]) {
-            score += 50.0;
-        } else {
-            score -= 30.0;
+        // Clients may ask for specific kinds only, e.g. source.fixAll on save
+        let only = params.context.only.as_deref();
+        let requested = |kind: &CodeActionKind| {
+            only.is_none_or(|kinds| kinds.iter().any(|k| kind.as_str().starts_with(k.as_str())))
+        };
+
+        let mut actions = Vec::new();
+        if requested(&CodeActionKind::QUICKFIX) {
+            actions = self.replacement_fixes(uri, range).await?;
+            actions.extend(self.repeated_word_fixes(uri, range).await?);
+            actions.extend(self.pattern_rule_fixes(uri, range).await?);
+            actions.extend(self.rule_fixes(uri, range).await?);
+            actions.extend(self.quick_fixes(uri, range, &params.context.diagnostics).await?);
+        }
+        if requested(&CodeActionKind::REFACTOR_REWRITE) {
+            actions.extend(self.number_to_words(uri, range).await?);
         }
+        if requested(&CodeActionKind::new(FIX_ALL_KIND)) {
+            actions.extend(self.fix_all(uri).await?);
+        }
+        if requested(&CodeActionKind::new(HANGING_CONJUNCTIONS_KIND)) {
+            actions.extend(self.bind_hanging_conjunctions(uri).await?);
+        }
+
+        eprintln!("[POLSKI-LS] Returning {} code actions", actions.len());
+        Ok((!actions.is_empty()).then_some(actions))
     }
 
-    // Prefix match bonus
-    let prefix_match_len = query
-        .iter()
-        .zip(candidate.iter())
-        .take_while(|(q, c)| q.eq_ignore_ascii_case(c))
-        .count();
-    score += (prefix_match_len as f32) * 8.0;
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> JsonResult<Option<PrepareRenameResponse>> {
+        let uri = &params.text_document.uri;
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, params.position);
+        let Some((word_start, word_end)) = word_bounds(source, idx) else {
+            return Ok(None);
+        };
+        let word: Vec<char> = source[word_start..word_end].to_vec();
+
+        // Only misspelled words can be renamed
+        let dictionary = self.dictionary.load();
+        if dictionary.contains_compound(&word) {
+            return Ok(None);
+        }
+
+        // Offer the best suggestion as the new name
+        let weights = self.settings.read().await.scoring;
+        let max_edit_distance = if word.len() <= 3 { 1 } else { 2 };
+        let fuzzy_matches = dictionary.fuzzy_match(&word, max_edit_distance, DEFAULT_CANDIDATE_CAP);
+
+        let placeholder = match rank_suggestions(&word, fuzzy_matches, &weights).first() {
+            Some(best) => {
+                let best: String = best.word.iter().collect();
+                apply_occurrence_casing(source, word_start, word_end, &best)
+            }
+            None => word.iter().collect(),
+        };
 
-    // Common word bonus
-    if is_common {
-        score += 35.0;
+        Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: Range {
+                start: line_index.index_to_position(source, word_start),
+                end: line_index.index_to_position(source, word_end),
+            },
+            placeholder,
+        }))
     }
 
-    score
+    async fn rename(&self, params: RenameParams) -> JsonResult<Option<WorkspaceEdit>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let new_name = params.new_name.trim();
+        if new_name.is_empty() || !new_name.chars().all(is_word_char) {
+            return Err(JsonError::invalid_params(format!(
+                "'{}' is not a single word",
+                params.new_name
+            )));
+        }
+
+        let doc_state = self.document(uri)?;
+
+        let source = &doc_state.source;
+        let line_index = &doc_state.line_index;
+        let idx = line_index.position_to_index(source, params.text_document_position.position);
+        let Some((word_start, word_end)) = word_bounds(source, idx) else {
+            return Ok(None);
+        };
+        let word: Vec<char> = source[word_start..word_end].to_vec();
+
+        if self.dictionary.load().contains_compound(&word) {
+            return Ok(None);
+        }
+
+        let edits = rename_edits(source, line_index, &word, new_name);
+        eprintln!(
+            "[POLSKI-LS] rename: {} occurrence(s) of '{}' -> '{}'",
+            edits.len(),
+            word.iter().collect::<String>(),
+            new_name
+        );
+
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone(), edits);
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> JsonResult<Option<serde_json::Value>> {
+        eprintln!("[POLSKI-LS] execute_command: {}", params.command);
+
+        match params.command.as_str() {
+            CMD_ADD_TO_DICTIONARY => self.add_to_dictionary(&params.arguments, false).await,
+            CMD_ADD_TO_PROJECT_DICTIONARY => self.add_to_dictionary(&params.arguments, true).await,
+            CMD_IGNORE_IN_FILE => self.ignore_in_file(&params.arguments).await,
+            CMD_IGNORE_FOR_SESSION => self.ignore_for_session(&params.arguments).await,
+            CMD_CLEAR_SESSION_IGNORES => self.clear_session_ignores().await,
+            CMD_RELOAD_DICTIONARIES => self.reload_dictionaries().await,
+            CMD_OPEN_USER_DICTIONARY => self.open_user_dictionary().await,
+            CMD_RELOAD_CONFIG => self.reload_config().await,
+            CMD_REFLOW_PARAGRAPH => self.reflow_paragraph(&params.arguments).await,
+            CMD_COMPLETION_ACCEPTED => self.completion_accepted(&params.arguments).await,
+            CMD_TOGGLE => self.toggle(&params.arguments).await,
+            CMD_TOGGLE_GLOBAL => self.toggle_global().await,
+            CMD_CHECK_DOCUMENT => self.check_document(&params.arguments).await,
+            other => Err(JsonError::invalid_params(format!("Unknown command: {}", other))),
+        }
+    }
 }
 
-impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> JsonResult<InitializeResult> {
-        eprintln!("[POLSKI-LS] initialize called");
-        Ok(InitializeResult {
-            server_info: Some(ServerInfo {
-                name: "polski-ls".to_string(),
-                version: Some(env!("CARGO_PKG_VERSION").to_string()),
-            }),
-            capabilities: ServerCapabilities {
-                completion_provider: Some(CompletionOptions {
-                    resolve_provider: Some(false),
-                    // Trigger on all letters including Polish diacritics
-                    trigger_characters: Some(
-                        "aąbcćdeęfghijklłmnńoópqrsśtuvwxyzźżAĄBCĆDEĘFGHIJKLŁMNŃOÓPQRSŚTUVWXYZŹŻ"
-                            .chars()
-                            .map(String::from)
-                            .collect(),
-                    ),
-                    // Space auto-accepts first suggestion for natural spell-checker flow
-                    all_commit_characters: Some(vec![" ".to_string()]),
-                    work_done_progress_options: Default::default(),
-                    completion_item: None,
-                }),
-                text_document_sync: Some(TextDocumentSyncCapability::Options(
-                    TextDocumentSyncOptions {
-                        open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::FULL),
-                        will_save: None,
-                        will_save_wait_until: None,
-                        save: None,
-                    },
-                )),
-                code_action_provider: Some(tower_lsp_server::lsp_types::CodeActionProviderCapability::Simple(true)),
-                execute_command_provider: Some(ExecuteCommandOptions {
-                    commands: vec![CMD_ADD_TO_DICTIONARY.to_string()],
-                    work_done_progress_options: Default::default(),
-                }),
-                ..Default::default()
-            },
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+
+    #[test]
+    fn test_is_word_char_ascii() {
+        assert!(is_word_char('a'));
+        assert!(is_word_char('Z'));
+        assert!(is_word_char('5'));
+        assert!(!is_word_char(' '));
+        assert!(!is_word_char('.'));
+        assert!(!is_word_char('\n'));
+    }
+
+    #[test]
+    fn test_is_word_char_polish() {
+        assert!(is_word_char('ą'));
+        assert!(is_word_char('Ą'));
+        assert!(is_word_char('ć'));
+        assert!(is_word_char('ę'));
+        assert!(is_word_char('ł'));
+        assert!(is_word_char('ń'));
+        assert!(is_word_char('ó'));
+        assert!(is_word_char('ś'));
+        assert!(is_word_char('ź'));
+        assert!(is_word_char('ż'));
+        assert!(is_word_char('Ż'));
+    }
+
+    #[test]
+    fn test_extract_words() {
+        let source: Vec<char> = "cześć świat".chars().collect();
+        let words = extract_words(&source);
+        assert_eq!(words.len(), 2);
+
+        let word1: String = words[0].0.iter().collect();
+        assert_eq!(word1, "cześć");
+        assert_eq!(words[0].1, 0); // start
+        assert_eq!(words[0].2, 5); // end
+
+        let word2: String = words[1].0.iter().collect();
+        assert_eq!(word2, "świat");
+    }
+
+    #[test]
+    fn test_extract_words_with_punctuation() {
+        let source: Vec<char> = "Dzień, dobry!".chars().collect();
+        let words = extract_words(&source);
+        assert_eq!(words.len(), 2);
+
+        let word1: String = words[0].0.iter().collect();
+        assert_eq!(word1, "Dzień");
+
+        let word2: String = words[1].0.iter().collect();
+        assert_eq!(word2, "dobry");
+    }
+
+    #[test]
+    fn test_extract_hyphenated_words() {
+        let text = "Bielsko-Biała, biało-czerwony - a nie -kot- 2-gi 1-2 'Johnny'ego' rock'n";
+        let source: Vec<char> = text.chars().collect();
+        let words: Vec<String> = extract_words(&source)
+            .into_iter()
+            .map(|(word, _, _)| word.iter().collect())
+            .collect();
+        assert_eq!(
+            words,
+            [
+                "Bielsko-Biała",
+                "biało-czerwony",
+                "a",
+                "nie",
+                "kot",
+                "2-gi",
+                "1",
+                "2",
+                "Johnny'ego",
+                "rock",
+                "n"
+            ]
+        );
+        assert_eq!(word_bounds(&source, 3), Some((0, 13)));
+        // The letters of a word with digits, the whole word without letters
+        assert_eq!(word_bounds(&source, 44), Some((46, 48)));
+        assert_eq!(word_bounds(&source, 49), Some((49, 50)));
+    }
+
+    fn test_checker(words: &[&str], settings: Settings) -> SpellChecker {
+        let mut dictionary = SimpleDictionary::new();
+        for word in words {
+            dictionary.add_word(word, false);
+        }
+        SpellChecker {
+            dictionary: SharedDictionary::new(dictionary),
+            settings: Arc::new(RwLock::new(settings)),
+            stats: Arc::new(Mutex::new(CheckStats::default())),
+            session_ignored: Arc::new(RwLock::new(HashSet::new())),
+            replacements: Arc::new(Replacements::new()),
+            rules: Arc::new(Rules::new()),
+            progress: ProgressReporter::default(),
+        }
+    }
+
+    fn test_document(text: &str) -> DocumentState {
+        let uri: Uri = "file:///test.md".parse().unwrap();
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        DocumentState::new(&uri, source, line_index, "markdown".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_abbreviations_are_not_flagged() {
+        let checker = test_checker(&["jest", "tutaj"], Settings::default());
+        let doc = test_document("Prof. Nowak jest tutaj, mgr Kowalski tzw. gość, dyr. prof");
+        let messages: Vec<String> = checker
+            .check(&doc)
+            .await
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unknown word: 'Nowak'",
+                "Unknown word: 'Kowalski'",
+                "Unknown word: 'gość'",
+                "Unknown word: 'prof'",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_abbreviation_period_rule() {
+        let mut settings = Settings::default();
+        settings.rules.missing_abbreviation_period = true;
+        let checker = test_checker(&["jest", "kot"], settings);
+        let doc = test_document("jest np kot, np. kot, prof kot, mgr kot");
+        let messages: Vec<String> = checker
+            .check(&doc)
+            .await
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Missing period after abbreviation: 'np'",
+                "Missing period after abbreviation: 'prof'",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replacement_rules() {
+        let mut checker = test_checker(&["na", "jest"], Settings::default());
+        let mut replacements = Replacements::new();
+        replacements.parse("wogóle\tw ogóle\nna prawdę\tnaprawdę\n");
+        checker.replacements = Arc::new(replacements);
+        let doc = test_document("Wogóle jest na prawdę");
+
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Incorrect form: 'Wogóle' (write 'w ogóle')",
+                "Incorrect form: 'na prawdę' (write 'naprawdę')",
+            ]
+        );
+        let edits: Vec<String> = fix_all_edits(&doc, &diagnostics)
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(edits, vec!["W ogóle", "naprawdę"]);
+
+        let mut settings = Settings::default();
+        settings.rules.replacements = false;
+        *checker.settings.write().await = settings;
+        assert_eq!(checker.check(&doc).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_spacing() {
+        let checker = test_checker(&["kot", "pies", "jest"], Settings::default());
+        let doc = test_document("Kot  jest ,pies");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["Double space", "Space before ','", "Missing space after ','"]
+        );
+        assert_eq!(diagnostics[0].code_description, None);
+        let edits: Vec<String> = fix_all_edits(&doc, &diagnostics)
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(edits, vec![" ", "", ", "]);
+
+        let mut settings = Settings::default();
+        settings.rules.spacing = false;
+        *checker.settings.write().await = settings;
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn initialized(&self, _: InitializedParams) {
-        eprintln!("[POLSKI-LS] initialized - server ready!");
-        self.client
-            .log_message(MessageType::INFO, "polski-ls initialized!")
-            .await;
+    #[tokio::test]
+    async fn test_straight_quotes() {
+        let checker = test_checker(&["mówi", "tak", "wpis"], Settings::default());
+        let text = "---\ntitle: \"Wpis\"\n---\nMówi \"tak\"";
+        let doc = test_document(text);
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Straight quotes: write „…”"]);
+        let edits: Vec<String> = fix_all_edits(&doc, &diagnostics)
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(edits, vec!["„tak”"]);
+
+        // In LaTeX a straight quote is a shorthand
+        let source: Vec<char> = "Mówi \"tak\"".chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///tekst.tex".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "latex".to_string());
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn shutdown(&self) -> JsonResult<()> {
-        Ok(())
+    #[tokio::test]
+    async fn test_dashes() {
+        let checker = test_checker(&["kot", "spał", "pies", "też"], Settings::default());
+        let text = "Kot - spał, pies -- też";
+        let diagnostics = checker.check(&test_document(text)).await;
+        let codes: Vec<_> = diagnostics.iter().filter_map(|d| d.code.clone()).collect();
+        assert_eq!(
+            codes,
+            vec![
+                NumberOrString::String("typography/hyphen-as-dash".to_string()),
+                NumberOrString::String("typography/double-hyphen".to_string()),
+            ]
+        );
+
+        // "--" is how LaTeX writes a dash
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///tekst.tex".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "latex".to_string());
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        eprintln!("[POLSKI-LS] did_open: {:?}", params.text_document.uri);
-        let source: Vec<char> = params.text_document.text.chars().collect();
-        let line_index = LineIndex::new(&source);
+    #[tokio::test]
+    async fn test_hanging_conjunctions() {
+        let mut settings = Settings::default();
+        let checker = test_checker(&["kot", "dom"], settings.clone());
+        let doc = test_document("Kot w dom");
+        assert!(checker.check(&doc).await.is_empty());
 
-        // Publish diagnostics before taking the lock to avoid holding it during async call
-        self.publish_diagnostics(&params.text_document.uri, &source, &line_index)
-            .await;
+        settings.rules.hanging_conjunctions = true;
+        *checker.settings.write().await = settings;
+        let diagnostics = checker.check(&doc).await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "'w' may end a line: bind it to the next word"
+        );
+        // Left to the dedicated bulk action rather than fix-all spelling
+        assert!(fix_all_edits(&doc, &diagnostics).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missing_commas() {
+        let checker = test_checker(&["wiem", "mimo", "że", "pada"], Settings::default());
+        let doc = test_document("Wiem mimo że pada");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Missing comma before 'mimo że'"]);
+        let edits: Vec<String> = fix_all_edits(&doc, &diagnostics)
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(edits, vec![", "]);
+    }
 
-        let mut documents = self.documents.lock().await;
-        documents.insert(
-            params.text_document.uri,
-            DocumentState { source, line_index },
+    #[tokio::test]
+    async fn test_pattern_rules() {
+        let mut checker = test_checker(&["ona", "wróciła", "z", "powrotem"], Settings::default());
+        let mut rules = Rules::new();
+        rules.parse(include_str!("../reguly.toml"));
+        checker.rules = Arc::new(rules);
+        let doc = test_document("Ona wróciła z powrotem");
+        let diagnostics = checker.check(&doc).await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("grammar/wracac-z-powrotem".to_string()))
         );
+        let edits: Vec<String> = fix_all_edits(&doc, &diagnostics)
+            .into_iter()
+            .map(|edit| edit.new_text)
+            .collect();
+        assert_eq!(edits, vec!["wróciła"]);
+
+        // The grammar category and the rule switch turn them off
+        let mut settings = Settings::default();
+        settings.categories.grammar.enabled = false;
+        *checker.settings.write().await = settings;
+        assert!(checker.check(&doc).await.is_empty());
+        let mut settings = Settings::default();
+        settings.rules.pattern_rules = false;
+        *checker.settings.write().await = settings;
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn did_change(&self, params: DidChangeTextDocumentParams) {
-        eprintln!("[POLSKI-LS] did_change: {:?}", params.text_document.uri);
-        let Some(last) = params.content_changes.last() else {
-            return;
-        };
+    #[tokio::test]
+    async fn test_repeated_words() {
+        let checker = test_checker(&["kot", "się", "zgubił"], Settings::default());
+        let doc = test_document("Kot się się zgubił, kott kott");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Repeated word: 'się'",
+                "Unknown word: 'kott'",
+                "Unknown word: 'kott'"
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("grammar/repeated-word".to_string()))
+        );
+        let edits = fix_all_edits(&doc, &diagnostics[..1]);
+        assert_eq!(edits[0].range.start, Position::new(0, 7));
+        assert_eq!(edits[0].range.end, Position::new(0, 11));
+        assert_eq!(edits[0].new_text, "");
 
-        let source: Vec<char> = last.text.chars().collect();
-        let line_index = LineIndex::new(&source);
+        let mut settings = Settings::default();
+        settings.rules.repeated_words = false;
+        *checker.settings.write().await = settings;
+        assert_eq!(checker.check(&doc).await.len(), 2);
+    }
 
-        // Publish diagnostics before taking the lock
-        self.publish_diagnostics(&params.text_document.uri, &source, &line_index)
-            .await;
+    #[tokio::test]
+    async fn test_ignored_words_are_not_flagged() {
+        let checker = test_checker(&["jest"], Settings::default());
+        let mut doc = test_document("Nowak jest, NOWAK i Kowalski");
+        doc.ignored_words.insert("nowak".to_string());
+        let messages: Vec<String> = checker
+            .check(&doc)
+            .await
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(messages, vec!["Unknown word: 'Kowalski'"]);
+    }
+
+    #[tokio::test]
+    async fn test_session_ignored_words_are_not_flagged() {
+        let checker = test_checker(&["jest"], Settings::default());
+        checker
+            .session_ignored
+            .write()
+            .await
+            .insert("kowalski".to_string());
+        let doc = test_document("Nowak jest, Kowalski też");
+        let messages: Vec<String> = checker
+            .check(&doc)
+            .await
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(messages, vec!["Unknown word: 'Nowak'", "Unknown word: 'też'"]);
+    }
 
-        let mut documents = self.documents.lock().await;
-        documents.insert(
-            params.text_document.uri,
-            DocumentState { source, line_index },
+    #[tokio::test]
+    async fn test_check_diagnostic_codes() {
+        let checker = test_checker(&["żaba", "kot"], Settings::default());
+        let doc = test_document("zaba i kott");
+        let diagnostics = checker.check(&doc).await;
+        let codes: Vec<_> = diagnostics.iter().filter_map(|d| d.code.clone()).collect();
+        assert_eq!(
+            codes,
+            vec![
+                NumberOrString::String("spelling/missing-diacritics".to_string()),
+                NumberOrString::String("spelling/unknown-word".to_string()),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "Missing diacritics: 'zaba' (did you mean 'żaba'?)"
         );
+        let href = &diagnostics[1].code_description.as_ref().unwrap().href;
+        assert_eq!(href.as_str(), "https://sjp.pl/kott");
     }
 
-    async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        // Clear diagnostics for closed document
-        self.client
-            .publish_diagnostics(params.text_document.uri.clone(), vec![], None)
-            .await;
+    #[tokio::test]
+    async fn test_check_diagnostic_settings() {
+        let mut settings = Settings::default();
+        settings.diagnostics.min_word_length = 5;
+        settings.diagnostics.severity = Severity::Warning;
+        let checker = test_checker(&["jest"], settings.clone());
+        let doc = test_document("Kott jest xyzxyz");
+        let diagnostics = checker.check(&doc).await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
 
-        let mut documents = self.documents.lock().await;
-        documents.remove(&params.text_document.uri);
+        settings.rules.unknown_words = false;
+        let checker = test_checker(&["jest"], settings);
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn completion(&self, params: CompletionParams) -> JsonResult<Option<CompletionResponse>> {
-        let pos = params.text_document_position.position;
-        eprintln!("[POLSKI-LS] completion: pos={}:{}", pos.line, pos.character);
+    #[tokio::test]
+    async fn test_check_category_settings() {
+        let mut settings = Settings::default();
+        settings.rules.missing_abbreviation_period = true;
+        settings.categories.typography.severity = Some(Severity::Warning);
+        let checker = test_checker(&["jest"], settings.clone());
+        let doc = test_document("jest np kott");
+        let diagnostics = checker.check(&doc).await;
+        let reported: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.code.clone().unwrap(), d.severity.unwrap()))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                (
+                    NumberOrString::String("typography/missing-abbreviation-period".to_string()),
+                    DiagnosticSeverity::WARNING
+                ),
+                (
+                    NumberOrString::String("spelling/unknown-word".to_string()),
+                    DiagnosticSeverity::HINT
+                ),
+            ]
+        );
 
-        let items = self
-            .generate_completions(
-                &params.text_document_position.text_document.uri,
-                params.text_document_position.position,
-            )
-            .await?;
+        settings.categories.spelling.enabled = false;
+        let checker = test_checker(&["jest"], settings);
+        assert_eq!(checker.check(&doc).await.len(), 1);
+    }
 
-        eprintln!("[POLSKI-LS] returning {} completions", items.len());
-        if !items.is_empty() {
-            let labels: Vec<_> = items.iter().take(5).map(|i| &i.label).collect();
-            eprintln!("[POLSKI-LS] top 5: {:?}", labels);
-        }
+    #[tokio::test]
+    async fn test_check_stops_when_superseded() {
+        let checker = test_checker(&["dom"], Settings::default());
+        let doc = test_document("Dom kotek piesek.");
+        assert_eq!(checker.check(&doc).await.len(), 2);
 
-        if items.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(CompletionResponse::List(CompletionList {
-                is_incomplete: true,
-                items,
-            })))
-        }
+        doc.clone().supersede();
+        assert!(doc.is_superseded());
+        assert!(checker.check(&doc).await.is_empty());
     }
 
-    async fn code_action(&self, params: CodeActionParams) -> JsonResult<Option<CodeActionResponse>> {
-        let uri = &params.text_document.uri;
-        let range = params.range;
+    #[tokio::test]
+    async fn test_check_attaches_suggestions() {
+        let checker = test_checker(&["jest", "kot", "pies"], Settings::default());
+        let doc = test_document("Kott jest, kott i xyzxyz");
+        let suggestions: Vec<Vec<String>> = checker
+            .check(&doc)
+            .await
+            .iter()
+            .map(|d| DiagnosticData::from_diagnostic(d).unwrap().suggestions)
+            .collect();
+        assert_eq!(suggestions, vec![vec!["kot"], vec!["kot"], vec![]]);
+    }
 
-        eprintln!(
-            "[POLSKI-LS] code_action: {:?} at {}:{}-{}:{}",
-            uri, range.start.line, range.start.character, range.end.line, range.end.character
+    #[tokio::test]
+    async fn test_check_hyphenated_compounds() {
+        let words = ["biało", "czerwony", "polsko", "niemiecki", "Bielsko-Biała"];
+        let checker = test_checker(&words, Settings::default());
+        let doc = test_document("Biało-czerwony polsko-niemiecki Bielsko-Biala biało-czerwny");
+        let diagnostics = checker.check(&doc).await;
+        let found: Vec<(&str, Vec<String>)> = diagnostics
+            .iter()
+            .map(|d| {
+                let suggestions = DiagnosticData::from_diagnostic(d).unwrap().suggestions;
+                (d.message.as_str(), suggestions)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "Missing diacritics: 'Bielsko-Biala' (did you mean 'Bielsko-Biała'?)",
+                    vec!["Bielsko-Biała".to_string()]
+                ),
+                (
+                    "Unknown word: 'biało-czerwny'",
+                    vec!["biało-czerwony".to_string()]
+                ),
+            ]
         );
+    }
 
-        let documents = self.documents.lock().await;
-        let Some(doc_state) = documents.get(uri) else {
-            return Ok(None);
-        };
+    #[tokio::test]
+    async fn test_check_apostrophe_endings() {
+        let checker = test_checker(&["iPhone", "ego", "mam", "nie"], Settings::default());
+        let doc = test_document("Mam iPhone’a, nie iPhoen'a i Johnny'ego.");
+        let diagnostics = checker.check(&doc).await;
+        let found: Vec<(&str, Vec<String>)> = diagnostics
+            .iter()
+            .map(|d| {
+                let suggestions = DiagnosticData::from_diagnostic(d).unwrap().suggestions;
+                (d.message.as_str(), suggestions)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Unknown word: 'iPhoen'a'", vec!["iPhone'a".to_string()]),
+                ("Unknown word: 'Johnny'ego'", vec![]),
+            ]
+        );
+    }
 
-        let source = &doc_state.source;
-        let line_index = &doc_state.line_index;
+    #[tokio::test]
+    async fn test_check_alphanumeric_words() {
+        let text = "Wirus COVID-19, plik MPEG4 i film 3D, H2O, 5-latek, 2024 r. i Wirsu-19";
+        let words = ["wirus", "covid", "plik", "film", "latek"];
+        let mut settings = Settings::default();
+        let checker = test_checker(&words, settings.clone());
+        let diagnostics = checker.check(&test_document(text)).await;
+        assert!(diagnostics.is_empty());
 
-        // Find the word at the cursor position
-        let start_idx = line_index.position_to_index(source, range.start);
+        settings.diagnostics.alphanumeric_words = AlphanumericWords::Core;
+        let checker = test_checker(&words, settings);
+        let diagnostics = checker.check(&test_document(text)).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'MPEG'", "Unknown word: 'Wirsu'"]);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 21));
+        assert_eq!(diagnostics[0].range.end, Position::new(0, 25));
+    }
 
-        // Find word boundaries
-        let mut word_start = start_idx;
-        while word_start > 0 && is_word_char(source[word_start - 1]) {
-            word_start -= 1;
-        }
+    #[tokio::test]
+    async fn test_symbols_are_not_flagged() {
+        let checker = test_checker(&["wiek", "moc", "sieć"], Settings::default());
+        let doc = test_document("XXI wiek, moc 3 kWh, sieć 400 kV i 5 GHz, XIIII wiek, MHZ");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'XIIII'", "Unknown word: 'MHZ'"]);
+    }
 
-        let mut word_end = start_idx;
-        while word_end < source.len() && is_word_char(source[word_end]) {
-            word_end += 1;
-        }
+    #[tokio::test]
+    async fn test_addresses_are_not_flagged() {
+        let checker = test_checker(&["zobacz", "pisz", "edytuj"], Settings::default());
+        let text = "Zobacz https://github.com/kaarrot, pisz jan@gmail.com, edytuj src/lib.rs teraz";
+        let diagnostics = checker.check(&test_document(text)).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'teraz'"]);
+    }
 
-        if word_start == word_end {
-            return Ok(None);
-        }
+    #[tokio::test]
+    async fn test_latex_markup_is_not_flagged() {
+        let checker = test_checker(&["wstęp", "jak", "wzór", "teza"], Settings::default());
+        let text = "\\section{Wstęp} Jak w \\cite{kowalsky}, wzór $\\alpha$ \\emph{tezza}.";
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///praca.tex".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "latex".to_string());
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'tezza'"]);
 
-        let word: Vec<char> = source[word_start..word_end].to_vec();
-        let word_string: String = word.iter().collect();
+        // Other documents are checked as they are
+        let diagnostics = checker.check(&test_document(text)).await;
+        assert!(diagnostics.len() > 1);
+    }
 
-        // Check if word is unknown
-        let dictionary = self.dictionary.lock().await;
-        if dictionary.contains(&word) {
-            return Ok(None);
-        }
+    #[tokio::test]
+    async fn test_org_markup_is_not_flagged() {
+        let checker = test_checker(&["notatki", "zadanie", "kod"], Settings::default());
+        let text = "* TODO Zadanie :pilne:\n\
+            :PROPERTIES:\n:CUSTOM_ID: zadanie-jeden\n:END:\n\
+            #+BEGIN_SRC python\nprint('hello')\n#+END_SRC\n\
+            [[file:notatki.org][Notatkii]] i kod";
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///notatki.org".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "org".to_string());
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'Notatkii'"]);
+    }
 
-        eprintln!("[POLSKI-LS] Generating suggestions for: '{}'", word_string);
+    #[tokio::test]
+    async fn test_po_checks_only_translations() {
+        let checker = test_checker(&["witaj", "plików"], Settings::default());
+        let text = "# Komentarz tłumacza\nmsgid \"Hello, %s\"\nmsgstr \"Witaj, %s\"\n\n\
+            msgid \"%d files\"\nmsgstr \"%d plików w {dirname}\\nkatalogu\"";
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///pl.po".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "po".to_string());
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'katalogu'"]);
+    }
 
-        // Get fuzzy matches for suggestions
-        let max_edit_distance = if word.len() <= 3 { 1 } else { 2 };
-        let fuzzy_matches = dictionary.fuzzy_match(&word, max_edit_distance, 10);
+    #[cfg(feature = "tree-sitter")]
+    #[tokio::test]
+    async fn test_source_code_checks_comments_and_strings() {
+        let checker = test_checker(&["zwraca", "wynik", "dodawania"], Settings::default());
+        let text = "/// Zwraca wynik dodawnia.\nfn suma(a: u32) -> u32 { a + 1 } // wynik";
+        let source: Vec<char> = text.chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let uri: Uri = "file:///lib.rs".parse().unwrap();
+        let doc = DocumentState::new(&uri, source, line_index, "rust".to_string());
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'dodawnia'"]);
+    }
 
-        if fuzzy_matches.is_empty() {
-            return Ok(None);
-        }
+    #[test]
+    fn test_camel_case_parts() {
+        let parts = |word: &str| -> Vec<String> {
+            let chars: Vec<char> = word.chars().collect();
+            camel_case_parts(&chars)
+                .into_iter()
+                .map(|(start, end)| chars[start..end].iter().collect())
+                .collect()
+        };
+        assert_eq!(parts("mojaFunkcjaPomocnicza"), ["moja", "Funkcja", "Pomocnicza"]);
+        assert_eq!(parts("HTTPSerwerŻądań"), ["HTTP", "Serwer", "Żądań"]);
+        assert_eq!(parts("Bielsko-Biała"), ["Bielsko-Biała"]);
+        assert_eq!(parts("NATO"), ["NATO"]);
+        assert_eq!(parts("iPhone"), ["iPhone"]);
+    }
 
-        let word_range = Range {
-            start: line_index.index_to_position(source, word_start),
-            end: line_index.index_to_position(source, word_end),
+    #[tokio::test]
+    async fn test_identifiers_are_checked_by_parts() {
+        let words = ["moja", "funkcja", "pomocnicza", "wywołaj"];
+        let checker = test_checker(&words, Settings::default());
+        let doc = test_document("Wywołaj mojaFunkcjaPomocnicza, moja_funkcja, doFunkcjja");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'Funkcjja'"]);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 47));
+    }
+
+    #[tokio::test]
+    async fn test_front_matter_is_skipped() {
+        let checker = test_checker(&["wpis", "treść"], Settings::default());
+        let doc = test_document("---\ntitle: Mój wpis\nslug: moj-wpis\n---\nTreść wpisu");
+        let diagnostics = checker.check(&doc).await;
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unknown word: 'Mój'", "Unknown word: 'wpisu'"]);
+    }
+
+    #[tokio::test]
+    async fn test_fix_all_edits() {
+        let checker = test_checker(&["jest", "kot", "pies"], Settings::default());
+        let doc = test_document("Kott jest, kott i piess, prof xyzxyz");
+        let diagnostics = checker.check(&doc).await;
+
+        let edits = fix_all_edits(&doc, &diagnostics);
+        let texts: Vec<&str> = edits.iter().map(|e| e.new_text.as_str()).collect();
+        assert_eq!(texts, vec!["Kot", "kot", "pies"]);
+        assert_eq!(edits[1].range.start, Position::new(0, 11));
+    }
+
+    #[test]
+    fn test_occurrences_ignore_case() {
+        let source: Vec<char> = "Kot i kotek, KOT oraz kot.".chars().collect();
+        let word: Vec<char> = "kot".chars().collect();
+        assert_eq!(occurrences(&source, &word), vec![(0, 3), (13, 16), (22, 25)]);
+    }
+
+    #[test]
+    fn test_rename_edits_keep_casing() {
+        let source: Vec<char> = "Pomylka tutaj. Kolejna pomylka!\nPOMYLKA".chars().collect();
+        let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let word: Vec<char> = "pomylka".chars().collect();
+
+        let edits = rename_edits(&source, &line_index, &word, "pomyłka");
+        let texts: Vec<&str> = edits.iter().map(|e| e.new_text.as_str()).collect();
+        assert_eq!(texts, vec!["Pomyłka", "pomyłka", "POMYŁKA"]);
+        assert_eq!(edits[1].range.start, Position::new(0, 23));
+        assert_eq!(edits[2].range.start, Position::new(1, 0));
+    }
+
+    #[test]
+    fn test_untitled_plaintext_detects_language() {
+        let doc = |uri: &str, language_id: &str, text: &str| {
+            let uri: Uri = uri.parse().unwrap();
+            let source: Vec<char> = text.chars().collect();
+            let line_index = LineIndex::new(&source, PositionEncoding::Utf16);
+            DocumentState::new(&uri, source, line_index, language_id.to_string())
         };
 
-        let mut actions: Vec<CodeActionOrCommand> = Vec::new();
+        let text = "Some English notes here.\n\nTo jest polski tekst, więc go sprawdzamy.";
+        let scratch = doc("untitled:Untitled-1", "", text);
+        let regions = scratch.polish_regions();
+        assert!(!scratch.is_checked(&regions, 0));
+        assert!(scratch.is_checked(&regions, 30));
 
-        // Add "Add to dictionary" action first
-        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
-            title: format!("Add '{}' to dictionary", word_string),
-            kind: Some(CodeActionKind::QUICKFIX),
-            diagnostics: None,
-            edit: None,
-            command: Some(Command {
-                title: format!("Add '{}' to dictionary", word_string),
-                command: CMD_ADD_TO_DICTIONARY.to_string(),
-                arguments: Some(vec![serde_json::json!({
-                    "word": word_string,
-                    "uri": uri.to_string()
-                })]),
-            }),
-            ..Default::default()
-        }));
+        let markdown = doc("untitled:Untitled-2", "markdown", text);
+        assert!(markdown.is_checked(&markdown.polish_regions(), 0));
 
-        for m in fuzzy_matches {
-            let suggestion_str: String = m.word.iter().collect();
-            let suggestion = apply_capitalization(&word, &suggestion_str);
+        let file = doc("file:///notes.txt", "plaintext", text);
+        assert!(file.is_checked(&file.polish_regions(), 0));
+    }
 
-            let mut changes = HashMap::new();
-            changes.insert(
-                uri.clone(),
-                vec![TextEdit {
-                    range: word_range,
-                    new_text: suggestion.clone(),
-                }],
-            );
+    #[test]
+    fn test_is_dictionary_file() {
+        let user_dir = Path::new("/home/user/.config/polski-ls");
+        let dictionaries = vec![PathBuf::from("/work/slownik-projektu.txt")];
+        let is_dictionary =
+            |path: &str| is_dictionary_file(Path::new(path), Some(user_dir), &dictionaries);
 
-            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
-                title: format!("Change to '{}'", suggestion),
-                kind: Some(CodeActionKind::QUICKFIX),
-                diagnostics: None,
-                edit: Some(WorkspaceEdit {
-                    changes: Some(changes),
-                    ..Default::default()
-                }),
-                ..Default::default()
-            }));
-        }
+        assert!(is_dictionary("/home/user/.config/polski-ls/slownik.txt"));
+        assert!(is_dictionary("/home/user/.config/polski-ls/nazwiska.txt"));
+        assert!(!is_dictionary("/home/user/.config/polski-ls/config.toml"));
+        assert!(is_dictionary("/work/slownik-projektu.txt"));
+        assert!(!is_dictionary("/work/notatki.txt"));
+    }
 
-        eprintln!("[POLSKI-LS] Returning {} code actions", actions.len());
-        Ok(Some(actions))
+    #[test]
+    fn test_word_bounds() {
+        let source: Vec<char> = "Dzień dobry!".chars().collect();
+        assert_eq!(word_bounds(&source, 0), Some((0, 5)));
+        assert_eq!(word_bounds(&source, 5), Some((0, 5)));
+        assert_eq!(word_bounds(&source, 8), Some((6, 11)));
+        assert_eq!(word_bounds(&source, 12), None);
+        assert_eq!(word_bounds(&[], 0), None);
     }
 
-    async fn execute_command(&self, params: ExecuteCommandParams) -> JsonResult<Option<serde_json::Value>> {
-        eprintln!("[POLSKI-LS] execute_command: {}", params.command);
+    #[test]
+    fn test_hover_markdown() {
+        let analyses = vec![Analysis {
+            lemma: "dzień".to_string(),
+            part_of_speech: "rzeczownik".to_string(),
+        }];
+        let info = Some(WordInfo {
+            is_common: true,
+            rank: None,
+            is_user: false,
+            is_project: false,
+        });
+        assert_eq!(
+            hover_markdown("dnia", &analyses, &[], info),
+            "**dnia**\n\n- Lemma: **dzień** (rzeczownik)\n- Common word"
+        );
+        assert!(hover_markdown("xyz", &[], &[], None).ends_with("- Not in dictionary"));
+        assert!(
+            hover_markdown("dnia", &analyses, &["day", "of the day"], info)
+                .contains("- English: *day; of the day*\n- Common word")
+        );
+        let ranked = info.map(|info| WordInfo {
+            rank: Some(431),
+            ..info
+        });
+        assert!(hover_markdown("dnia", &[], &[], ranked).ends_with("- Frequency rank: 431"));
+    }
 
-        if params.command == CMD_ADD_TO_DICTIONARY {
-            if let Some(arg) = params.arguments.first() {
-                if let (Some(word), Some(uri_str)) = (
-                    arg.get("word").and_then(|v| v.as_str()),
-                    arg.get("uri").and_then(|v| v.as_str()),
-                ) {
-                    eprintln!("[POLSKI-LS] Adding word to dictionary: '{}'", word);
-
-                    // Add word to dictionary
-                    let mut dictionary = self.dictionary.lock().await;
-                    if let Err(e) = dictionary.add_user_word(word) {
-                        eprintln!("[POLSKI-LS] Error adding word to dictionary: {}", e);
-                        self.client
-                            .show_message(MessageType::ERROR, format!("Failed to add word to dictionary: {}", e))
-                            .await;
-                        return Ok(None);
-                    }
-                    drop(dictionary);
-
-                    // Show success message
-                    self.client
-                        .show_message(MessageType::INFO, format!("Added '{}' to dictionary", word))
-                        .await;
-
-                    // Refresh diagnostics for the document
-                    if let Ok(uri) = uri_str.parse::<Uri>() {
-                        let documents = self.documents.lock().await;
-                        if let Some(doc_state) = documents.get(&uri) {
-                            let source = doc_state.source.clone();
-                            let line_index = doc_state.line_index.clone();
-                            drop(documents);
-
-                            self.publish_diagnostics(&uri, &source, &line_index).await;
-                        }
-                    }
-                }
+    #[test]
+    fn test_number_markdown() {
+        assert_eq!(
+            number_markdown("1 234", 1234, Case::Nominative),
+            "**1 234**\n\n- In words: *tysiąc dwieście trzydzieści cztery*"
+        );
+        assert!(number_markdown("5", 5, Case::Genitive)
+            .ends_with("- In words: *pięciu*\n- Nominative: *pięć*"));
+    }
+
+    /// Top-1 and top-3 accuracy of code action suggestions on testdata/suggestions.tsv.
+    /// Guards matcher and scoring changes against silently degrading corrections.
+    #[test]
+    #[cfg(feature = "embedded-dict")]
+    fn test_suggestion_quality_corpus() {
+        let dictionary = SimpleDictionary::embedded();
+        let weights = ScoringWeights::default();
+        let corpus: Vec<(&str, &str)> = include_str!("../testdata/suggestions.tsv")
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('\t'))
+            .collect();
+
+        let (mut top1, mut top3) = (0, 0);
+        let mut misses = Vec::new();
+        for (misspelling, expected) in &corpus {
+            let word: Vec<char> = misspelling.chars().collect();
+            let ranked = suggest(&word, &dictionary, &weights, 3);
+
+            if ranked.first().is_some_and(|s| s == expected) {
+                top1 += 1;
+            } else {
+                misses.push(format!("{} -> {}: {:?}", misspelling, expected, ranked));
+            }
+            if ranked.iter().any(|s| s == expected) {
+                top3 += 1;
             }
         }
 
-        Ok(None)
+        // Floors just under the current results; raise them as matching improves
+        let total = corpus.len() as f64;
+        let (top1_rate, top3_rate) = (top1 as f64 / total, top3 as f64 / total);
+        assert!(corpus.len() >= 50, "corpus too small: {}", corpus.len());
+        assert!(
+            top1_rate >= 0.96,
+            "top-1 {:.2}, misses: {:?}",
+            top1_rate,
+            misses
+        );
+        assert!(
+            top3_rate >= 0.98,
+            "top-3 {:.2}, misses: {:?}",
+            top3_rate,
+            misses
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_document_words() {
+        let source: Vec<char> = "Kowalczyk i kowalczyk, Kowal, kowalski 2024 kow".chars().collect();
+        let prefix: Vec<char> = "kow".chars().collect();
+        assert_eq!(
+            document_words(&source, &prefix, 44),
+            vec![
+                ("Kowalczyk".to_string(), 2),
+                ("Kowal".to_string(), 1),
+                ("kowalski".to_string(), 1),
+            ]
+        );
+        assert!(document_words(&source, &"2024".chars().collect::<Vec<_>>(), 44).is_empty());
+    }
 
     #[test]
-    fn test_is_word_char_ascii() {
-        assert!(is_word_char('a'));
-        assert!(is_word_char('Z'));
-        assert!(is_word_char('5'));
-        assert!(!is_word_char(' '));
-        assert!(!is_word_char('.'));
-        assert!(!is_word_char('\n'));
+    fn test_word_index() {
+        let doc = test_document("Kowalczyk pisze, kowalczyk czyta 2024 razy.");
+        let index = doc.word_index();
+        assert_eq!(index["kowalczyk"], ("Kowalczyk".to_string(), 2));
+        assert_eq!(index["razy"], ("razy".to_string(), 1));
+        assert!(!index.contains_key("2024"));
     }
 
     #[test]
-    fn test_is_word_char_polish() {
-        assert!(is_word_char('ą'));
-        assert!(is_word_char('Ą'));
-        assert!(is_word_char('ć'));
-        assert!(is_word_char('ę'));
-        assert!(is_word_char('ł'));
-        assert!(is_word_char('ń'));
-        assert!(is_word_char('ó'));
-        assert!(is_word_char('ś'));
-        assert!(is_word_char('ź'));
-        assert!(is_word_char('ż'));
-        assert!(is_word_char('Ż'));
+    fn test_phrase_starts() {
+        let source: Vec<char> = "Tak. Ala w związku  z t".chars().collect();
+        // Two spaces end the phrase
+        assert_eq!(phrase_starts(&source, 22), vec![20]);
+        assert_eq!(phrase_starts(&source, 11), vec![9, 5]);
+        assert!(phrase_starts(&source, 5).is_empty());
     }
 
     #[test]
-    fn test_apply_capitalization_lowercase() {
-        let original: Vec<char> = "słodko".chars().collect();
-        assert_eq!(apply_capitalization(&original, "słodki"), "słodki");
+    fn test_commit_characters() {
+        let client = |name: &str| ClientInfo {
+            name: name.to_string(),
+            version: None,
+        };
+        let space = Some(vec![" ".to_string()]);
+        assert_eq!(commit_characters(None, None), space);
+        assert_eq!(commit_characters(None, Some(&client("helix"))), space);
+        assert_eq!(commit_characters(None, Some(&client("Visual Studio Code"))), None);
+        assert_eq!(commit_characters(Some(Vec::new()), None), None);
+        let configured = Some(vec![" ".to_string(), ",".to_string()]);
+        assert_eq!(
+            commit_characters(configured.clone(), Some(&client("Zed"))),
+            configured
+        );
     }
 
     #[test]
-    fn test_apply_capitalization_uppercase() {
-        let original: Vec<char> = "Słodko".chars().collect();
-        assert_eq!(apply_capitalization(&original, "słodki"), "Słodki");
+    fn test_previous_word() {
+        let source: Vec<char> = "Dzień  dob\nKot, mi".chars().collect();
+        assert_eq!(previous_word(&source, 7).as_deref(), Some("dzień"));
+        assert_eq!(previous_word(&source, 0), None);
+        // Punctuation ends the context
+        assert_eq!(previous_word(&source, 16), None);
     }
 
     #[test]
-    fn test_apply_capitalization_polish_uppercase() {
-        let original: Vec<char> = "Żółty".chars().collect();
-        assert_eq!(apply_capitalization(&original, "żółw"), "Żółw");
+    fn test_sjp_url() {
+        assert_eq!(sjp_url("dom"), "https://sjp.pl/dom");
+        assert_eq!(sjp_url("Żółw"), "https://sjp.pl/%C5%BC%C3%B3%C5%82w");
     }
 
     #[test]
-    fn test_apply_capitalization_empty() {
-        let original: Vec<char> = Vec::new();
-        assert_eq!(apply_capitalization(&original, "test"), "test");
+    fn test_completion_documentation() {
+        let data = serde_json::json!({ "word": "dzień", "editDistance": 1, "common": true });
+        let doc = completion_documentation(&data);
+        assert!(doc.starts_with("**dzień**"));
+        assert!(doc.contains("common word"));
+        assert!(doc.contains("Edit distance from typed text: 1"));
+        assert!(doc.contains("https://sjp.pl/dzie%C5%84"));
+
+        let data = serde_json::json!({ "word": "Kowalczyk", "editDistance": 0, "occurrences": 3 });
+        let doc = completion_documentation(&data);
+        assert!(doc.contains("used 3 times in this document"));
+        let data = serde_json::json!({
+            "word": "Kowalczyk",
+            "source": OPEN_DOCUMENTS_SOURCE,
+            "occurrences": 2,
+        });
+        let doc = completion_documentation(&data);
+        assert!(doc.contains("used 2 times in other open documents"));
+        let data = serde_json::json!({ "word": "np.", "expansion": "na przykład" });
+        assert_eq!(completion_documentation(&data), "**np.**\n\n- Abbreviation of *na przykład*");
     }
 
     #[test]
-    fn test_extract_words() {
-        let source: Vec<char> = "cześć świat".chars().collect();
-        let words = extract_words(&source);
-        assert_eq!(words.len(), 2);
+    fn test_label_details() {
+        let data = serde_json::json!({ "word": "dzień", "editDistance": 1, "common": true });
+        let details = label_details(&data);
+        assert_eq!(details.detail.as_deref(), Some(" ~1 edit"));
+        assert_eq!(details.description.as_deref(), Some("★ dictionary"));
 
-        let word1: String = words[0].0.iter().collect();
-        assert_eq!(word1, "cześć");
-        assert_eq!(words[0].1, 0); // start
-        assert_eq!(words[0].2, 5); // end
+        let data = serde_json::json!({ "word": "kubernetes", "dictionary": "user" });
+        let details = label_details(&data);
+        assert_eq!(details.detail, None);
+        assert_eq!(details.description.as_deref(), Some("user dictionary"));
 
-        let word2: String = words[1].0.iter().collect();
-        assert_eq!(word2, "świat");
+        let data = serde_json::json!({ "word": "Kowalczyk", "source": DOCUMENT_SOURCE });
+        assert_eq!(label_details(&data).description.as_deref(), Some("this document"));
     }
 
     #[test]
-    fn test_extract_words_with_punctuation() {
-        let source: Vec<char> = "Dzień, dobry!".chars().collect();
-        let words = extract_words(&source);
-        assert_eq!(words.len(), 2);
+    fn test_string_argument() {
+        let arguments = vec![serde_json::json!({ "word": "kot", "count": 1 })];
+        assert_eq!(string_argument(&arguments, "word").unwrap(), "kot");
 
-        let word1: String = words[0].0.iter().collect();
-        assert_eq!(word1, "Dzień");
+        let err = string_argument(&arguments, "count").unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidParams);
+        assert!(string_argument(&[], "word").is_err());
+    }
 
-        let word2: String = words[1].0.iter().collect();
-        assert_eq!(word2, "dobry");
+    #[test]
+    fn test_diagnostics_result_id() {
+        let diagnostic = |message: &str| Diagnostic {
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let a = diagnostics_result_id(&[diagnostic("Unknown word: 'kot'")]);
+        let b = diagnostics_result_id(&[diagnostic("Unknown word: 'kot'")]);
+        let c = diagnostics_result_id(&[diagnostic("Unknown word: 'pies'")]);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, diagnostics_result_id(&[]));
     }
 
     #[test]
     fn test_calculate_completion_score_exact_match() {
         let query: Vec<char> = "test".chars().collect();
         let candidate: Vec<char> = "test".chars().collect();
-        let score = calculate_completion_score(&query, &candidate, 0, false);
+        let weights = ScoringWeights::default();
+        let score = calculate_completion_score(&query, &candidate, 0, 0.0, &weights);
         // 100 (base) + 50 (first letter) + 32 (4 chars prefix match * 8)
         assert_eq!(score, 182.0);
     }
@@ -644,8 +4655,9 @@ mod tests {
     fn test_calculate_completion_score_common_word_bonus() {
         let query: Vec<char> = "test".chars().collect();
         let candidate: Vec<char> = "test".chars().collect();
-        let score_common = calculate_completion_score(&query, &candidate, 0, true);
-        let score_normal = calculate_completion_score(&query, &candidate, 0, false);
+        let weights = ScoringWeights::default();
+        let score_common = calculate_completion_score(&query, &candidate, 0, 1.0, &weights);
+        let score_normal = calculate_completion_score(&query, &candidate, 0, 0.0, &weights);
         assert_eq!(score_common - score_normal, 35.0);
     }
 
@@ -653,10 +4665,93 @@ mod tests {
     fn test_calculate_completion_score_edit_distance_penalty() {
         let query: Vec<char> = "test".chars().collect();
         let candidate: Vec<char> = "tест".chars().collect();
-        let score_0 = calculate_completion_score(&query, &candidate, 0, false);
-        let score_1 = calculate_completion_score(&query, &candidate, 1, false);
-        let score_2 = calculate_completion_score(&query, &candidate, 2, false);
+        let weights = ScoringWeights::default();
+        let score_0 = calculate_completion_score(&query, &candidate, 0, 0.0, &weights);
+        let score_1 = calculate_completion_score(&query, &candidate, 1, 0.0, &weights);
+        let score_2 = calculate_completion_score(&query, &candidate, 2, 0.0, &weights);
         assert!(score_0 > score_1);
         assert!(score_1 > score_2);
     }
+
+    #[test]
+    fn test_neighbouring_key_typos_rank_higher() {
+        let query: Vec<char> = "bardzp".chars().collect();
+        let weights = ScoringWeights::default();
+        let score = |candidate: &str| {
+            let candidate: Vec<char> = candidate.chars().collect();
+            calculate_completion_score(&query, &candidate, 1, 0.0, &weights)
+        };
+        // 'p' is next to 'o' but far from 'y'
+        assert!(score("bardzo") > score("bardzy"));
+        assert_eq!(distance_penalty(0.5, &weights.distance_penalties), 10.0);
+        assert_eq!(distance_penalty(3.0, &weights.distance_penalties), 100.0);
+    }
+
+    #[test]
+    fn test_frequency_weight() {
+        let result = |is_common: bool, rank: Option<u32>| FuzzyMatchResult {
+            word: Vec::new(),
+            edit_distance: 0,
+            is_common,
+            rank,
+        };
+        assert_eq!(frequency_weight(&result(true, None)), 1.0);
+        assert_eq!(frequency_weight(&result(false, None)), 0.0);
+        assert_eq!(frequency_weight(&result(false, Some(1))), 1.0);
+        assert_eq!(frequency_weight(&result(true, Some(1_000_000))), 0.0);
+        // A ranked list grades words instead of splitting them in two
+        let frequent = frequency_weight(&result(false, Some(100)));
+        assert!(frequent > frequency_weight(&result(false, Some(5000))));
+    }
+
+    #[test]
+    fn test_rank_suggestions_best_first() {
+        let result = |word: &str, edit_distance: u8, is_common: bool| FuzzyMatchResult {
+            word: word.chars().collect(),
+            edit_distance,
+            is_common,
+            rank: None,
+        };
+        let word: Vec<char> = "domk".chars().collect();
+        let matches = vec![
+            result("tom", 2, false),
+            result("dom", 1, false),
+            result("domek", 1, true),
+        ];
+        let ranked = rank_suggestions(&word, matches, &ScoringWeights::default());
+        let words: Vec<String> = ranked.iter().map(|m| m.word.iter().collect()).collect();
+        assert_eq!(words, vec!["domek", "dom", "tom"]);
+    }
+
+    #[test]
+    fn test_calculate_completion_score_custom_weights() {
+        let query: Vec<char> = "test".chars().collect();
+        let candidate: Vec<char> = "test".chars().collect();
+        let weights = ScoringWeights {
+            first_letter_bonus: 0.0,
+            prefix_weight: 1.0,
+            ..ScoringWeights::default()
+        };
+        let score = calculate_completion_score(&query, &candidate, 0, 0.0, &weights);
+        assert_eq!(score, 104.0);
+    }
+
+    #[test]
+    fn test_merge_spans() {
+        // Addresses and markup come as two separately sorted lists
+        let spans = vec![10..12, 30..35, 0..4, 11..15, 15..18, 20..22, 32..33];
+        assert_eq!(merge_spans(spans), vec![0..4, 10..18, 20..22, 30..35]);
+        assert!(merge_spans(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_session_exit_code() {
+        let session = Session {
+            shutdown_received: Arc::new(AtomicBool::new(false)),
+            usage: Arc::new(Mutex::new(UsageStats::new())),
+        };
+        assert_eq!(session.exit_code(), ExitCode::FAILURE);
+        session.shutdown_received.store(true, Ordering::Relaxed);
+        assert_eq!(session.exit_code(), ExitCode::SUCCESS);
+    }
 }
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::dictionary::{user_config_dir, WordListError};
+
+/// File name of the optional bigram counts in ~/.config/polski-ls/.
+pub const BIGRAMS_FILE: &str = "bigramy.tsv";
+
+/// Optional bigram language model: how often each word follows another, used to rank
+/// completions by the word before the cursor ("dobry" rather than "dobrym" after "dzień").
+///
+/// Source format is one pair per line: `previous<TAB>word<TAB>count`, with `#` comments;
+/// a missing count counts as 1 and repeated pairs add up.
+#[derive(Debug, Default)]
+pub struct Bigrams {
+    /// Lowercase previous word -> counts of the lowercase words following it, and the
+    /// largest of those counts.
+    followers: HashMap<String, (HashMap<String, u32>, u32)>,
+}
+
+impl Bigrams {
+    /// Create an empty model.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load ~/.config/polski-ls/bigramy.tsv; the model stays empty if it doesn't exist.
+    pub fn load() -> Self {
+        let mut bigrams = Self::new();
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(BIGRAMS_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading bigrams: {:?}", path);
+                bigrams.parse(&content);
+            }
+        }
+
+        bigrams
+    }
+
+    /// Add counts from bigram source text.
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let Some((previous, word, count)) = parse_line(line) else {
+                continue;
+            };
+            let count = count.unwrap_or(1);
+            let (counts, most) = self.followers.entry(previous.to_lowercase()).or_default();
+            let total = counts.entry(word.to_lowercase()).or_default();
+            *total = total.saturating_add(count);
+            *most = (*most).max(*total);
+        }
+    }
+
+    /// How strongly `previous` predicts `word` (both lowercase), from 0 (never seen
+    /// together) to 1 (its most frequent follower).
+    pub fn affinity(&self, previous: &str, word: &str) -> f32 {
+        let Some((counts, most)) = self.followers.get(previous) else {
+            return 0.0;
+        };
+        match counts.get(word) {
+            Some(&count) if *most > 0 => count as f32 / *most as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+/// The previous word, the word and the count of a bigram line, `None` for comments,
+/// blank lines and lines without both words. A count that isn't a number is treated as
+/// missing; `validate_bigrams` reports it.
+fn parse_line(line: &str) -> Option<(&str, &str, Option<u32>)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let mut fields = trimmed.split('\t').map(str::trim);
+    let previous = fields.next().filter(|field| !field.is_empty())?;
+    let word = fields.next().filter(|field| !field.is_empty())?;
+    let count = fields.next().and_then(|count| count.parse().ok());
+    Some((previous, word, count))
+}
+
+/// Validate bigram source text, reporting lines without two words or with a bad count.
+pub fn validate_bigrams(content: &str) -> Vec<WordListError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .filter_map(|(idx, line)| {
+            let fields: Vec<&str> = line.trim().split('\t').map(str::trim).collect();
+            let message = match fields.as_slice() {
+                [previous, word, ..] if previous.is_empty() || word.is_empty() => {
+                    format!("empty word in '{}'", line.trim())
+                }
+                [_, _] => return None,
+                [_, _, count] if count.parse::<u32>().is_ok() => return None,
+                [_, _, count] => format!("invalid count '{}'", count),
+                _ => format!(
+                    "expected previous<TAB>word<TAB>count, found '{}'",
+                    line.trim()
+                ),
+            };
+            Some(WordListError {
+                line: idx + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affinity() {
+        let mut bigrams = Bigrams::new();
+        bigrams.parse("# comment\ndzień\tdobry\t120\nDzień\tdobrym\t3\ndzień\tdobry\t30\n");
+        bigrams.parse("dobry\twieczór\n");
+
+        assert_eq!(bigrams.affinity("dzień", "dobry"), 1.0);
+        assert_eq!(bigrams.affinity("dzień", "dobrym"), 0.02);
+        assert_eq!(bigrams.affinity("dobry", "wieczór"), 1.0);
+        assert_eq!(bigrams.affinity("dzień", "kot"), 0.0);
+        assert_eq!(bigrams.affinity("noc", "dobra"), 0.0);
+    }
+
+    #[test]
+    fn test_validate_bigrams() {
+        let content = "# comment\ndzień\tdobry\t120\ndobry\twieczór\n\
+                       dzień dobry\n\tkot\nkot\tma\tdużo\n";
+        let errors = validate_bigrams(content);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![4, 5, 6]);
+    }
+}
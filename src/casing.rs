@@ -0,0 +1,275 @@
+use crate::abbreviations;
+
+/// Letter-case pattern to reproduce on a replacement word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Casing {
+    /// Keep the suggestion as stored in the dictionary.
+    Lower,
+    /// Uppercase the first letter ("Dzień").
+    Capitalized,
+    /// Uppercase every letter ("DZIEŃ"), e.g. in headings.
+    Upper,
+}
+
+/// Apply capitalization from original word to suggestion.
+/// An all-caps original ("WARSZ") uppercases the whole suggestion, an uppercase first
+/// letter capitalizes it, and in mixed case ("McD") the uppercase letters are copied.
+pub fn apply_capitalization(original: &[char], suggestion: &str) -> String {
+    match word_casing(original) {
+        Some(casing) => apply_casing(casing, suggestion),
+        None => copy_letter_case(original, suggestion),
+    }
+}
+
+/// The casing `word` is written in on its own, `None` for mixed case ("McDonald").
+/// A single capital letter counts as capitalized, not all-caps.
+fn word_casing(word: &[char]) -> Option<Casing> {
+    let letters = word.iter().filter(|c| c.is_alphabetic()).count();
+    let uppercase = word.iter().filter(|c| c.is_uppercase()).count();
+    if uppercase == 0 {
+        Some(Casing::Lower)
+    } else if letters > 1 && uppercase == letters {
+        Some(Casing::Upper)
+    } else if uppercase == 1 && word.first().is_some_and(|c| c.is_uppercase()) {
+        Some(Casing::Capitalized)
+    } else {
+        None
+    }
+}
+
+/// Uppercase the letters of `suggestion` where `original` has uppercase ones. Letters
+/// already uppercase in the suggestion stay so.
+fn copy_letter_case(original: &[char], suggestion: &str) -> String {
+    let mut result = String::with_capacity(suggestion.len());
+    for (idx, ch) in suggestion.chars().enumerate() {
+        if original.get(idx).is_some_and(|c| c.is_uppercase()) {
+            result.extend(ch.to_uppercase());
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Rewrite `suggestion` to follow the given casing.
+pub fn apply_casing(casing: Casing, suggestion: &str) -> String {
+    match casing {
+        Casing::Lower => suggestion.to_string(),
+        Casing::Capitalized => {
+            let mut chars = suggestion.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        Casing::Upper => suggestion.to_uppercase(),
+    }
+}
+
+/// Determine the casing for one occurrence of a word at `source[start..end]`.
+///
+/// Each occurrence is judged on its own, so bulk fixes keep headings in capitals,
+/// capitalize sentence starts and leave mid-sentence words alone.
+pub fn occurrence_casing(source: &[char], start: usize, end: usize) -> Casing {
+    let word = &source[start..end];
+    let letters = word.iter().filter(|c| c.is_alphabetic()).count();
+    let all_upper = letters > 0 && word.iter().all(|c| !c.is_lowercase());
+
+    if all_upper && (letters > 1 || in_uppercase_line(source, start, end)) {
+        Casing::Upper
+    } else if word.first().is_some_and(|c| c.is_uppercase()) || is_sentence_start(source, start) {
+        Casing::Capitalized
+    } else {
+        Casing::Lower
+    }
+}
+
+/// Apply the casing of the occurrence at `source[start..end]` to `suggestion`; mixed case
+/// is copied letter by letter.
+pub fn apply_occurrence_casing(
+    source: &[char],
+    start: usize,
+    end: usize,
+    suggestion: &str,
+) -> String {
+    let word = &source[start..end];
+    match word_casing(word) {
+        None => copy_letter_case(word, suggestion),
+        Some(_) => apply_casing(occurrence_casing(source, start, end), suggestion),
+    }
+}
+
+/// True if the word starts the document, a line-separated paragraph or a sentence.
+fn is_sentence_start(source: &[char], start: usize) -> bool {
+    let mut idx = start;
+    let mut saw_newline = false;
+    while idx > 0 {
+        let ch = source[idx - 1];
+        if ch == '\n' {
+            if saw_newline {
+                return true; // blank line: new paragraph
+            }
+            saw_newline = true;
+        } else if !ch.is_whitespace() && !matches!(ch, '"' | '„' | '”' | '«' | '»' | '(') {
+            return match ch {
+                '.' => !ends_abbreviation(&source[..idx - 1]),
+                '!' | '?' | '…' => true,
+                _ => false,
+            };
+        }
+        idx -= 1;
+    }
+    true
+}
+
+/// True if a period after `before` belongs to an abbreviation ("np.", "m.in.") or an
+/// ordinal number ("3.") rather than ending a sentence.
+fn ends_abbreviation(before: &[char]) -> bool {
+    let token_start = before
+        .iter()
+        .rposition(|c| !c.is_alphanumeric() && *c != '.')
+        .map_or(0, |idx| idx + 1);
+    let token = &before[token_start..];
+    if token.is_empty() {
+        return false;
+    }
+    token.contains(&'.')
+        || token.iter().all(|c| c.is_ascii_digit())
+        || abbreviations::find(token).is_some_and(|a| a.needs_period)
+}
+
+/// True if the line containing the word has several letters, none of them lowercase.
+/// Used to tell a one-letter word in an all-caps heading ("W DOMU") from a capitalized one.
+fn in_uppercase_line(source: &[char], start: usize, end: usize) -> bool {
+    let line_start = source[..start]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |idx| idx + 1);
+    let line_end = source[end..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(source.len(), |idx| end + idx);
+
+    let line = &source[line_start..line_end];
+    let letters = line.iter().filter(|c| c.is_alphabetic()).count();
+    letters > 1 && line.iter().all(|c| !c.is_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn casing_of(text: &str, word: &str) -> Casing {
+        let source: Vec<char> = text.chars().collect();
+        let byte_start = text.find(word).unwrap();
+        let start = text[..byte_start].chars().count();
+        occurrence_casing(&source, start, start + word.chars().count())
+    }
+
+    #[test]
+    fn test_apply_capitalization_lowercase() {
+        let original: Vec<char> = "słodko".chars().collect();
+        assert_eq!(apply_capitalization(&original, "słodki"), "słodki");
+    }
+
+    #[test]
+    fn test_apply_capitalization_uppercase() {
+        let original: Vec<char> = "Słodko".chars().collect();
+        assert_eq!(apply_capitalization(&original, "słodki"), "Słodki");
+    }
+
+    #[test]
+    fn test_apply_capitalization_polish_uppercase() {
+        let original: Vec<char> = "Żółty".chars().collect();
+        assert_eq!(apply_capitalization(&original, "żółw"), "Żółw");
+    }
+
+    #[test]
+    fn test_apply_capitalization_empty() {
+        let original: Vec<char> = Vec::new();
+        assert_eq!(apply_capitalization(&original, "test"), "test");
+    }
+
+    #[test]
+    fn test_apply_capitalization_all_caps() {
+        let original: Vec<char> = "WARSZWA".chars().collect();
+        assert_eq!(apply_capitalization(&original, "warszawa"), "WARSZAWA");
+        let original: Vec<char> = "ŻÓŁ".chars().collect();
+        assert_eq!(apply_capitalization(&original, "żółć"), "ŻÓŁĆ");
+        // One capital letter starts a capitalized word
+        let original: Vec<char> = "Ż".chars().collect();
+        assert_eq!(apply_capitalization(&original, "żółw"), "Żółw");
+    }
+
+    #[test]
+    fn test_apply_capitalization_mixed_case() {
+        let original: Vec<char> = "McD".chars().collect();
+        assert_eq!(apply_capitalization(&original, "mcdonald"), "McDonald");
+        let original: Vec<char> = "bIELSKO-bIAŁA".chars().collect();
+        assert_eq!(
+            apply_capitalization(&original, "Bielsko-Biała"),
+            "BIELSKO-BIAŁA"
+        );
+        let original: Vec<char> = "iPhon".chars().collect();
+        assert_eq!(apply_capitalization(&original, "iphone"), "iPhone");
+    }
+
+    #[test]
+    fn test_apply_casing_upper_polish() {
+        assert_eq!(apply_casing(Casing::Upper, "źrebak"), "ŹREBAK");
+        assert_eq!(apply_casing(Casing::Capitalized, "ąć"), "Ąć");
+        assert_eq!(apply_casing(Casing::Capitalized, ""), "");
+    }
+
+    #[test]
+    fn test_occurrence_casing_mid_sentence() {
+        assert_eq!(casing_of("Mam nowy dom.", "nowy"), Casing::Lower);
+        assert_eq!(
+            casing_of("Jadę do Krakowa.", "Krakowa"),
+            Casing::Capitalized
+        );
+    }
+
+    #[test]
+    fn test_occurrence_casing_sentence_start() {
+        assert_eq!(casing_of("dzien dobry", "dzien"), Casing::Capitalized);
+        assert_eq!(casing_of("Tak. dzien dobry", "dzien"), Casing::Capitalized);
+        assert_eq!(casing_of("Tak, dzien dobry", "dzien"), Casing::Lower);
+        assert_eq!(casing_of("Tak?  „dzien”", "dzien"), Casing::Capitalized);
+        assert_eq!(
+            casing_of("koniec\n\nnowy akapit", "nowy"),
+            Casing::Capitalized
+        );
+        assert_eq!(casing_of("koniec\nnowy wiersz", "nowy"), Casing::Lower);
+    }
+
+    #[test]
+    fn test_occurrence_casing_after_abbreviation() {
+        assert_eq!(casing_of("kot, np. psies", "psies"), Casing::Lower);
+        assert_eq!(casing_of("ma m.in. kot", "kot"), Casing::Lower);
+        assert_eq!(casing_of("to prof. nowak", "nowak"), Casing::Lower);
+        assert_eq!(casing_of("Rozdział 3. kot", "kot"), Casing::Lower);
+        assert_eq!(casing_of("Mam psa. kot", "kot"), Casing::Capitalized);
+    }
+
+    #[test]
+    fn test_occurrence_casing_heading() {
+        assert_eq!(casing_of("ŻÓŁTY DOM", "ŻÓŁTY"), Casing::Upper);
+        assert_eq!(casing_of("tekst\nW DOMU\ntekst", "W"), Casing::Upper);
+        assert_eq!(casing_of("Mam psa. W domu", "W"), Casing::Capitalized);
+    }
+
+    #[test]
+    fn test_apply_occurrence_casing_per_occurrence() {
+        let text = "SWIAT\n\nswiat jest duży, cały swiat";
+        let source: Vec<char> = text.chars().collect();
+        let fixed: Vec<String> = [(0, 5), (7, 12), (28, 33)]
+            .iter()
+            .map(|&(s, e)| apply_occurrence_casing(&source, s, e, "świat"))
+            .collect();
+        assert_eq!(fixed, vec!["ŚWIAT", "Świat", "świat"]);
+
+        let source: Vec<char> = "Zamów ŁóDŹ".chars().collect();
+        assert_eq!(apply_occurrence_casing(&source, 6, 10, "łódź"), "ŁóDŹ");
+    }
+}
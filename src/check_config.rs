@@ -0,0 +1,151 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use crate::bigrams::{validate_bigrams, BIGRAMS_FILE};
+use crate::config::{read_settings_file, PROJECT_CONFIG_FILE, USER_CONFIG_FILE};
+use crate::dictionary::{
+    user_config_dir, user_dictionary_files, validate_word_list, DictionaryOptions, WordListError,
+    PROJECT_DICT_PATH,
+};
+use crate::expansions::{validate_expansions, EXPANSIONS_FILE};
+use crate::glossary::{validate_glossary, GLOSSARY_FILE};
+use crate::ignore::{IgnoreFile, IGNORE_FILE_NAME};
+use crate::morphology::{validate_morphology, USER_MORPHOLOGY_FILE};
+use crate::replacements::{validate_replacements, REPLACEMENTS_FILE};
+use crate::rules::{validate_rules, RULES_FILE};
+
+/// Validate all configured dictionaries and print problems as `path:line: message`.
+/// Returns a failing exit code when any problem was found.
+pub fn run(options: &DictionaryOptions) -> ExitCode {
+    let mut problems = 0;
+
+    #[cfg(feature = "embedded-dict")]
+    if options.embedded_enabled() {
+        problems += report(
+            "<embedded>/slowa.txt",
+            &validate_word_list(include_str!("../slowa.txt")),
+        );
+        problems += report(
+            "<embedded>/morfologia.tsv",
+            &validate_morphology(include_str!("../morfologia.tsv")),
+        );
+    }
+    if !options.embedded_enabled() {
+        problems += check_file(&options.system_dict_path, validate_word_list);
+    }
+    problems += report(
+        "<embedded>/skroty.tsv",
+        &validate_expansions(include_str!("../skroty.tsv")),
+    );
+    problems += report(
+        "<embedded>/zamiany.tsv",
+        &validate_replacements(include_str!("../zamiany.tsv")),
+    );
+    problems += report(
+        "<embedded>/reguly.toml",
+        &validate_rules(include_str!("../reguly.toml")),
+    );
+
+    match user_config_dir() {
+        Some(dir) if dir.is_dir() => {
+            for path in user_dictionary_files(&dir) {
+                problems += check_file(&path, validate_word_list);
+            }
+            let morphology = dir.join(USER_MORPHOLOGY_FILE);
+            if morphology.is_file() {
+                problems += check_file(&morphology, validate_morphology);
+            }
+            problems += check_settings_file(&dir.join(USER_CONFIG_FILE));
+            let glossary = dir.join(GLOSSARY_FILE);
+            if glossary.is_file() {
+                problems += check_file(&glossary, validate_glossary);
+            }
+            let bigrams = dir.join(BIGRAMS_FILE);
+            if bigrams.is_file() {
+                problems += check_file(&bigrams, validate_bigrams);
+            }
+            let expansions = dir.join(EXPANSIONS_FILE);
+            if expansions.is_file() {
+                problems += check_file(&expansions, validate_expansions);
+            }
+            let replacements = dir.join(REPLACEMENTS_FILE);
+            if replacements.is_file() {
+                problems += check_file(&replacements, validate_replacements);
+            }
+            let rules = dir.join(RULES_FILE);
+            if rules.is_file() {
+                problems += check_file(&rules, validate_rules);
+            }
+        }
+        Some(dir) => println!(
+            "{}: no user configuration directory, skipping",
+            dir.display()
+        ),
+        None => {
+            println!("error: could not determine config directory");
+            problems += 1;
+        }
+    }
+
+    let project_dict = Path::new(PROJECT_DICT_PATH);
+    if project_dict.is_file() {
+        problems += check_file(project_dict, validate_word_list);
+    }
+    problems += check_ignore_file();
+    problems += check_settings_file(Path::new(PROJECT_CONFIG_FILE));
+
+    if problems == 0 {
+        println!("configuration OK");
+        ExitCode::SUCCESS
+    } else {
+        println!("{} problem(s) found", problems);
+        ExitCode::FAILURE
+    }
+}
+
+/// Validate `.polski-ls-ignore` in the current directory, if present.
+fn check_ignore_file() -> usize {
+    let Ok(root) = std::env::current_dir() else {
+        return 0;
+    };
+    let path = root.join(IGNORE_FILE_NAME);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return 0;
+    };
+
+    let (_, errors) = IgnoreFile::parse(&root, &content);
+    for error in &errors {
+        println!("{}:{}: {}", path.display(), error.line, error.message);
+    }
+    errors.len()
+}
+
+/// Validate a TOML settings file, if present.
+fn check_settings_file(path: &Path) -> usize {
+    match read_settings_file(path) {
+        Ok(_) => 0,
+        Err(e) => {
+            println!("{}: {}", path.display(), e);
+            1
+        }
+    }
+}
+
+/// Read and validate one file, returning the number of problems.
+fn check_file(path: &Path, validate: fn(&str) -> Vec<WordListError>) -> usize {
+    match std::fs::read_to_string(path) {
+        Ok(content) => report(&path.display().to_string(), &validate(&content)),
+        Err(e) => {
+            println!("{}: cannot read file: {}", path.display(), e);
+            1
+        }
+    }
+}
+
+/// Print errors found in `name` and return how many there were.
+fn report(name: &str, errors: &[WordListError]) -> usize {
+    for error in errors {
+        println!("{}:{}: {}", name, error.line, error.message);
+    }
+    errors.len()
+}
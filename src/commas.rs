@@ -0,0 +1,156 @@
+use crate::diagnostics::MISSING_COMMA;
+use crate::typography::Finding;
+
+/// Words opening a subordinate clause, which a comma separates from the main one.
+const SUBORDINATORS: &[&str] = &[
+    "aby",
+    "ponieważ",
+    "że",
+    "który",
+    "która",
+    "które",
+    "którego",
+    "której",
+    "któremu",
+    "którym",
+    "którą",
+    "którzy",
+    "których",
+    "którymi",
+];
+
+/// Conjunctions joining two clauses of the same kind ("i że", "lub aby"), after which
+/// no comma is needed.
+const COORDINATORS: &[&str] = &["a", "albo", "ani", "bądź", "czy", "i", "lub", "oraz"];
+
+/// First words of compound conjunctions ("mimo że", "chyba że"), written together: the
+/// comma goes before the whole conjunction.
+const COMPOUND_STARTS: &[&str] = &[
+    "chyba",
+    "dlatego",
+    "mimo",
+    "pomimo",
+    "tak",
+    "tylko",
+    "tyle",
+    "zwłaszcza",
+];
+
+/// Prepositions taking a relative pronoun ("w którym"); the comma goes before them.
+const PREPOSITIONS: &[&str] = &[
+    "bez", "dla", "do", "dzięki", "między", "na", "nad", "o", "od", "po", "pod", "przed", "przez",
+    "przy", "u", "w", "we", "z", "za", "ze",
+];
+
+/// Subordinate clauses ("że", "który", "aby", "ponieważ") not separated by a comma from
+/// the word before them, or before their compound conjunction or preposition ("mimo
+/// że", "w którym"). The fix inserts the comma.
+pub fn missing(source: &[char]) -> Vec<Finding> {
+    let words = words(source);
+    let mut findings = Vec::new();
+    for (index, &(start, end)) in words.iter().enumerate() {
+        let word = lowercase(&source[start..end]);
+        if !SUBORDINATORS.contains(&word.as_str()) || index == 0 {
+            continue;
+        }
+        // The comma goes before a compound conjunction or a preposition taking "który"
+        let mut clause = index;
+        let (previous_start, previous_end) = words[index - 1];
+        let previous = lowercase(&source[previous_start..previous_end]);
+        let joined = if word.starts_with("któr") {
+            PREPOSITIONS
+        } else {
+            COMPOUND_STARTS
+        };
+        if joined.contains(&previous.as_str()) && separated_by_space(source, previous_end, start) {
+            clause = index - 1;
+        }
+        if clause == 0 {
+            continue;
+        }
+
+        let (before_end, clause_start) = (words[clause - 1].1, words[clause].0);
+        let before = lowercase(&source[words[clause - 1].0..before_end]);
+        if COORDINATORS.contains(&before.as_str())
+            || !separated_by_space(source, before_end, clause_start)
+        {
+            continue;
+        }
+        let gap: String = source[before_end..clause_start].iter().collect();
+        findings.push(Finding {
+            start: before_end,
+            end: clause_start,
+            code: MISSING_COMMA,
+            message: format!(
+                "Missing comma before '{}'",
+                source[clause_start..end].iter().collect::<String>()
+            ),
+            replacement: format!(",{}", gap),
+        });
+    }
+    findings
+}
+
+/// Spans of the words of `source`.
+fn words(source: &[char]) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        if !source[i].is_alphabetic() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < source.len() && source[i].is_alphabetic() {
+            i += 1;
+        }
+        words.push((start, i));
+    }
+    words
+}
+
+/// Whether only whitespace, within one paragraph, lies between `start` and `end`.
+fn separated_by_space(source: &[char], start: usize, end: usize) -> bool {
+    let gap = &source[start..end];
+    !gap.is_empty()
+        && gap.iter().all(|c| c.is_whitespace())
+        && gap.iter().filter(|&&c| c == '\n').count() < 2
+}
+
+fn lowercase(word: &[char]) -> String {
+    word.iter().flat_map(|c| c.to_lowercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typography::tests::apply;
+
+    #[test]
+    fn test_missing_commas() {
+        assert_eq!(
+            apply(
+                "Wiem że przyjdzie. Dom który stoi. Idę aby zdążyć. Idę\nponieważ tak.",
+                missing
+            ),
+            "Wiem, że przyjdzie. Dom, który stoi. Idę, aby zdążyć. Idę,\nponieważ tak."
+        );
+    }
+
+    #[test]
+    fn test_compound_conjunctions_and_prepositions() {
+        assert_eq!(
+            apply(
+                "Wyszedł mimo że padało. Dom w którym mieszkam. Przyjdę chyba że nie.",
+                missing
+            ),
+            "Wyszedł, mimo że padało. Dom, w którym mieszkam. Przyjdę, chyba że nie."
+        );
+    }
+
+    #[test]
+    fn test_correct_sentences_are_left_alone() {
+        let text = "Wiem, że tak i że nie. Że też! Dom, w którym, lub który. Mimo\n\nże";
+        assert_eq!(apply(text, missing), text);
+    }
+}
@@ -0,0 +1,295 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use memmap2::Mmap;
+
+use crate::dictionary::parse_words;
+
+/// `slowa.txt` compiled by build.rs.
+#[cfg(feature = "embedded-dict")]
+pub static EMBEDDED: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/slowa.bin"));
+
+/// First bytes of a compiled word list. build.rs writes the same format.
+const MAGIC: &[u8; 8] = b"PLSKDICT";
+/// Bumped whenever the layout below changes; files of another version are ignored.
+pub const FORMAT_VERSION: u32 = 2;
+/// Magic, version and word count.
+const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
+
+/// Flag of a common word (`*` in the text).
+pub const COMMON: u8 = 1;
+/// Flag of a word from one of the user's dictionaries.
+pub const USER: u8 = 2;
+/// Flag of a word followed by its frequency rank.
+const RANKED: u8 = 4;
+
+/// A word list compiled ahead of time (`polski-ls compile-dictionary`), read through a
+/// memory mapping so startup skips parsing the text and server instances reading the
+/// same file share its pages.
+///
+/// Layout, integers little-endian: the magic, the format version (u32), the word count
+/// (u32), then per word a flags byte (`COMMON`, `USER`, `RANKED`), its length in bytes
+/// (u16), its UTF-8 spelling and, if `RANKED`, its frequency rank (u32).
+pub struct CompiledWordList {
+    map: Mmap,
+    count: usize,
+}
+
+impl CompiledWordList {
+    /// Map the file at `path` and check its header and records.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        // SAFETY: the mapping is read-only and the file is only ever replaced, never
+        // written in place, by `write_compiled`
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| e.to_string())?;
+        let count = validate(&map)?;
+        Ok(Self { map, count })
+    }
+
+    /// Number of words in the list.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Every word with its flags and frequency rank, in file order.
+    pub fn words(&self) -> impl Iterator<Item = (&str, u8, Option<u32>)> {
+        Records(&self.map[HEADER_LEN..]).map(|record| record.expect("validated on open"))
+    }
+}
+
+/// Every word of the compiled list `bytes` with its flags and frequency rank, in order.
+#[cfg(feature = "embedded-dict")]
+pub fn words_in(bytes: &[u8]) -> Result<impl Iterator<Item = (&str, u8, Option<u32>)>, String> {
+    validate(bytes)?;
+    Ok(Records(&bytes[HEADER_LEN..]).map(|record| record.expect("validated above")))
+}
+
+/// Walks the records after the header.
+struct Records<'a>(&'a [u8]);
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<(&'a str, u8, Option<u32>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&flags, rest) = self.0.split_first()?;
+        let Some((len, rest)) = rest.split_first_chunk::<2>() else {
+            self.0 = &[];
+            return Some(Err("truncated record".to_string()));
+        };
+        let len = u16::from_le_bytes(*len) as usize;
+        let rank_len = if flags & RANKED != 0 { 4 } else { 0 };
+        if rest.len() < len + rank_len {
+            self.0 = &[];
+            return Some(Err("truncated record".to_string()));
+        }
+        let (word, rest) = rest.split_at(len);
+        let (rank, rest) = rest.split_at(rank_len);
+        self.0 = rest;
+        let rank = rank.try_into().ok().map(u32::from_le_bytes);
+        Some(
+            std::str::from_utf8(word)
+                .map(|word| (word, flags & !RANKED, rank))
+                .map_err(|e| e.to_string()),
+        )
+    }
+}
+
+/// Check the header and every record of `bytes`, returning the word count.
+fn validate(bytes: &[u8]) -> Result<usize, String> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err("not a compiled word list".to_string());
+    }
+    let field = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let version = field(MAGIC.len());
+    if version != FORMAT_VERSION {
+        return Err(format!(
+            "format version {}, expected {}",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    let count = field(MAGIC.len() + 4) as usize;
+    let mut found = 0;
+    for record in Records(&bytes[HEADER_LEN..]) {
+        record?;
+        found += 1;
+    }
+    if found != count {
+        return Err(format!("{} words, header says {}", found, count));
+    }
+    Ok(count)
+}
+
+/// Serialize `words` in the compiled format.
+pub fn encode<'a>(words: impl Iterator<Item = (&'a str, u8, Option<u32>)>) -> Vec<u8> {
+    let mut records = Vec::new();
+    let mut count: u32 = 0;
+    for (word, flags, rank) in words {
+        // Longer entries can't be real words; leaving them out keeps the length a u16
+        let Ok(len) = u16::try_from(word.len()) else {
+            continue;
+        };
+        let flags = if rank.is_some() {
+            flags | RANKED
+        } else {
+            flags
+        };
+        records.push(flags);
+        records.extend_from_slice(&len.to_le_bytes());
+        records.extend_from_slice(word.as_bytes());
+        if let Some(rank) = rank {
+            records.extend_from_slice(&rank.to_le_bytes());
+        }
+        count += 1;
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + records.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.extend_from_slice(&records);
+    bytes
+}
+
+/// Write `words` compiled to `path`. The file is written beside it and renamed into
+/// place, so a server mapping the old file keeps reading consistent contents.
+pub fn write_compiled<'a>(
+    path: &Path,
+    words: impl Iterator<Item = (&'a str, u8, Option<u32>)>,
+) -> std::io::Result<()> {
+    let temporary = path.with_extension("bin.tmp");
+    std::fs::write(&temporary, encode(words))?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Flags of a word that is common or not.
+pub fn common_flag(is_common: bool) -> u8 {
+    if is_common {
+        COMMON
+    } else {
+        0
+    }
+}
+
+/// Where the compiled form of the word list at `path` is looked for: beside it, with a
+/// `.bin` extension.
+pub fn compiled_path(path: &Path) -> PathBuf {
+    path.with_extension("bin")
+}
+
+/// Open the compiled form of the word list at `text_path` if there is one that is valid
+/// and not older than the text. Otherwise `None`, after logging why when it exists, so
+/// the text is parsed instead.
+pub fn open_for(text_path: &Path) -> Option<CompiledWordList> {
+    let path = compiled_path(text_path);
+    let compiled_modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let text_modified = std::fs::metadata(text_path).and_then(|m| m.modified());
+    if text_modified.is_ok_and(|modified| modified > compiled_modified) {
+        eprintln!(
+            "[POLSKI-LS] {:?} is older than {:?}, parsing the text",
+            path, text_path
+        );
+        return None;
+    }
+
+    match CompiledWordList::open(&path) {
+        Ok(list) => Some(list),
+        Err(e) => {
+            eprintln!("[POLSKI-LS] Ignoring compiled dictionary {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Compile the word list at `input` to `output`, for `polski-ls compile-dictionary`.
+pub fn run(input: &Path, output: &Path) -> ExitCode {
+    let content = match std::fs::read_to_string(input) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("{}: {}", input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let words =
+        parse_words(&content).map(|(word, is_common, rank)| (word, common_flag(is_common), rank));
+    match write_compiled(output, words) {
+        Ok(()) => {
+            println!("{} -> {}", input.display(), output.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {}", output.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [(&str, u8, Option<u32>); 3] = [
+        ("kot", COMMON, Some(1520)),
+        ("żółw", 0, None),
+        ("Warszawa", USER, Some(300)),
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-compiled-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("slowa.bin");
+        write_compiled(&path, WORDS.into_iter()).unwrap();
+
+        let list = CompiledWordList::open(&path).unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.words().collect::<Vec<_>>(), WORDS);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_for_skips_stale_files() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-stale-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("slowa.txt");
+        std::fs::write(&text, "kot\n").unwrap();
+        assert!(open_for(&text).is_none());
+
+        write_compiled(&compiled_path(&text), WORDS.into_iter()).unwrap();
+        assert_eq!(open_for(&text).unwrap().len(), 3);
+
+        // Editing the text after compiling it makes the compiled file stale
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&text)
+            .and_then(|file| file.set_modified(later))
+            .unwrap();
+        assert!(open_for(&text).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-dict")]
+    fn test_embedded_matches_text() {
+        let compiled: Vec<(&str, u8, Option<u32>)> = words_in(EMBEDDED).unwrap().collect();
+        let parsed: Vec<(&str, u8, Option<u32>)> = parse_words(include_str!("../slowa.txt"))
+            .map(|(word, is_common, rank)| (word, common_flag(is_common), rank))
+            .collect();
+        assert_eq!(compiled, parsed);
+    }
+
+    #[test]
+    fn test_rejects_other_versions_and_damage() {
+        let bytes = encode(WORDS.into_iter());
+        assert_eq!(validate(&bytes), Ok(3));
+
+        let mut other_version = bytes.clone();
+        other_version[MAGIC.len()] = 1;
+        assert!(validate(&other_version).unwrap_err().contains("version 1"));
+
+        assert!(validate(&bytes[..bytes.len() - 1]).is_err());
+        assert!(validate(b"kot\n*pies\n").is_err());
+    }
+}
@@ -0,0 +1,817 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tower_lsp_server::lsp_types::DiagnosticSeverity;
+
+use crate::diagnostics::Category;
+use crate::dictionary::PROJECT_DICT_PATH;
+use crate::keyboard::KeyboardLayout;
+use crate::latency::DEFAULT_MAX_RESULTS;
+
+/// Name of the configuration section clients use for our settings.
+pub const SECTION: &str = "polski-ls";
+
+/// Per-project settings file, looked up in the workspace root.
+pub const PROJECT_CONFIG_FILE: &str = ".polski-ls.toml";
+
+/// User settings file, looked up in the user config directory.
+pub const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Server settings, deserialized from the client's `polski-ls` configuration section.
+/// Missing fields keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub scoring: ScoringWeights,
+    pub completion: CompletionSettings,
+    pub diagnostics: DiagnosticSettings,
+    pub code_actions: CodeActionSettings,
+    pub reflow: ReflowSettings,
+    pub typography: TypographySettings,
+    pub rules: RuleSettings,
+    pub categories: CategorySettings,
+    pub dictionaries: DictionarySettings,
+    pub files: FileSettings,
+    pub front_matter: FrontMatterSettings,
+}
+
+impl Settings {
+    /// Parse settings sent by the client, accepting either the bare settings object
+    /// or one wrapped in a `polski-ls` section. Out-of-range values are clamped.
+    pub fn from_json(value: &Value) -> Result<Self, serde_json::Error> {
+        let mut settings = Self::deserialize(section(value))?;
+        settings.scoring = settings.scoring.clamped();
+        settings.completion = settings.completion.clamped();
+        settings.code_actions.max_suggestions = settings
+            .code_actions
+            .max_suggestions
+            .clamp(1, MAX_SUGGESTIONS_LIMIT);
+        settings.diagnostics.min_word_length = settings
+            .diagnostics
+            .min_word_length
+            .clamp(1, MAX_MIN_WORD_LENGTH);
+        settings.reflow.line_width = settings
+            .reflow
+            .line_width
+            .clamp(MIN_LINE_WIDTH, MAX_LINE_WIDTH);
+        Ok(settings)
+    }
+}
+
+/// The settings object itself, unwrapping a `polski-ls` section if there is one.
+fn section(value: &Value) -> &Value {
+    value.get(SECTION).unwrap_or(value)
+}
+
+/// Raw settings from each source, kept separately so one can change without losing the
+/// others. Later layers override earlier ones key by key.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsLayers {
+    /// From `~/.config/polski-ls/config.toml`; defaults shared by every editor.
+    pub user: Value,
+    /// From `initializationOptions` or `workspace/didChangeConfiguration`.
+    pub client: Value,
+    /// From the workspace's `.polski-ls.toml`.
+    pub project: Value,
+}
+
+impl SettingsLayers {
+    /// Merge the layers and parse the result.
+    pub fn resolve(&self) -> Result<Settings, serde_json::Error> {
+        let mut merged = Value::Object(Default::default());
+        for layer in [&self.user, &self.client, &self.project] {
+            merge(&mut merged, section(layer));
+        }
+        Settings::from_json(&merged)
+    }
+}
+
+/// Overlay `layer` onto `base`: objects are merged recursively, anything else replaces
+/// the old value. A `null` layer changes nothing.
+fn merge(base: &mut Value, layer: &Value) {
+    match (base, layer) {
+        (_, Value::Null) => {}
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                merge(base.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (base, layer) => *base = layer.clone(),
+    }
+}
+
+/// Read a TOML settings file, using the same keys as the client settings. Returns
+/// `Ok(None)` if the file does not exist.
+pub fn read_settings_file(path: &Path) -> Result<Option<Value>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let table: toml::Table = content.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    let value = serde_json::to_value(table).map_err(|e| e.to_string())?;
+    // Reject unusable files up front, so errors point at the file rather than the merge
+    Settings::from_json(&value).map_err(|e| e.to_string())?;
+    Ok(Some(value))
+}
+
+/// Which files are checked: by language, and by patterns relative to the workspace root
+/// in `.polski-ls-ignore` syntax.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FileSettings {
+    /// Language ids (`textDocument.languageId`) to check; `"*"` allows every language.
+    pub language_ids: Vec<String>,
+    /// If not empty, only files matching one of these are checked.
+    pub include: Vec<String>,
+    /// Files matching any of these are never checked.
+    pub exclude: Vec<String>,
+}
+
+/// Prose formats checked by default. The empty id covers clients that don't send one.
+const DEFAULT_LANGUAGE_IDS: &[&str] = &[
+    "",
+    "asciidoc",
+    "git-commit",
+    "gitcommit",
+    "latex",
+    "markdown",
+    "org",
+    "plaintext",
+    "po",
+    "restructuredtext",
+    "tex",
+    "text",
+];
+
+impl FileSettings {
+    pub fn allows_language(&self, language_id: &str) -> bool {
+        self.language_ids
+            .iter()
+            .any(|id| id == "*" || id == language_id)
+    }
+}
+
+impl Default for FileSettings {
+    fn default() -> Self {
+        Self {
+            language_ids: DEFAULT_LANGUAGE_IDS.iter().map(|id| id.to_string()).collect(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// Upper bound for `diagnostics.minWordLength`.
+const MAX_MIN_WORD_LENGTH: usize = 20;
+
+/// How spelling findings are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiagnosticSettings {
+    /// Shorter words are never flagged; there are too many false positives among them.
+    pub min_word_length: usize,
+    pub severity: Severity,
+    /// Documents longer than this many characters are not checked automatically;
+    /// 0 means no limit.
+    pub max_document_length: usize,
+    /// Milliseconds to wait after the last edit before re-checking a document; 0 checks
+    /// on every change.
+    pub debounce_ms: u64,
+    /// How words mixing digits and letters ("COVID-19", "MP3", "3D") are checked.
+    pub alphanumeric_words: AlphanumericWords,
+    /// Documents are only checked when `polski-ls.checkDocument` asks for it, never on
+    /// open or change.
+    pub on_demand: bool,
+}
+
+impl DiagnosticSettings {
+    /// Whether a document of `length` characters is too long for automatic checks.
+    pub fn exceeds_length(&self, length: usize) -> bool {
+        self.max_document_length > 0 && length > self.max_document_length
+    }
+
+    /// How long to wait after an edit before re-checking.
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+}
+
+impl Default for DiagnosticSettings {
+    fn default() -> Self {
+        Self {
+            min_word_length: 3,
+            severity: Severity::Hint,
+            max_document_length: 500_000,
+            debounce_ms: 300,
+            alphanumeric_words: AlphanumericWords::Skip,
+            on_demand: false,
+        }
+    }
+}
+
+/// Treatment of words mixing digits and letters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlphanumericWords {
+    /// Not checked at all: they are mostly names, models and units.
+    Skip,
+    /// Only the letters are checked, without leading and trailing digits ("COVID" in
+    /// "COVID-19"); words with digits between letters ("H2O") are skipped.
+    Core,
+}
+
+/// Diagnostic severity as written in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl Severity {
+    pub fn to_lsp(self) -> DiagnosticSeverity {
+        match self {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Information => DiagnosticSeverity::INFORMATION,
+            Severity::Hint => DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+/// Bounds for the completion settings.
+const MAX_RESULTS_LIMIT: usize = 500;
+const MAX_EDIT_DISTANCE_LIMIT: u8 = 3;
+const MAX_MIN_PREFIX_LENGTH: usize = 10;
+
+/// Completion limits. Lower ones keep completion responsive on slow machines, e.g. in
+/// remote containers.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CompletionSettings {
+    /// Most items returned per request; fewer while requests are slow.
+    pub max_results: usize,
+    /// Most typos tolerated in the typed prefix; prefixes of up to three letters
+    /// tolerate one at most.
+    pub max_edit_distance: u8,
+    /// Letters of a word that must be typed before single words are completed.
+    pub min_prefix_length: usize,
+    /// Characters that accept the selected completion when typed; `None` leaves the
+    /// choice to the server, which depends on the client. Only read on initialize.
+    pub commit_characters: Option<Vec<String>>,
+}
+
+impl CompletionSettings {
+    /// Bring out-of-range values within bounds.
+    fn clamped(self) -> Self {
+        Self {
+            max_results: self.max_results.clamp(1, MAX_RESULTS_LIMIT),
+            max_edit_distance: self.max_edit_distance.min(MAX_EDIT_DISTANCE_LIMIT),
+            min_prefix_length: self.min_prefix_length.clamp(1, MAX_MIN_PREFIX_LENGTH),
+            commit_characters: self.commit_characters,
+        }
+    }
+}
+
+impl Default for CompletionSettings {
+    fn default() -> Self {
+        Self {
+            max_results: DEFAULT_MAX_RESULTS,
+            max_edit_distance: 2,
+            min_prefix_length: 2,
+            commit_characters: None,
+        }
+    }
+}
+
+/// Upper bound for `codeActions.maxSuggestions`.
+const MAX_SUGGESTIONS_LIMIT: usize = 50;
+
+/// Quick-fix menu settings.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CodeActionSettings {
+    /// Maximum number of "Change to" suggestions, best first.
+    pub max_suggestions: usize,
+    /// Where the "Add to dictionary" action goes relative to the suggestions.
+    pub add_to_dictionary: MenuPlacement,
+}
+
+impl Default for CodeActionSettings {
+    fn default() -> Self {
+        Self {
+            max_suggestions: 10,
+            add_to_dictionary: MenuPlacement::Top,
+        }
+    }
+}
+
+/// Position of an action within the quick-fix menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuPlacement {
+    Top,
+    Bottom,
+}
+
+/// Locations of dictionary files.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DictionarySettings {
+    /// Project dictionary, relative to the workspace root.
+    pub project: String,
+    /// Additional read-only word lists; relative paths start at the workspace root.
+    pub extra: Vec<String>,
+}
+
+impl Default for DictionarySettings {
+    fn default() -> Self {
+        Self {
+            project: PROJECT_DICT_PATH.to_string(),
+            extra: Vec::new(),
+        }
+    }
+}
+
+/// Which checks run. Most are on by default; missing abbreviation periods and hanging
+/// conjunctions are opt-in.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RuleSettings {
+    /// Report words missing from the dictionaries.
+    pub unknown_words: bool,
+    /// Report abbreviations like "prof" or "np" written without their period.
+    pub missing_abbreviation_period: bool,
+    /// Report text matching the replacement rules ("wogóle" for "w ogóle").
+    pub replacements: bool,
+    /// Report words written twice in a row ("się się").
+    pub repeated_words: bool,
+    /// Report double spaces, spaces before punctuation and missing spaces after it.
+    pub spacing: bool,
+    /// Report straight quotes ("tak") instead of Polish ones („tak”).
+    pub quotes: bool,
+    /// Report hyphens ("tak - nie") and double hyphens ("tak -- nie") used as dashes.
+    pub dashes: bool,
+    /// Report single-letter words ("w", "i") followed by a breaking space, which typeset
+    /// text keeps off line ends.
+    pub hanging_conjunctions: bool,
+    /// Report subordinate clauses ("że", "który", "aby", "ponieważ") without a comma.
+    pub commas: bool,
+    /// Report text matching the grammar and style rules of reguly.toml files.
+    pub pattern_rules: bool,
+}
+
+impl Default for RuleSettings {
+    fn default() -> Self {
+        Self {
+            unknown_words: true,
+            missing_abbreviation_period: false,
+            replacements: true,
+            repeated_words: true,
+            spacing: true,
+            quotes: true,
+            dashes: true,
+            hanging_conjunctions: false,
+            commas: true,
+            pattern_rules: true,
+        }
+    }
+}
+
+/// Reporting of each category of checks, on top of the individual `rules`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CategorySettings {
+    /// Unknown words, missing diacritics and replacement rules.
+    pub spelling: CategoryReporting,
+    /// Punctuation and typesetting, such as abbreviation periods.
+    pub typography: CategoryReporting,
+    /// How words go together, such as a word repeated by mistake.
+    pub grammar: CategoryReporting,
+}
+
+impl CategorySettings {
+    pub fn get(&self, category: Category) -> CategoryReporting {
+        match category {
+            Category::Spelling => self.spelling,
+            Category::Typography => self.typography,
+            Category::Grammar => self.grammar,
+        }
+    }
+}
+
+/// Whether one category of checks is reported, and how severely.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CategoryReporting {
+    pub enabled: bool,
+    /// Overrides `diagnostics.severity` for this category.
+    pub severity: Option<Severity>,
+}
+
+impl CategoryReporting {
+    /// Severity of this category's findings, or `None` if they aren't reported.
+    pub fn severity(&self, default: Severity) -> Option<DiagnosticSeverity> {
+        self.enabled.then(|| self.severity.unwrap_or(default).to_lsp())
+    }
+}
+
+impl Default for CategoryReporting {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            severity: None,
+        }
+    }
+}
+
+/// Front matter at the top of Markdown documents.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FrontMatterSettings {
+    /// Keys whose values are checked; the rest of the front matter is skipped.
+    pub checked_keys: Vec<String>,
+}
+
+impl Default for FrontMatterSettings {
+    fn default() -> Self {
+        Self {
+            checked_keys: vec!["title".to_string(), "description".to_string()],
+        }
+    }
+}
+
+/// Bounds for `reflow.lineWidth`.
+const MIN_LINE_WIDTH: usize = 20;
+const MAX_LINE_WIDTH: usize = 1000;
+
+/// How the typography rules typeset text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TypographySettings {
+    /// Quotes used inside quotes.
+    pub nested_quotes: NestedQuotes,
+}
+
+/// Quotation marks for a quote within a quote: „Mówi ‚tak’” or „Mówi «tak»”.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedQuotes {
+    #[default]
+    Single,
+    Guillemets,
+}
+
+impl NestedQuotes {
+    /// The opening and closing marks.
+    pub fn marks(self) -> (char, char) {
+        match self {
+            NestedQuotes::Single => ('‚', '’'),
+            NestedQuotes::Guillemets => ('«', '»'),
+        }
+    }
+}
+
+/// Settings for the reflowParagraph command.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReflowSettings {
+    /// Maximum line length in characters.
+    pub line_width: usize,
+}
+
+impl Default for ReflowSettings {
+    fn default() -> Self {
+        Self { line_width: 80 }
+    }
+}
+
+/// Weights used to rank completion candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ScoringWeights {
+    /// Added when the first letter matches the typed prefix.
+    pub first_letter_bonus: f32,
+    /// Subtracted when the first letter differs.
+    pub first_letter_penalty: f32,
+    /// Added per matching leading character.
+    pub prefix_weight: f32,
+    /// Added for words marked common (`*`) in the dictionary.
+    pub common_bonus: f32,
+    /// Subtracted for edit distance 1, 2 and 3+.
+    pub distance_penalties: [f32; 3],
+    /// Layout whose neighbouring keys make a substitution count as less than a full edit.
+    pub keyboard_layout: KeyboardLayout,
+    /// Added, scaled by how strongly the preceding word predicts the candidate, when a
+    /// bigram model is loaded.
+    pub context_bonus: f32,
+    /// Added, scaled by how often the user accepted the candidate before, for words
+    /// learned from past completions.
+    pub usage_bonus: f32,
+    /// Added for words offered because they occur in the document but not the dictionary.
+    pub document_bonus: f32,
+    /// Added for words offered because they occur in another open document.
+    pub open_documents_bonus: f32,
+}
+
+/// Upper bound for any single weight; keeps one signal from drowning out the others.
+const MAX_WEIGHT: f32 = 500.0;
+
+impl ScoringWeights {
+    /// Clamp every weight into `0..=MAX_WEIGHT`, replacing NaN with the default.
+    pub fn clamped(self) -> Self {
+        let defaults = Self::default();
+        let clamp = |value: f32, default: f32| {
+            if value.is_nan() {
+                default
+            } else {
+                value.clamp(0.0, MAX_WEIGHT)
+            }
+        };
+
+        Self {
+            first_letter_bonus: clamp(self.first_letter_bonus, defaults.first_letter_bonus),
+            first_letter_penalty: clamp(self.first_letter_penalty, defaults.first_letter_penalty),
+            prefix_weight: clamp(self.prefix_weight, defaults.prefix_weight),
+            common_bonus: clamp(self.common_bonus, defaults.common_bonus),
+            distance_penalties: [0, 1, 2]
+                .map(|i| clamp(self.distance_penalties[i], defaults.distance_penalties[i])),
+            keyboard_layout: self.keyboard_layout,
+            context_bonus: clamp(self.context_bonus, defaults.context_bonus),
+            usage_bonus: clamp(self.usage_bonus, defaults.usage_bonus),
+            document_bonus: clamp(self.document_bonus, defaults.document_bonus),
+            open_documents_bonus: clamp(self.open_documents_bonus, defaults.open_documents_bonus),
+        }
+    }
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            first_letter_bonus: 50.0,
+            first_letter_penalty: 30.0,
+            prefix_weight: 8.0,
+            common_bonus: 35.0,
+            distance_penalties: [20.0, 50.0, 100.0],
+            keyboard_layout: KeyboardLayout::default(),
+            context_bonus: 40.0,
+            usage_bonus: 30.0,
+            document_bonus: 35.0,
+            open_documents_bonus: 25.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_empty_settings_use_defaults() {
+        let settings = Settings::from_json(&json!({})).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_partial_scoring_block() {
+        let settings = Settings::from_json(&json!({
+            "polski-ls": { "scoring": { "commonBonus": 10.0 } }
+        }))
+        .unwrap();
+        assert_eq!(settings.scoring.common_bonus, 10.0);
+        assert_eq!(settings.scoring.prefix_weight, 8.0);
+        assert_eq!(settings.scoring.context_bonus, 40.0);
+        assert_eq!(settings.scoring.usage_bonus, 30.0);
+        assert_eq!(settings.scoring.document_bonus, 35.0);
+        assert_eq!(settings.scoring.open_documents_bonus, 25.0);
+        let layout = settings.scoring.keyboard_layout;
+        assert_eq!(layout, KeyboardLayout::PolishProgrammers);
+
+        let settings = Settings::from_json(&json!({ "scoring": { "keyboardLayout": "uniform" } }));
+        let layout = settings.unwrap().scoring.keyboard_layout;
+        assert_eq!(layout, KeyboardLayout::Uniform);
+    }
+
+    #[test]
+    fn test_scoring_weights_are_clamped() {
+        let settings = Settings::from_json(&json!({
+            "scoring": { "prefixWeight": -5.0, "distancePenalties": [1e9, 50.0, 100.0] }
+        }))
+        .unwrap();
+        assert_eq!(settings.scoring.prefix_weight, 0.0);
+        assert_eq!(
+            settings.scoring.distance_penalties,
+            [MAX_WEIGHT, 50.0, 100.0]
+        );
+    }
+
+    #[test]
+    fn test_code_action_settings() {
+        let settings = Settings::from_json(&json!({
+            "codeActions": { "maxSuggestions": 500, "addToDictionary": "bottom" }
+        }))
+        .unwrap();
+        assert_eq!(settings.code_actions.max_suggestions, MAX_SUGGESTIONS_LIMIT);
+        assert_eq!(settings.code_actions.add_to_dictionary, MenuPlacement::Bottom);
+
+        let settings = Settings::from_json(&json!({ "codeActions": { "maxSuggestions": 0 } }));
+        assert_eq!(settings.unwrap().code_actions.max_suggestions, 1);
+    }
+
+    #[test]
+    fn test_completion_settings() {
+        let settings = Settings::from_json(&json!({
+            "completion": { "maxResults": 20, "maxEditDistance": 1, "minPrefixLength": 3 }
+        }))
+        .unwrap();
+        assert_eq!(settings.completion.max_results, 20);
+        assert_eq!(settings.completion.max_edit_distance, 1);
+        assert_eq!(settings.completion.min_prefix_length, 3);
+        assert_eq!(settings.completion.commit_characters, None);
+
+        let settings = Settings::from_json(&json!({
+            "completion": { "maxResults": 0, "maxEditDistance": 9, "minPrefixLength": 0 }
+        }))
+        .unwrap();
+        assert_eq!(settings.completion.max_results, 1);
+        assert_eq!(settings.completion.max_edit_distance, MAX_EDIT_DISTANCE_LIMIT);
+        assert_eq!(settings.completion.min_prefix_length, 1);
+
+        let settings = Settings::from_json(&json!({ "completion": { "commitCharacters": [] } }));
+        assert_eq!(settings.unwrap().completion.commit_characters, Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_reflow_line_width_is_clamped() {
+        let settings = Settings::from_json(&json!({ "reflow": { "lineWidth": 5 } })).unwrap();
+        assert_eq!(settings.reflow.line_width, MIN_LINE_WIDTH);
+        let settings = Settings::from_json(&json!({ "reflow": { "lineWidth": 72 } })).unwrap();
+        assert_eq!(settings.reflow.line_width, 72);
+    }
+
+    #[test]
+    fn test_rule_settings() {
+        assert!(Settings::default().rules.unknown_words);
+        assert!(!Settings::default().rules.missing_abbreviation_period);
+        assert!(Settings::default().rules.replacements);
+        let settings = Settings::from_json(&json!({
+            "rules": { "unknownWords": false, "missingAbbreviationPeriod": true }
+        }))
+        .unwrap();
+        assert!(!settings.rules.unknown_words);
+        assert!(settings.rules.missing_abbreviation_period);
+    }
+
+    #[test]
+    fn test_diagnostic_settings() {
+        let settings = Settings::from_json(&json!({
+            "diagnostics": { "minWordLength": 0, "severity": "warning" }
+        }))
+        .unwrap();
+        assert_eq!(settings.diagnostics.min_word_length, 1);
+        assert_eq!(settings.diagnostics.severity.to_lsp(), DiagnosticSeverity::WARNING);
+        assert!(Settings::from_json(&json!({ "diagnostics": { "severity": "loud" } })).is_err());
+
+        let limits = DiagnosticSettings::default();
+        assert!(!limits.exceeds_length(500_000));
+        assert!(limits.exceeds_length(500_001));
+        let settings = Settings::from_json(&json!({ "diagnostics": { "maxDocumentLength": 0 } }));
+        assert!(!settings.unwrap().diagnostics.exceeds_length(usize::MAX));
+
+        assert_eq!(limits.debounce(), Duration::from_millis(300));
+        let settings = Settings::from_json(&json!({ "diagnostics": { "debounceMs": 0 } }));
+        assert!(settings.unwrap().diagnostics.debounce().is_zero());
+
+        assert_eq!(limits.alphanumeric_words, AlphanumericWords::Skip);
+        let settings = json!({ "diagnostics": { "alphanumericWords": "core" } });
+        let settings = Settings::from_json(&settings).unwrap();
+        assert_eq!(settings.diagnostics.alphanumeric_words, AlphanumericWords::Core);
+
+        assert!(!limits.on_demand);
+        let settings = Settings::from_json(&json!({ "diagnostics": { "onDemand": true } }));
+        assert!(settings.unwrap().diagnostics.on_demand);
+    }
+
+    #[test]
+    fn test_category_settings() {
+        let settings = Settings::default();
+        let spelling = settings.categories.get(Category::Spelling);
+        assert_eq!(spelling.severity(Severity::Hint), Some(DiagnosticSeverity::HINT));
+
+        let settings = json!({ "categories": {
+            "spelling": { "severity": "error" },
+            "typography": { "enabled": false, "severity": "warning" }
+        } });
+        let categories = Settings::from_json(&settings).unwrap().categories;
+        let spelling = categories.get(Category::Spelling);
+        assert_eq!(spelling.severity(Severity::Hint), Some(DiagnosticSeverity::ERROR));
+        assert_eq!(categories.get(Category::Typography).severity(Severity::Hint), None);
+    }
+
+    #[test]
+    fn test_typography_settings() {
+        assert_eq!(Settings::default().typography.nested_quotes.marks(), ('‚', '’'));
+        let settings = json!({ "typography": { "nestedQuotes": "guillemets" } });
+        let settings = Settings::from_json(&settings).unwrap();
+        assert_eq!(settings.typography.nested_quotes, NestedQuotes::Guillemets);
+    }
+
+    #[test]
+    fn test_front_matter_settings() {
+        assert_eq!(
+            Settings::default().front_matter.checked_keys,
+            vec!["title", "description"]
+        );
+        let settings = json!({ "frontMatter": { "checkedKeys": ["summary"] } });
+        let settings = Settings::from_json(&settings).unwrap();
+        assert_eq!(settings.front_matter.checked_keys, vec!["summary"]);
+    }
+
+    #[test]
+    fn test_project_dictionary_path() {
+        assert_eq!(Settings::default().dictionaries.project, ".polski-ls/slownik.txt");
+        let settings = Settings::from_json(&json!({
+            "dictionaries": { "project": "docs/words.txt" }
+        }))
+        .unwrap();
+        assert_eq!(settings.dictionaries.project, "docs/words.txt");
+        assert!(settings.dictionaries.extra.is_empty());
+    }
+
+    #[test]
+    fn test_later_layers_override_earlier() {
+        let layers = SettingsLayers {
+            user: json!({
+                "codeActions": { "maxSuggestions": 3 },
+                "reflow": { "lineWidth": 120 }
+            }),
+            client: json!({ "polski-ls": {
+                "rules": { "missingAbbreviationPeriod": true },
+                "reflow": { "lineWidth": 100 }
+            } }),
+            project: json!({
+                "reflow": { "lineWidth": 72 },
+                "files": { "exclude": ["vendor/"] }
+            }),
+        };
+        let settings = layers.resolve().unwrap();
+        assert!(settings.rules.missing_abbreviation_period);
+        assert_eq!(settings.reflow.line_width, 72);
+        assert_eq!(settings.files.exclude, vec!["vendor/"]);
+        assert_eq!(settings.code_actions.max_suggestions, 3);
+
+        assert_eq!(SettingsLayers::default().resolve().unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_read_settings_file() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(PROJECT_CONFIG_FILE);
+        assert_eq!(read_settings_file(&path), Ok(None));
+
+        std::fs::write(
+            &path,
+            "[files]\nexclude = [\"*.log\"]\n\n[dictionaries]\nextra = [\"terms.txt\"]\n",
+        )
+        .unwrap();
+        let value = read_settings_file(&path).unwrap().unwrap();
+        let settings = Settings::from_json(&value).unwrap();
+        assert_eq!(settings.files.exclude, vec!["*.log"]);
+        assert_eq!(settings.dictionaries.extra, vec!["terms.txt"]);
+
+        std::fs::write(&path, "[rules]\nunknownWords = \"yes\"\n").unwrap();
+        assert!(read_settings_file(&path).is_err());
+        std::fs::write(&path, "[rules\n").unwrap();
+        assert!(read_settings_file(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_language_ids() {
+        let defaults = FileSettings::default();
+        assert!(defaults.allows_language("markdown"));
+        assert!(defaults.allows_language(""));
+        assert!(!defaults.allows_language("rust"));
+
+        let settings = Settings::from_json(&json!({ "files": { "languageIds": ["rust"] } }));
+        let files = settings.unwrap().files;
+        assert!(files.allows_language("rust"));
+        assert!(!files.allows_language("markdown"));
+
+        let settings = Settings::from_json(&json!({ "files": { "languageIds": ["*"] } }));
+        assert!(settings.unwrap().files.allows_language("python"));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(Settings::from_json(&json!({ "scoring": { "commonBonus": "high" } })).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tower_lsp_server::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use crate::dictionary::skeleton_key;
+
+/// Group of related checks, reported with its own severity and switched on or off as a
+/// whole; its name prefixes the codes of its diagnostics ("spelling/unknown-word").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Spelling,
+    Typography,
+    Grammar,
+}
+
+impl Category {
+    /// The category of the diagnostics with `code`.
+    pub fn of(code: &str) -> Self {
+        match code {
+            MISSING_ABBREVIATION_PERIOD
+            | DOUBLE_SPACE
+            | SPACE_BEFORE_PUNCTUATION
+            | MISSING_SPACE_AFTER_PUNCTUATION
+            | STRAIGHT_QUOTES
+            | HYPHEN_AS_DASH
+            | DOUBLE_HYPHEN
+            | HANGING_CONJUNCTION => Category::Typography,
+            REPEATED_WORD | MISSING_COMMA => Category::Grammar,
+            _ => Category::Spelling,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Spelling => "spelling",
+            Category::Typography => "typography",
+            Category::Grammar => "grammar",
+        }
+    }
+}
+
+/// `code` tagged with its category, as published in `Diagnostic.code`.
+pub fn tagged_code(code: &str) -> String {
+    format!("{}/{}", Category::of(code).name(), code)
+}
+
+/// Stable codes identifying what kind of problem a diagnostic reports.
+pub const UNKNOWN_WORD: &str = "unknown-word";
+/// An unknown word that matches a dictionary word once Polish letters are restored.
+pub const MISSING_DIACRITICS: &str = "missing-diacritics";
+pub const MISSING_ABBREVIATION_PERIOD: &str = "missing-abbreviation-period";
+/// Text matching one of the replacement rules, such as "wogóle" for "w ogóle".
+pub const REPLACEMENT_RULE: &str = "replacement-rule";
+/// A word written twice in a row, like "się się".
+pub const REPEATED_WORD: &str = "repeated-word";
+/// A subordinate clause ("że", "który") without the comma before it.
+pub const MISSING_COMMA: &str = "missing-comma";
+/// Two or more spaces between words.
+pub const DOUBLE_SPACE: &str = "double-space";
+/// A space between a word and the punctuation closing it ("kot ,").
+pub const SPACE_BEFORE_PUNCTUATION: &str = "space-before-punctuation";
+/// Punctuation running into the next word ("kot,pies").
+pub const MISSING_SPACE_AFTER_PUNCTUATION: &str = "missing-space-after-punctuation";
+/// Straight quotes ("tak") where Polish uses „tak”.
+pub const STRAIGHT_QUOTES: &str = "straight-quotes";
+/// A hyphen between spaces used as a dash ("tak - nie").
+pub const HYPHEN_AS_DASH: &str = "hyphen-as-dash";
+/// Two hyphens typed for a dash ("tak -- nie").
+pub const DOUBLE_HYPHEN: &str = "double-hyphen";
+/// A single-letter word ("w", "i") that may be left at the end of a line.
+pub const HANGING_CONJUNCTION: &str = "hanging-conjunction";
+
+/// Extra information carried in `Diagnostic.data`, so code actions can reuse what was
+/// computed when the diagnostic was published.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticData {
+    /// Corrections, best first, in dictionary casing.
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+}
+
+impl DiagnosticData {
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Read our data back from a diagnostic echoed by the client, if it carries any.
+    pub fn from_diagnostic(diagnostic: &Diagnostic) -> Option<Self> {
+        serde_json::from_value(diagnostic.data.clone()?).ok()
+    }
+}
+
+/// Merge findings on overlapping ranges so a token shows a single squiggle.
+///
+/// When several checks report the same or overlapping text, the most specific one
+/// wins: the most severe, then the one covering the least text. Remaining ties keep
+/// the finding reported first. The result is ordered by position.
+pub fn dedup(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut candidates: Vec<(usize, Diagnostic)> = diagnostics.into_iter().enumerate().collect();
+    candidates.sort_by_key(|(order, d)| {
+        (
+            d.severity.unwrap_or(DiagnosticSeverity::HINT),
+            span(&d.range),
+            *order,
+        )
+    });
+
+    // Accepted findings never overlap, keyed by start position
+    let mut accepted: BTreeMap<Position, Diagnostic> = BTreeMap::new();
+    for (_, diagnostic) in candidates {
+        let range = diagnostic.range;
+        let overlaps = if range.start == range.end {
+            accepted.contains_key(&range.start)
+                || accepted
+                    .range(..range.start)
+                    .next_back()
+                    .is_some_and(|(_, d)| d.range.end > range.start)
+        } else {
+            accepted
+                .range(..range.end)
+                .next_back()
+                .is_some_and(|(_, d)| d.range.end > range.start || d.range.start == range.start)
+        };
+        if !overlaps {
+            accepted.insert(range.start, diagnostic);
+        }
+    }
+
+    accepted.into_values().collect()
+}
+
+/// Whether `suggestion` is `word` with some Polish letters restored, e.g. "zolw" → "żółw".
+pub fn restores_diacritics(word: &str, suggestion: &str) -> bool {
+    let fold = |text: &str| skeleton_key(&text.chars().collect::<Vec<_>>());
+    word.to_lowercase() != suggestion.to_lowercase() && fold(word) == fold(suggestion)
+}
+
+/// Size of a range as (lines spanned, characters on a single line).
+fn span(range: &Range) -> (u32, u32) {
+    let lines = range.end.line - range.start.line;
+    if lines == 0 {
+        (0, range.end.character.saturating_sub(range.start.character))
+    } else {
+        (lines, range.end.character)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: u32, start: u32, end: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, start), Position::new(line, end)),
+            severity: Some(DiagnosticSeverity::HINT),
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_disjoint_findings_are_kept_in_order() {
+        let result = dedup(vec![
+            diagnostic(1, 0, 4, "c"),
+            diagnostic(0, 5, 9, "b"),
+            diagnostic(0, 0, 4, "a"),
+        ]);
+        assert_eq!(messages(&result), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_same_range_keeps_first() {
+        let result = dedup(vec![
+            diagnostic(0, 0, 4, "spelling"),
+            diagnostic(0, 0, 4, "duplicate"),
+        ]);
+        assert_eq!(messages(&result), vec!["spelling"]);
+    }
+
+    #[test]
+    fn test_narrower_finding_wins() {
+        let result = dedup(vec![
+            diagnostic(0, 0, 12, "whole phrase"),
+            diagnostic(0, 5, 8, "one word"),
+            diagnostic(0, 14, 18, "elsewhere"),
+        ]);
+        assert_eq!(messages(&result), vec!["one word", "elsewhere"]);
+    }
+
+    #[test]
+    fn test_more_severe_finding_wins() {
+        let mut warning = diagnostic(0, 0, 10, "warning");
+        warning.severity = Some(DiagnosticSeverity::WARNING);
+        let result = dedup(vec![diagnostic(0, 2, 5, "hint"), warning]);
+        assert_eq!(messages(&result), vec!["warning"]);
+    }
+
+    #[test]
+    fn test_data_round_trip() {
+        let data = DiagnosticData {
+            suggestions: vec!["kot".to_string(), "kat".to_string()],
+        };
+        let mut with_data = diagnostic(0, 0, 4, "kott");
+        with_data.data = Some(data.to_value());
+        assert_eq!(DiagnosticData::from_diagnostic(&with_data), Some(data));
+
+        assert_eq!(DiagnosticData::from_diagnostic(&diagnostic(0, 0, 4, "none")), None);
+        with_data.data = Some(serde_json::json!("other server's data"));
+        assert_eq!(DiagnosticData::from_diagnostic(&with_data), None);
+    }
+
+    #[test]
+    fn test_restores_diacritics() {
+        assert!(restores_diacritics("zolw", "żółw"));
+        assert!(restores_diacritics("Zrodlo", "źródło"));
+        assert!(restores_diacritics("zołw", "żółw"));
+        assert!(!restores_diacritics("kott", "kot"));
+        assert!(!restores_diacritics("Żółw", "żółw"));
+        assert!(!restores_diacritics("zolw", "zołw2"));
+    }
+
+    #[test]
+    fn test_empty_range_inside_other_finding() {
+        let result = dedup(vec![
+            diagnostic(0, 0, 6, "word"),
+            diagnostic(0, 3, 3, "point"),
+        ]);
+        assert_eq!(messages(&result), vec!["point"]);
+    }
+}
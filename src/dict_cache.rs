@@ -0,0 +1,135 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::compiled_dict::{self, common_flag, CompiledWordList, FORMAT_VERSION, USER};
+use crate::dictionary::SimpleDictionary;
+
+/// Compiled copy of the baseline word list together with the user dictionaries, kept in
+/// the user cache directory (~/.cache/polski-ls). It is named after a hash of everything
+/// it was built from, so any change to the inputs simply misses the cache.
+pub struct DictionaryCache {
+    path: PathBuf,
+}
+
+impl DictionaryCache {
+    /// The cache entry for `inputs` (the baseline text, then each user dictionary's text)
+    /// in the user cache directory, if it is known.
+    pub fn new(inputs: &[&str]) -> Option<Self> {
+        let dir = dirs::cache_dir()?.join("polski-ls");
+        Some(Self::in_dir(&dir, inputs))
+    }
+
+    fn in_dir(dir: &Path, inputs: &[&str]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        FORMAT_VERSION.hash(&mut hasher);
+        inputs.hash(&mut hasher);
+        let name = format!("dictionary-{:016x}.bin", hasher.finish());
+        Self {
+            path: dir.join(name),
+        }
+    }
+
+    /// The cached words, or `None` when there is no valid entry for these inputs.
+    pub fn load(&self) -> Option<CompiledWordList> {
+        if !self.path.is_file() {
+            return None;
+        }
+        match CompiledWordList::open(&self.path) {
+            Ok(list) => {
+                eprintln!("[POLSKI-LS] Loading cached dictionary: {:?}", self.path);
+                Some(list)
+            }
+            Err(e) => {
+                eprintln!(
+                    "[POLSKI-LS] Ignoring dictionary cache {:?}: {}",
+                    self.path, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Store the words of `dictionary`, those from `first_user` on being the user's.
+    pub fn store_dictionary(&self, dictionary: &SimpleDictionary, first_user: usize) {
+        let words =
+            dictionary
+                .loaded_words()
+                .iter()
+                .enumerate()
+                .map(|(idx, (word, is_common, rank))| {
+                    let user = if idx >= first_user { USER } else { 0 };
+                    (word, common_flag(is_common) | user, rank)
+                });
+        self.store(words);
+    }
+
+    /// Write `words` as the entry for these inputs and remove entries for older ones.
+    /// Failing to write only costs the next startup its speed, so errors are logged.
+    pub fn store<'a>(&self, words: impl Iterator<Item = (&'a str, u8, Option<u32>)>) {
+        let Some(dir) = self.path.parent() else {
+            return;
+        };
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!(
+                "[POLSKI-LS] ERROR: Cannot create cache directory {:?}: {}",
+                dir, e
+            );
+            return;
+        }
+        if let Err(e) = compiled_dict::write_compiled(&self.path, words) {
+            eprintln!(
+                "[POLSKI-LS] ERROR: Cannot write dictionary cache {:?}: {}",
+                self.path, e
+            );
+            return;
+        }
+
+        let stale = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.starts_with("dictionary-") && entry.path() != self.path
+            });
+        for entry in stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiled_dict::COMMON;
+
+    #[test]
+    fn test_entries_follow_inputs() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-cache-{}", std::process::id()));
+        let cache = DictionaryCache::in_dir(&dir, &["kot\n*pies\n", "żółw\n"]);
+        assert!(cache.load().is_none());
+
+        let words = [
+            ("kot", 0, None),
+            ("pies", COMMON, Some(12)),
+            ("żółw", USER, None),
+        ];
+        cache.store(words.into_iter());
+        let words: Vec<(String, u8, Option<u32>)> = cache
+            .load()
+            .unwrap()
+            .words()
+            .map(|(word, flags, rank)| (word.to_string(), flags, rank))
+            .collect();
+        assert_eq!(words[1], ("pies".to_string(), COMMON, Some(12)));
+        assert_eq!(words[2], ("żółw".to_string(), USER, None));
+
+        // Changed inputs miss, and storing them drops the old entry
+        let changed = DictionaryCache::in_dir(&dir, &["kot\n*pies\n", "żółw\nżaba\n"]);
+        assert_ne!(changed.path, cache.path);
+        assert!(changed.load().is_none());
+        changed.store([("kot", 0, None)].into_iter());
+        assert!(!cache.path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
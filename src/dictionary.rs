@@ -1,9 +1,79 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::watch;
+
+use crate::apostrophe::{self, is_apostrophe};
+use crate::compiled_dict;
+use crate::dict_cache::DictionaryCache;
+use crate::fst_dictionary::FstDictionary;
+use crate::symspell::SymSpellDictionary;
+use crate::word_list::WordList;
+
+/// Default system-wide word list, used when the embedded one is unavailable or disabled.
+/// Packagers can override it at build time via the `POLSKI_LS_SYSTEM_DICT` environment variable.
+pub const SYSTEM_DICT_PATH: &str = match option_env!("POLSKI_LS_SYSTEM_DICT") {
+    Some(path) => path,
+    None => "/usr/share/polski-ls/slowa.txt",
+};
+
+/// Default location of the project dictionary, relative to the workspace root.
+pub const PROJECT_DICT_PATH: &str = ".polski-ls/slownik.txt";
+
+/// Where the baseline word list comes from.
+#[derive(Debug, Clone)]
+pub struct DictionaryOptions {
+    /// Use the word list compiled into the binary (requires the `embedded-dict` feature).
+    pub use_embedded: bool,
+    /// Word list read from disk when the embedded one is not used.
+    pub system_dict_path: PathBuf,
+    /// Index used for fuzzy lookups.
+    pub backend: DictionaryBackend,
+}
+
+/// How fuzzy lookups (suggestions and typo-tolerant completion) find their candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DictionaryBackend {
+    /// Compare the query against every word; no extra memory.
+    #[default]
+    Simple,
+    /// Precomputed deletion variants: a few hash lookups per query, at the cost of a
+    /// larger index built at startup.
+    Symspell,
+    /// Finite state transducer searched with Levenshtein automata: compact, suited to
+    /// very large morphological word lists.
+    Fst,
+}
+
+impl DictionaryOptions {
+    /// Whether the embedded word list will actually be used.
+    pub fn embedded_enabled(&self) -> bool {
+        self.use_embedded && cfg!(feature = "embedded-dict")
+    }
+}
+
+impl Default for DictionaryOptions {
+    fn default() -> Self {
+        Self {
+            use_embedded: true,
+            system_dict_path: PathBuf::from(SYSTEM_DICT_PATH),
+            backend: DictionaryBackend::default(),
+        }
+    }
+}
+
 /// Result of a fuzzy match operation.
 #[derive(Debug, Clone)]
 pub struct FuzzyMatchResult {
     pub word: Vec<char>,
     pub edit_distance: u8,
     pub is_common: bool,
+    /// Position in the word list's frequency ranking (1 = most frequent), if it has one.
+    pub rank: Option<u32>,
 }
 
 /// Trait for dictionary implementations that support fuzzy matching.
@@ -11,6 +81,27 @@ pub trait Dictionary: Send + Sync {
     /// Check if a word exists in the dictionary (case-insensitive).
     fn contains(&self, word: &[char]) -> bool;
 
+    /// Whether `word` is known, is a hyphenated compound of known words
+    /// ("polsko-niemiecki"), which Polish forms freely, or is a known foreign word with a
+    /// Polish ending after an apostrophe ("iPhone'a").
+    fn contains_compound(&self, word: &[char]) -> bool {
+        if self.contains(word) {
+            return true;
+        }
+        if let Some((base, _)) = apostrophe::split(word) {
+            return self.contains_compound(base);
+        }
+        word.contains(&'-') && word.split(|&c| c == '-').all(|part| self.contains(part))
+    }
+
+    /// Check many words at once, so backends can answer a whole document in one pass.
+    fn contains_many(&self, words: &[Vec<char>]) -> Vec<bool> {
+        words.iter().map(|word| self.contains(word)).collect()
+    }
+
+    /// Words that start with `prefix` (case-insensitive), common and short ones first.
+    fn prefix_match(&self, prefix: &[char], max_results: usize) -> Vec<FuzzyMatchResult>;
+
     /// Find words matching the prefix within the given edit distance.
     fn fuzzy_match(
         &self,
@@ -18,48 +109,197 @@ pub trait Dictionary: Send + Sync {
         max_edit_distance: u8,
         max_results: usize,
     ) -> Vec<FuzzyMatchResult>;
+
+    /// Number of words in the dictionary.
+    fn word_count(&self) -> usize;
+
+    /// Short name of the lookup backend, reported in the status notification.
+    fn backend_name(&self) -> &'static str;
 }
 
 /// Simple in-memory dictionary implementation.
+#[derive(Clone)]
 pub struct SimpleDictionary {
-    words: Vec<(Vec<char>, bool)>, // (word, is_common)
-    user_dict_path: Option<std::path::PathBuf>,
+    words: WordList,
+    user_dict_path: Option<PathBuf>,
+    /// Lowercased words that came from the user's own dictionary files.
+    user_words: HashSet<String>,
+    /// Words from the workspace's project dictionary, replaced when it is reloaded.
+    project_words: WordList,
+    project_dict_path: Option<PathBuf>,
+    /// Words from extra read-only lists named in the settings.
+    extra_words: WordList,
+    /// Every entry lowercased, mapped to whether any of its spellings is common and the
+    /// best frequency rank among them, so membership is one hash lookup instead of a scan.
+    lookup: HashMap<String, (bool, Option<u32>)>,
+    /// Every spelling keyed by its lowercase form, sorted so the words sharing a prefix
+    /// form one range.
+    prefix_index: BTreeMap<(String, Box<str>), bool>,
+    /// Every spelling keyed by its lowercase form without diacritics ("zolty" for
+    /// "żółty"), so text typed without them finds its words in one range.
+    skeleton_index: BTreeMap<(String, Box<str>), bool>,
+    /// Every entry keyed by how it sounds, so spelling errors that are several edits
+    /// away ("hżan" for "chrzan") are still found.
+    phonetic_index: HashMap<String, Vec<(Box<str>, bool)>>,
+    /// Multi-word entries ("w związku z tym") keyed by their lowercase form, with whether
+    /// they are common and their best rank. Kept apart from the single words, which are
+    /// all that spell checking and word completion look at.
+    phrase_index: BTreeMap<(String, Box<str>), (bool, Option<u32>)>,
+    /// Backend answering fuzzy queries, `None` to scan every entry.
+    fuzzy_index: Option<FuzzyIndex>,
+    backend: DictionaryBackend,
+}
+
+/// Index answering fuzzy queries without comparing the query against every word.
+#[derive(Clone)]
+enum FuzzyIndex {
+    SymSpell(SymSpellDictionary),
+    Fst(FstDictionary),
+}
+
+impl FuzzyIndex {
+    /// Build the index for `backend` over `entries`, or `None` for plain scanning.
+    fn build<'a>(
+        backend: DictionaryBackend,
+        entries: impl Iterator<Item = (&'a str, bool)>,
+    ) -> Option<Self> {
+        match backend {
+            DictionaryBackend::Simple => None,
+            DictionaryBackend::Symspell => {
+                let mut symspell = SymSpellDictionary::new();
+                for (word, is_common) in entries {
+                    symspell.add_word(word, is_common);
+                }
+                Some(Self::SymSpell(symspell))
+            }
+            DictionaryBackend::Fst => Some(Self::Fst(FstDictionary::build(entries))),
+        }
+    }
+
+    fn add_word(&mut self, word: &str, is_common: bool) {
+        match self {
+            Self::SymSpell(symspell) => symspell.add_word(word, is_common),
+            Self::Fst(fst) => fst.add_word(word, is_common),
+        }
+    }
+
+    fn dictionary(&self) -> &dyn Dictionary {
+        match self {
+            Self::SymSpell(symspell) => symspell,
+            Self::Fst(fst) => fst,
+        }
+    }
+}
+
+/// What the dictionary knows about a word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordInfo {
+    pub is_common: bool,
+    pub rank: Option<u32>,
+    pub is_user: bool,
+    pub is_project: bool,
 }
 
 impl SimpleDictionary {
     /// Create a new empty dictionary.
     pub fn new() -> Self {
         Self {
-            words: Vec::new(),
+            words: WordList::new(),
             user_dict_path: None,
+            user_words: HashSet::new(),
+            project_words: WordList::new(),
+            project_dict_path: None,
+            extra_words: WordList::new(),
+            lookup: HashMap::new(),
+            prefix_index: BTreeMap::new(),
+            skeleton_index: BTreeMap::new(),
+            phonetic_index: HashMap::new(),
+            phrase_index: BTreeMap::new(),
+            fuzzy_index: None,
+            backend: DictionaryBackend::Simple,
         }
     }
 
+    /// All entries: the loaded word lists followed by the project and extra dictionaries.
+    fn entries(&self) -> impl Iterator<Item = (&str, bool, Option<u32>)> {
+        self.words
+            .iter()
+            .chain(self.project_words.iter())
+            .chain(self.extra_words.iter())
+    }
+
+    /// The entries that are single words, not phrases.
+    fn single_words(&self) -> impl Iterator<Item = (&str, bool, Option<u32>)> {
+        self.entries().filter(|(word, _, _)| !is_phrase(word))
+    }
+
     /// Add a word to the dictionary.
     pub fn add_word(&mut self, word: &str, is_common: bool) {
-        self.words.push((word.chars().collect(), is_common));
+        self.add_ranked_word(word, is_common, None);
+    }
+
+    /// Add a word with its frequency rank, if known.
+    fn add_ranked_word(&mut self, word: &str, is_common: bool, rank: Option<u32>) {
+        self.index_word(word, is_common, rank);
+        self.words.push(word, is_common, rank);
+    }
+
+    /// Record a new entry in the membership lookup and the prefix indexes, or in the
+    /// phrase index if it is a phrase.
+    fn index_word(&mut self, word: &str, is_common: bool, rank: Option<u32>) {
+        if is_phrase(word) {
+            let phrase = word.split_whitespace().collect::<Vec<_>>().join(" ");
+            let key = (phrase.to_lowercase(), phrase.into());
+            let known = self.phrase_index.entry(key).or_default();
+            known.0 |= is_common;
+            known.1 = known.1.into_iter().chain(rank).min();
+            return;
+        }
+        let chars: Vec<char> = word.chars().collect();
+        let key = lowercase_key(&chars);
+        let known = self.lookup.entry(key.clone()).or_default();
+        known.0 |= is_common;
+        known.1 = known.1.into_iter().chain(rank).min();
+        *self.prefix_index.entry((key, word.into())).or_default() |= is_common;
+        let skeleton = (skeleton_key(&chars), word.into());
+        *self.skeleton_index.entry(skeleton).or_default() |= is_common;
+        let homophones = self.phonetic_index.entry(phonetic_key(&chars)).or_default();
+        homophones.push((word.into(), is_common));
+        if let Some(index) = &mut self.fuzzy_index {
+            index.add_word(word, is_common);
+        }
+    }
+
+    /// Rebuild the membership lookup and the indexes after entries were removed.
+    fn rebuild_lookup(&mut self) {
+        self.lookup.clear();
+        self.prefix_index.clear();
+        self.skeleton_index.clear();
+        self.phonetic_index.clear();
+        self.phrase_index.clear();
+        self.fuzzy_index = None;
+        let entries: Vec<(String, bool, Option<u32>)> = self
+            .entries()
+            .map(|(word, is_common, rank)| (word.to_string(), is_common, rank))
+            .collect();
+        for (word, is_common, rank) in entries {
+            self.index_word(&word, is_common, rank);
+        }
+        self.use_backend(self.backend);
     }
 
     /// Parse words from text content (one word per line, *prefix = common)
     fn parse_word_list(&mut self, content: &str) {
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() || trimmed.starts_with('#') {
-                continue; // skip empty lines and comments
-            }
-            if let Some(word) = trimmed.strip_prefix('*') {
-                self.add_word(word, true);
-            } else {
-                self.add_word(trimmed, false);
-            }
+        for (word, is_common, rank) in parse_words(content) {
+            self.add_ranked_word(word, is_common, rank);
         }
     }
 
-    /// Load embedded baseline dictionary
+    /// Load embedded baseline dictionary, parsed at build time
+    #[cfg(feature = "embedded-dict")]
     pub fn embedded() -> Self {
-        let mut dict = Self::new();
-        dict.parse_word_list(include_str!("../slowa.txt"));
-        dict
+        let words = compiled_dict::words_in(compiled_dict::EMBEDDED);
+        Self::from_compiled(words.expect("build.rs writes a valid word list"))
     }
 
     /// Add a word to the in-memory dictionary and save it to the user dictionary file.
@@ -73,29 +313,12 @@ impl SimpleDictionary {
             return Ok(());
         }
 
-        self.words.push((word_chars, false));
+        self.add_word(word, false);
+        self.user_words.insert(word.to_lowercase());
 
         // Save to user dictionary file if path is set
         if let Some(path) = &self.user_dict_path {
-            use std::io::Write;
-
-            eprintln!("[POLSKI-LS] Saving word '{}' to {:?}", word, path);
-
-            // Ensure parent directory exists
-            if let Some(parent) = path.parent() {
-                eprintln!("[POLSKI-LS] Creating directory: {:?}", parent);
-                std::fs::create_dir_all(parent)?;
-                eprintln!("[POLSKI-LS] Directory created successfully");
-            }
-
-            // Append word to file
-            eprintln!("[POLSKI-LS] Opening file for append: {:?}", path);
-            let mut file = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path)?;
-
-            writeln!(file, "{}", word)?;
+            append_word(path, word)?;
             eprintln!("[POLSKI-LS] Successfully added '{}' to user dictionary: {:?}", word, path);
         } else {
             eprintln!("[POLSKI-LS] ERROR: No user_dict_path set, word '{}' not saved to file", word);
@@ -108,24 +331,218 @@ impl SimpleDictionary {
         Ok(())
     }
 
-    /// Load embedded + user extension files from ~/.config/polski-ls/*.txt
-    pub fn with_user_extensions() -> Self {
-        let mut dict = Self::embedded();
+    /// The baseline and user word lists in load order, without the project and extra
+    /// dictionaries.
+    pub fn loaded_words(&self) -> &WordList {
+        &self.words
+    }
+
+    /// File that "Add to dictionary" writes to, if the config directory is known.
+    pub fn user_dict_path(&self) -> Option<&Path> {
+        self.user_dict_path.as_deref()
+    }
 
-        // Try to get config directory, fallback to $HOME/.config if not available
-        let config_dir = dirs::config_dir().or_else(|| {
-            eprintln!("[POLSKI-LS] dirs::config_dir() returned None, using fallback");
-            std::env::var("HOME").ok().map(|home| {
-                let path = std::path::PathBuf::from(home).join(".config");
-                eprintln!("[POLSKI-LS] Fallback config dir: {:?}", path);
-                path
-            })
+    /// Add words that appeared in the user dictionary file since it was loaded, e.g. from
+    /// an edit made outside the editor. Returns how many words were added.
+    pub fn merge_user_dictionary(&mut self) -> usize {
+        let Some(path) = self.user_dict_path.clone() else {
+            return 0;
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return 0;
+        };
+
+        let mut added = 0;
+        for (word, is_common, rank) in parse_words(&content) {
+            let word_chars: Vec<char> = word.chars().collect();
+            if !self.contains(&word_chars) {
+                self.add_ranked_word(word, is_common, rank);
+                self.user_words.insert(word.to_lowercase());
+                added += 1;
+            }
+        }
+        if added > 0 {
+            eprintln!("[POLSKI-LS] Merged {} new words from {:?}", added, path);
+        }
+        added
+    }
+
+    /// Watch the user dictionary file without help from the client, calling `on_change`
+    /// from the watcher thread whenever it is written. The directory is watched rather
+    /// than the file so editors that save by renaming a temporary file are noticed.
+    /// Watching stops when the returned watcher is dropped.
+    pub fn watch_user_dictionary<F>(&self, on_change: F) -> Option<RecommendedWatcher>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let path = self.user_dict_path.clone()?;
+        let dir = path.parent()?.to_path_buf();
+        let watched = path.clone();
+        let handler = move |result: notify::Result<Event>| match result {
+            Ok(event) if !event.kind.is_access() && event.paths.contains(&watched) => on_change(),
+            Ok(_) => {}
+            Err(e) => eprintln!("[POLSKI-LS] ERROR: File watcher: {}", e),
+        };
+
+        let watcher = notify::recommended_watcher(handler).and_then(|mut watcher| {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
         });
+        match watcher {
+            Ok(watcher) => {
+                eprintln!("[POLSKI-LS] Watching user dictionary: {:?}", path);
+                Some(watcher)
+            }
+            Err(e) => {
+                eprintln!("[POLSKI-LS] ERROR: Cannot watch {:?}: {}", dir, e);
+                None
+            }
+        }
+    }
+
+    /// Load (or reload) the workspace's project dictionary from `path`, replacing the
+    /// previous one. A missing file gives an empty project dictionary.
+    pub fn load_project_dictionary(&mut self, path: PathBuf) {
+        self.project_words = match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                eprintln!("[POLSKI-LS] Loading project dict: {:?}", path);
+                let mut words = WordList::new();
+                words.extend(parse_words(&content));
+                words
+            }
+            Err(_) => WordList::new(),
+        };
+        self.project_dict_path = Some(path);
+        self.rebuild_lookup();
+    }
 
-        if let Some(config_dir) = config_dir {
-            eprintln!("[POLSKI-LS] Config directory: {:?}", config_dir);
-            let polski_ls_dir = config_dir.join("polski-ls");
+    /// Load (or reload) the extra word lists, replacing the previous ones. Unreadable
+    /// files are reported and skipped.
+    pub fn load_extra_dictionaries(&mut self, paths: &[PathBuf]) {
+        self.extra_words = WordList::new();
+        for path in paths {
+            match std::fs::read_to_string(path) {
+                Ok(content) => {
+                    eprintln!("[POLSKI-LS] Loading extra dict: {:?}", path);
+                    self.extra_words.extend(parse_words(&content));
+                }
+                Err(e) => eprintln!(
+                    "[POLSKI-LS] ERROR: Failed to read dictionary {:?}: {}",
+                    path, e
+                ),
+            }
+        }
+        self.rebuild_lookup();
+    }
+
+    /// Take over the project and extra dictionaries loaded into `other`, e.g. while this
+    /// dictionary was still being loaded.
+    fn adopt_configured_words(&mut self, other: &SimpleDictionary) {
+        self.project_words = other.project_words.clone();
+        self.project_dict_path = other.project_dict_path.clone();
+        self.extra_words = other.extra_words.clone();
+        self.rebuild_lookup();
+    }
 
+    /// Add a word to the project dictionary and save it to the workspace file.
+    pub fn add_project_word(&mut self, word: &str) -> std::io::Result<()> {
+        let Some(path) = self.project_dict_path.clone() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No workspace folder is open, so there is no project dictionary.",
+            ));
+        };
+
+        let word_chars: Vec<char> = word.chars().collect();
+        if self.project_words.contains_word(&word_chars) {
+            eprintln!("[POLSKI-LS] Word '{}' already in project dictionary", word);
+            return Ok(());
+        }
+
+        append_word(&path, word)?;
+        self.index_word(word, false, None);
+        self.project_words.push(word, false, None);
+        eprintln!("[POLSKI-LS] Added '{}' to project dictionary: {:?}", word, path);
+        Ok(())
+    }
+
+    /// Load the baseline dictionary: embedded if enabled, otherwise the system word list.
+    pub fn baseline(options: &DictionaryOptions) -> Self {
+        #[cfg(feature = "embedded-dict")]
+        if options.embedded_enabled() {
+            return Self::embedded();
+        }
+
+        if let Some(list) = compiled_dict::open_for(&options.system_dict_path) {
+            eprintln!(
+                "[POLSKI-LS] Loading compiled system dict: {:?} ({} words)",
+                compiled_dict::compiled_path(&options.system_dict_path),
+                list.len()
+            );
+            return Self::from_compiled(list.words());
+        }
+
+        let mut dict = Self::new();
+        match std::fs::read_to_string(&options.system_dict_path) {
+            Ok(content) => {
+                eprintln!("[POLSKI-LS] Loading system dict: {:?}", options.system_dict_path);
+                dict.parse_word_list(&content);
+            }
+            Err(e) => eprintln!(
+                "[POLSKI-LS] ERROR: Failed to read system dictionary {:?}: {}",
+                options.system_dict_path, e
+            ),
+        }
+        dict
+    }
+
+    /// Answer fuzzy queries from the index of `backend`, built from the current words and
+    /// kept up to date as words are added.
+    pub fn use_backend(&mut self, backend: DictionaryBackend) {
+        self.backend = backend;
+        let entries = self.single_words().map(|(word, is_common, _)| (word, is_common));
+        self.fuzzy_index = FuzzyIndex::build(backend, entries);
+    }
+
+    /// Load baseline + user extension files from ~/.config/polski-ls/*.txt
+    pub fn with_user_extensions(options: &DictionaryOptions) -> Self {
+        let config_dir = user_config_dir();
+        let user_files: Vec<(PathBuf, String)> = config_dir
+            .as_deref()
+            .map(user_dictionary_files)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|path| Some((path.clone(), std::fs::read_to_string(&path).ok()?)))
+            .collect();
+
+        // Parsing is skipped when the same inputs were compiled on an earlier start
+        let cache = baseline_text(options).and_then(|baseline| {
+            let mut inputs = vec![baseline.as_ref()];
+            inputs.extend(user_files.iter().map(|(_, content)| content.as_str()));
+            DictionaryCache::new(&inputs)
+        });
+        let mut dict = match cache.as_ref().and_then(DictionaryCache::load) {
+            Some(list) => Self::from_compiled(list.words()),
+            None => {
+                let mut dict = Self::baseline(options);
+                let first_user = dict.words.len();
+                for (path, content) in &user_files {
+                    eprintln!("[POLSKI-LS] Loading user dict: {:?}", path);
+                    dict.parse_word_list(content);
+                }
+                let user_words: Vec<String> = (first_user..dict.words.len())
+                    .map(|idx| dict.words.get(idx).0.to_lowercase())
+                    .collect();
+                dict.user_words.extend(user_words);
+                if let Some(cache) = &cache {
+                    cache.store_dictionary(&dict, first_user);
+                }
+                dict
+            }
+        };
+        dict.use_backend(options.backend);
+
+        if let Some(polski_ls_dir) = config_dir {
             // Set the user dictionary path
             dict.user_dict_path = Some(polski_ls_dir.join("slownik.txt"));
             eprintln!("[POLSKI-LS] User dictionary path: {:?}", dict.user_dict_path);
@@ -139,26 +556,331 @@ impl SimpleDictionary {
                     eprintln!("[POLSKI-LS] Successfully created directory");
                 }
             }
-
-            if polski_ls_dir.is_dir() {
-                if let Ok(entries) = std::fs::read_dir(&polski_ls_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.extension().map_or(false, |e| e == "txt") {
-                            if let Ok(content) = std::fs::read_to_string(&path) {
-                                eprintln!("[POLSKI-LS] Loading user dict: {:?}", path);
-                                dict.parse_word_list(&content);
-                            }
-                        }
-                    }
-                }
-            }
         } else {
             eprintln!("[POLSKI-LS] ERROR: Could not determine config directory!");
         }
 
         dict
     }
+
+    /// Rebuild a dictionary from its compiled words, those flagged `USER` counting as
+    /// the user's own.
+    fn from_compiled<'a>(words: impl Iterator<Item = (&'a str, u8, Option<u32>)>) -> Self {
+        let mut dict = Self::new();
+        for (word, flags, rank) in words {
+            dict.add_ranked_word(word, flags & compiled_dict::COMMON != 0, rank);
+            if flags & compiled_dict::USER != 0 {
+                dict.user_words.insert(word.to_lowercase());
+            }
+        }
+        dict
+    }
+
+    /// Words that `word` is spelled like with some diacritics left out, e.g. "żółty" for
+    /// "zolty" or "zołty", common and short ones first.
+    pub fn diacritic_match(&self, word: &[char], max_results: usize) -> Vec<FuzzyMatchResult> {
+        let skeleton = skeleton_key(word);
+        self.skeleton_match(word, max_results, |key| *key == skeleton)
+    }
+
+    /// Like `diacritic_match`, for words starting with `prefix`.
+    pub fn diacritic_prefix_match(
+        &self,
+        prefix: &[char],
+        max_results: usize,
+    ) -> Vec<FuzzyMatchResult> {
+        self.skeleton_match(prefix, max_results, |_| true)
+    }
+
+    /// Words whose skeleton starts with the skeleton of `typed`, accepted by `filter`, and
+    /// that only add diacritics to the letters typed.
+    fn skeleton_match(
+        &self,
+        typed: &[char],
+        max_results: usize,
+        filter: impl Fn(&String) -> bool,
+    ) -> Vec<FuzzyMatchResult> {
+        let skeleton = skeleton_key(typed);
+        let typed = lowercase_key(typed);
+        let mut results: Vec<FuzzyMatchResult> = self
+            .skeleton_index
+            .range((skeleton.clone(), Box::from(""))..)
+            .take_while(|((key, _), _)| key.starts_with(&skeleton))
+            .filter(|((key, word), _)| {
+                let word = word.to_lowercase();
+                filter(key)
+                    && typed
+                        .chars()
+                        .zip(word.chars())
+                        .all(|(t, w)| t == w || t == base_letter(w))
+            })
+            .map(|((_, word), &is_common)| FuzzyMatchResult {
+                word: word.chars().collect(),
+                edit_distance: 0,
+                is_common,
+                rank: self.rank(&word.to_lowercase()),
+            })
+            .collect();
+
+        results.sort_by_key(by_frequency);
+        results.truncate(max_results);
+        results
+    }
+
+    /// Phrases starting with `typed`, words separated by single spaces, and longer than
+    /// it, ignoring case; common and frequent ones first.
+    pub fn phrase_match(&self, typed: &[char], max_results: usize) -> Vec<FuzzyMatchResult> {
+        let key = lowercase_key(typed);
+        let mut results: Vec<FuzzyMatchResult> = self
+            .phrase_index
+            .range((key.clone(), Box::from(""))..)
+            .take_while(|((lower, _), _)| lower.starts_with(&key))
+            .filter(|((lower, _), _)| lower.len() > key.len())
+            .map(|((_, phrase), &(is_common, rank))| FuzzyMatchResult {
+                word: phrase.chars().collect(),
+                edit_distance: 0,
+                is_common,
+                rank,
+            })
+            .collect();
+
+        results.sort_by_key(by_frequency);
+        results.truncate(max_results);
+        results
+    }
+
+    /// Best frequency rank among the spellings of the lowercase word `key`.
+    fn rank(&self, key: &str) -> Option<u32> {
+        self.lookup.get(key).and_then(|&(_, rank)| rank)
+    }
+
+    /// Look up a word (case-insensitive), returning `None` if it is unknown.
+    pub fn word_info(&self, word: &[char]) -> Option<WordInfo> {
+        let lower = lowercase_key(word);
+        self.lookup.get(&lower).map(|&(is_common, rank)| WordInfo {
+            is_common,
+            rank,
+            is_user: self.user_words.contains(&lower),
+            is_project: self.project_words.contains_word(word),
+        })
+    }
+}
+
+/// Text of the baseline word list, which the dictionary cache is keyed on.
+fn baseline_text(options: &DictionaryOptions) -> Option<Cow<'static, str>> {
+    #[cfg(feature = "embedded-dict")]
+    if options.embedded_enabled() {
+        return Some(Cow::Borrowed(include_str!("../slowa.txt")));
+    }
+    std::fs::read_to_string(&options.system_dict_path)
+        .ok()
+        .map(Cow::Owned)
+}
+
+/// Parse a word list (one word per line, *prefix = common, # comments). A word may be
+/// followed by its rank in a frequency list ("kot 1520", 1 = most frequent).
+pub fn parse_words(content: &str) -> impl Iterator<Item = (&str, bool, Option<u32>)> {
+    content.lines().filter_map(|line| {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None; // skip empty lines and comments
+        }
+        let (entry, is_common) = match trimmed.strip_prefix('*') {
+            Some(entry) => (entry, true),
+            None => (trimmed, false),
+        };
+        let (word, rank) = split_rank(entry);
+        Some((word, is_common, rank))
+    })
+}
+
+/// Split the frequency rank off a word list entry; entries without a valid one are
+/// returned whole.
+fn split_rank(entry: &str) -> (&str, Option<u32>) {
+    let ranked = entry
+        .rsplit_once(char::is_whitespace)
+        .and_then(|(word, rank)| {
+            let rank = rank.parse::<u32>().ok().filter(|&rank| rank > 0)?;
+            Some((word.trim_end(), rank))
+        });
+    match ranked {
+        Some((word, rank)) => (word, Some(rank)),
+        None => (entry, None),
+    }
+}
+
+/// Whether a word list entry is a phrase of several words rather than a single word.
+fn is_phrase(entry: &str) -> bool {
+    entry.contains(char::is_whitespace)
+}
+
+/// Sort key putting common, frequent and short words first.
+fn by_frequency(m: &FuzzyMatchResult) -> (bool, u32, usize) {
+    (!m.is_common, m.rank.unwrap_or(u32::MAX), m.word.len())
+}
+
+/// Append a word to a dictionary file, creating it and its directory if needed.
+fn append_word(path: &Path, word: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    eprintln!("[POLSKI-LS] Saving word '{}' to {:?}", word, path);
+
+    // Ensure parent directory exists
+    if let Some(parent) = path.parent() {
+        eprintln!("[POLSKI-LS] Creating directory: {:?}", parent);
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", word)
+}
+
+/// Case-insensitive comparison of two words.
+pub fn same_word(a: &[char], b: &[char]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(x, y)| x.to_lowercase().eq(y.to_lowercase()))
+}
+
+/// A word in lowercase, as used for case-insensitive lookups.
+pub fn lowercase_key(word: &[char]) -> String {
+    word.iter().flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// A word in lowercase without diacritics, so "Żółty" and "zolty" share a key.
+pub fn skeleton_key(word: &[char]) -> String {
+    word.iter()
+        .flat_map(|c| c.to_lowercase())
+        .map(base_letter)
+        .collect()
+}
+
+/// A word in lowercase with the Polish spellings of one sound merged: "rz" as "ż", "ch"
+/// as "h", "ó" as "u" and the nasal vowels as "en"/"on" ("em"/"om" before "b" and "p"),
+/// so "może" and "morze" or "mięso" and "mienso" share a key.
+pub fn phonetic_key(word: &[char]) -> String {
+    let lower: Vec<char> = word.iter().flat_map(|c| c.to_lowercase()).collect();
+    let mut key = String::with_capacity(lower.len());
+    let mut idx = 0;
+    while idx < lower.len() {
+        let next = lower.get(idx + 1).copied();
+        match (lower[idx], next) {
+            ('r', Some('z')) => {
+                key.push('ż');
+                idx += 1;
+            }
+            ('c', Some('h')) => {
+                key.push('h');
+                idx += 1;
+            }
+            ('ó', _) => key.push('u'),
+            ('ę', Some('b' | 'p')) => key.push_str("em"),
+            ('ą', Some('b' | 'p')) => key.push_str("om"),
+            ('ę', _) => key.push_str("en"),
+            ('ą', _) => key.push_str("on"),
+            (c, _) => key.push(c),
+        }
+        idx += 1;
+    }
+    key
+}
+
+/// Base letter of a lowercase Polish letter with a diacritic.
+pub fn base_letter(c: char) -> char {
+    match c {
+        'ą' => 'a',
+        'ć' => 'c',
+        'ę' => 'e',
+        'ł' => 'l',
+        'ń' => 'n',
+        'ó' => 'o',
+        'ś' => 's',
+        'ź' | 'ż' => 'z',
+        other => other,
+    }
+}
+
+/// Directory holding user configuration and dictionaries (~/.config/polski-ls).
+pub fn user_config_dir() -> Option<PathBuf> {
+    // Try to get config directory, fallback to $HOME/.config if not available
+    let config_dir = dirs::config_dir().or_else(|| {
+        eprintln!("[POLSKI-LS] dirs::config_dir() returned None, using fallback");
+        std::env::var("HOME").ok().map(|home| {
+            let path = PathBuf::from(home).join(".config");
+            eprintln!("[POLSKI-LS] Fallback config dir: {:?}", path);
+            path
+        })
+    })?;
+
+    eprintln!("[POLSKI-LS] Config directory: {:?}", config_dir);
+    Some(config_dir.join("polski-ls"))
+}
+
+/// List the *.txt word lists in a directory, sorted by path.
+pub fn user_dictionary_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// A syntax problem found in a word list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordListError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Validate a word list without loading it, reporting every malformed line.
+pub fn validate_word_list(content: &str) -> Vec<WordListError> {
+    let mut errors = Vec::new();
+
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let (word, rank) = split_rank(trimmed.strip_prefix('*').unwrap_or(trimmed));
+        let last = word.rsplit(char::is_whitespace).next().unwrap_or_default();
+        // Phrases are words separated by whitespace
+        let message = if word.is_empty() {
+            Some("'*' marker without a word".to_string())
+        } else if rank.is_none() && is_phrase(word) && last.parse::<u32>().is_ok() {
+            Some(format!("invalid frequency rank '{}'", last))
+        } else {
+            let misplaced_hyphen = word.starts_with('-') || word.ends_with('-');
+            word.chars()
+                .find(|&c| {
+                    !c.is_alphanumeric() && !c.is_whitespace() && c != '-' && !is_apostrophe(c)
+                })
+                .map(|c| format!("invalid character '{}' in '{}'", c, word))
+                .or_else(|| {
+                    misplaced_hyphen.then(|| format!("hyphen not joining words in '{}'", word))
+                })
+        };
+
+        if let Some(message) = message {
+            errors.push(WordListError {
+                line: idx + 1,
+                message,
+            });
+        }
+    }
+
+    errors
 }
 
 impl Default for SimpleDictionary {
@@ -168,14 +890,38 @@ impl Default for SimpleDictionary {
 }
 
 impl Dictionary for SimpleDictionary {
+    fn word_count(&self) -> usize {
+        self.words.len() + self.project_words.len() + self.extra_words.len()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        match &self.fuzzy_index {
+            Some(index) => index.dictionary().backend_name(),
+            None => "simple",
+        }
+    }
+
     fn contains(&self, word: &[char]) -> bool {
-        self.words.iter().any(|(dict_word, _)| {
-            dict_word.len() == word.len()
-                && dict_word
-                    .iter()
-                    .zip(word.iter())
-                    .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
-        })
+        self.lookup.contains_key(&lowercase_key(word))
+    }
+
+    fn prefix_match(&self, prefix: &[char], max_results: usize) -> Vec<FuzzyMatchResult> {
+        let key = lowercase_key(prefix);
+        let mut results: Vec<FuzzyMatchResult> = self
+            .prefix_index
+            .range((key.clone(), Box::from(""))..)
+            .take_while(|((lower, _), _)| lower.starts_with(&key))
+            .map(|((lower, word), &is_common)| FuzzyMatchResult {
+                word: word.chars().collect(),
+                edit_distance: 0,
+                is_common,
+                rank: self.rank(lower),
+            })
+            .collect();
+
+        results.sort_by_key(by_frequency);
+        results.truncate(max_results);
+        results
     }
 
     fn fuzzy_match(
@@ -184,28 +930,63 @@ impl Dictionary for SimpleDictionary {
         max_edit_distance: u8,
         max_results: usize,
     ) -> Vec<FuzzyMatchResult> {
-        let mut results: Vec<FuzzyMatchResult> = self
-            .words
-            .iter()
-            .filter_map(|(word, is_common)| {
-                let distance = levenshtein_distance(prefix, word);
-                if distance <= max_edit_distance {
-                    Some(FuzzyMatchResult {
-                        word: word.clone(),
-                        edit_distance: distance,
-                        is_common: *is_common,
-                    })
-                } else {
-                    None
+        let mut results: Vec<FuzzyMatchResult> = match &self.fuzzy_index {
+            Some(index) => {
+                let dictionary = index.dictionary();
+                let mut results = dictionary.fuzzy_match(prefix, max_edit_distance, max_results);
+                // The indexes don't keep frequency ranks
+                for m in &mut results {
+                    m.rank = self.rank(&lowercase_key(&m.word));
                 }
-            })
-            .collect();
+                results
+            }
+            None => {
+                let mut chars = Vec::new();
+                self.single_words()
+                    .filter_map(|(word, is_common, rank)| {
+                        chars.clear();
+                        chars.extend(word.chars());
+                        let distance = osa_distance(prefix, &chars);
+                        if distance <= max_edit_distance {
+                            Some(FuzzyMatchResult {
+                                word: chars.clone(),
+                                edit_distance: distance,
+                                is_common,
+                                rank,
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            }
+        };
 
-        // Sort by edit distance first, then by common status
+        // Words spelled the way they sound ("morze" for "może") are one mistake, however
+        // many letters it takes
+        let homophones = self.phonetic_index.get(&phonetic_key(prefix));
+        if let Some(homophones) = homophones.filter(|_| max_edit_distance > 0) {
+            let mut found: HashSet<Vec<char>> = results.iter().map(|m| m.word.clone()).collect();
+            let extra: Vec<FuzzyMatchResult> = homophones
+                .iter()
+                .map(|(word, is_common)| (word.chars().collect::<Vec<char>>(), *is_common))
+                .filter(|(word, _)| found.insert(word.clone()))
+                .map(|(word, is_common)| FuzzyMatchResult {
+                    edit_distance: osa_distance(prefix, &word).min(1),
+                    rank: self.rank(&lowercase_key(&word)),
+                    word,
+                    is_common,
+                })
+                .collect();
+            results.extend(extra);
+        }
+
+        // Sort by edit distance first, then by common status and frequency
         results.sort_by(|a, b| {
             a.edit_distance
                 .cmp(&b.edit_distance)
                 .then_with(|| b.is_common.cmp(&a.is_common))
+                .then_with(|| a.rank.unwrap_or(u32::MAX).cmp(&b.rank.unwrap_or(u32::MAX)))
         });
 
         results.truncate(max_results);
@@ -213,8 +994,82 @@ impl Dictionary for SimpleDictionary {
     }
 }
 
-/// Calculate the Levenshtein edit distance between two character sequences.
-pub fn levenshtein_distance(a: &[char], b: &[char]) -> u8 {
+/// A dictionary shared between request handlers. Readers take a snapshot without
+/// waiting for anyone; updates change a copy and swap it in, so a check or completion in
+/// progress keeps using the words it started with.
+#[derive(Clone)]
+pub struct SharedDictionary {
+    current: Arc<ArcSwap<SimpleDictionary>>,
+    /// Serializes updates, so concurrent ones don't overwrite each other's copies.
+    writer: Arc<Mutex<()>>,
+    /// False until the dictionary passed to `finish_loading` is in place.
+    loaded: Arc<watch::Sender<bool>>,
+}
+
+impl SharedDictionary {
+    pub fn new(dictionary: SimpleDictionary) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(dictionary)),
+            writer: Arc::new(Mutex::new(())),
+            loaded: Arc::new(watch::Sender::new(true)),
+        }
+    }
+
+    /// An empty dictionary standing in for one still being loaded in the background,
+    /// until it is passed to `finish_loading`. Updates made meanwhile go to the empty
+    /// one; the project and extra dictionaries they load are carried over.
+    pub fn loading() -> Self {
+        let shared = Self::new(SimpleDictionary::new());
+        shared.loaded.send_replace(false);
+        shared
+    }
+
+    /// Publish the dictionary loaded in the background and wake everyone waiting in
+    /// `loaded`.
+    pub fn finish_loading(&self, mut dictionary: SimpleDictionary) {
+        let _writer = self.writer.lock().unwrap();
+        dictionary.adopt_configured_words(&self.current.load());
+        self.current.store(Arc::new(dictionary));
+        self.loaded.send_replace(true);
+    }
+
+    /// Whether the dictionary has finished loading.
+    pub fn is_loaded(&self) -> bool {
+        *self.loaded.borrow()
+    }
+
+    /// Wait until the dictionary has finished loading.
+    pub async fn loaded(&self) {
+        let mut loaded = self.loaded.subscribe();
+        // The sender lives as long as `self`, so waiting can't fail
+        let _ = loaded.wait_for(|loaded| *loaded).await;
+    }
+
+    /// The current dictionary; later updates don't affect the returned snapshot.
+    pub fn load(&self) -> Arc<SimpleDictionary> {
+        self.current.load_full()
+    }
+
+    /// Apply `update` to a copy of the dictionary and publish the result.
+    pub fn update<R>(&self, update: impl FnOnce(&mut SimpleDictionary) -> R) -> R {
+        let _writer = self.writer.lock().unwrap();
+        let mut dictionary = SimpleDictionary::clone(&self.current.load());
+        let result = update(&mut dictionary);
+        self.current.store(Arc::new(dictionary));
+        result
+    }
+
+    /// Replace the whole dictionary, e.g. after reloading it from disk.
+    pub fn replace(&self, dictionary: SimpleDictionary) {
+        let _writer = self.writer.lock().unwrap();
+        self.current.store(Arc::new(dictionary));
+    }
+}
+
+/// Edit distance between two character sequences with optimal string alignment: like
+/// Levenshtein, but swapping two adjacent letters ("śiwat" for "świat") costs 1 rather
+/// than 2, as it is the most common typing error. Case is ignored for ASCII letters.
+pub fn osa_distance(a: &[char], b: &[char]) -> u8 {
     let m = a.len();
     let n = b.len();
 
@@ -226,25 +1081,28 @@ pub fn levenshtein_distance(a: &[char], b: &[char]) -> u8 {
         return m.min(255) as u8;
     }
 
-    // Use two rows instead of full matrix to save memory
+    // Three rows: a transposition looks two rows back
+    let mut before_prev_row: Vec<usize> = vec![0; n + 1];
     let mut prev_row: Vec<usize> = (0..=n).collect();
     let mut curr_row: Vec<usize> = vec![0; n + 1];
+    let same = |x: char, y: char| x.eq_ignore_ascii_case(&y);
 
     for i in 1..=m {
         curr_row[0] = i;
 
         for j in 1..=n {
-            let cost = if a[i - 1].eq_ignore_ascii_case(&b[j - 1]) {
-                0
-            } else {
-                1
-            };
+            let cost = if same(a[i - 1], b[j - 1]) { 0 } else { 1 };
 
             curr_row[j] = (prev_row[j] + 1) // deletion
                 .min(curr_row[j - 1] + 1) // insertion
                 .min(prev_row[j - 1] + cost); // substitution
+
+            if i > 1 && j > 1 && same(a[i - 1], b[j - 2]) && same(a[i - 2], b[j - 1]) {
+                curr_row[j] = curr_row[j].min(before_prev_row[j - 2] + 1); // transposition
+            }
         }
 
+        std::mem::swap(&mut before_prev_row, &mut prev_row);
         std::mem::swap(&mut prev_row, &mut curr_row);
     }
 
@@ -255,39 +1113,245 @@ pub fn levenshtein_distance(a: &[char], b: &[char]) -> u8 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_project_dictionary() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-project-{}", std::process::id()));
+        let path = dir.join(".polski-ls").join("slownik.txt");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut dict = SimpleDictionary::new();
+        dict.add_word("dom", false);
+        assert!(dict.add_project_word("kubernetes").is_err());
+
+        dict.load_project_dictionary(path.clone());
+        dict.add_project_word("Kubernetes").unwrap();
+        let word: Vec<char> = "kubernetes".chars().collect();
+        assert!(dict.contains(&word));
+        assert!(dict.word_info(&word).unwrap().is_project);
+        assert_eq!(dict.word_count(), 2);
+
+        // Reloading replaces the project words with the file contents
+        std::fs::write(&path, "terraform\n").unwrap();
+        dict.load_project_dictionary(path);
+        assert!(!dict.contains(&word));
+        assert!(dict.contains(&"Terraform".chars().collect::<Vec<_>>()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_extra_dictionaries() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-extra-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("terms.txt");
+        std::fs::write(&path, "# team terms\n*Grafana\n").unwrap();
+
+        let mut dict = SimpleDictionary::new();
+        dict.load_extra_dictionaries(&[path.clone(), dir.join("missing.txt")]);
+        let word: Vec<char> = "grafana".chars().collect();
+        assert!(dict.contains(&word));
+        assert!(dict.word_info(&word).unwrap().is_common);
+
+        dict.load_extra_dictionaries(&[]);
+        assert!(!dict.contains(&word));
+
+        // Dropping the extra list keeps a word that the base list also has
+        dict.add_word("GRAFANA", false);
+        dict.load_extra_dictionaries(&[path]);
+        dict.load_extra_dictionaries(&[]);
+        assert!(dict.contains(&word));
+        assert!(!dict.word_info(&word).unwrap().is_common);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_merge_user_dictionary() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("slownik.txt");
+        std::fs::write(&path, "kotlet\n").unwrap();
+
+        let mut dict = SimpleDictionary::new();
+        dict.add_word("dom", false);
+        assert_eq!(dict.merge_user_dictionary(), 0);
+
+        dict.user_dict_path = Some(path.clone());
+        assert_eq!(dict.merge_user_dictionary(), 1);
+        std::fs::write(&path, "kotlet\nDom\nschabowy\nschabowy\n").unwrap();
+        assert_eq!(dict.merge_user_dictionary(), 1);
+        let word: Vec<char> = "schabowy".chars().collect();
+        assert!(dict.word_info(&word).unwrap().is_user);
+        assert_eq!(dict.word_count(), 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_contains_many() {
+        let mut dict = SimpleDictionary::new();
+        dict.add_word("dom", false);
+        dict.add_word("Żółw", true);
+
+        let words: Vec<Vec<char>> = ["Dom", "kot", "żÓŁW", "dom", ""]
+            .iter()
+            .map(|w| w.chars().collect())
+            .collect();
+        let expected: Vec<bool> = words.iter().map(|w| dict.contains(w)).collect();
+        assert_eq!(dict.contains_many(&words), [true, false, true, true, false]);
+        assert_eq!(dict.contains_many(&words), expected);
+    }
+
+    #[test]
+    fn test_phonetic_match() {
+        let mut dict = SimpleDictionary::new();
+        for word in ["chrzan", "może", "morze", "góra", "zęby", "kot"] {
+            dict.add_word(word, false);
+        }
+        let word = |text: &str| -> Vec<char> { text.chars().collect() };
+        assert_eq!(phonetic_key(&word("Chrzan")), phonetic_key(&word("hżan")));
+        assert_eq!(phonetic_key(&word("zęby")), "zemby");
+
+        // Three edits away, found as one spelling mistake
+        let matches = dict.fuzzy_match(&word("hżan"), 2, 10);
+        assert_eq!(matches[0].word, word("chrzan"));
+        assert_eq!(matches[0].edit_distance, 1);
+
+        let words: Vec<String> = dict
+            .fuzzy_match(&word("może"), 1, 10)
+            .iter()
+            .map(|m| m.word.iter().collect())
+            .collect();
+        assert_eq!(words, ["może", "morze"]);
+        assert_eq!(dict.fuzzy_match(&word("gura"), 1, 10)[0].word, word("góra"));
+        assert!(dict.fuzzy_match(&word("hżan"), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_diacritic_match() {
+        let mut dict = SimpleDictionary::new();
+        for word in ["żółty", "żółtko", "Żory", "zolty", "źle"] {
+            dict.add_word(word, false);
+        }
+        let words = |matches: Vec<FuzzyMatchResult>| -> Vec<String> {
+            matches.iter().map(|m| m.word.iter().collect()).collect()
+        };
+
+        let word = |text: &str| -> Vec<char> { text.chars().collect() };
+        let restored = dict.diacritic_match(&word("zolty"), 10);
+        assert_eq!(words(restored), ["zolty", "żółty"]);
+        assert_eq!(words(dict.diacritic_match(&word("ŻOłty"), 10)), ["żółty"]);
+        assert!(dict.diacritic_match(&word("zoltyy"), 10).is_empty());
+        assert_eq!(
+            words(dict.diacritic_prefix_match(&word("zo"), 10)),
+            ["Żory", "zolty", "żółty", "żółtko"]
+        );
+    }
+
+    #[test]
+    fn test_prefix_match() {
+        let mut dict = SimpleDictionary::new();
+        for word in ["kotlet", "kot", "Kotwica", "koza", "akt"] {
+            dict.add_word(word, false);
+        }
+        dict.add_word("kota", true);
+
+        let words = |prefix: &str, max_results| -> Vec<String> {
+            let prefix: Vec<char> = prefix.chars().collect();
+            dict.prefix_match(&prefix, max_results)
+                .iter()
+                .map(|m| m.word.iter().collect())
+                .collect()
+        };
+        // Common words first, then shorter ones
+        assert_eq!(words("KOT", 10), ["kota", "kot", "kotlet", "Kotwica"]);
+        assert_eq!(words("kot", 2), ["kota", "kot"]);
+        assert!(words("kz", 10).is_empty());
+    }
+
+    #[test]
+    fn test_shared_dictionary_snapshots() {
+        let shared = SharedDictionary::new(SimpleDictionary::new());
+        let before = shared.load();
+        shared.update(|dict| dict.add_word("dom", false));
+
+        let word: Vec<char> = "dom".chars().collect();
+        assert!(shared.load().contains(&word));
+        // A snapshot taken earlier is not affected by the update
+        assert!(!before.contains(&word));
+
+        shared.replace(SimpleDictionary::new());
+        assert_eq!(shared.load().word_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_loading_keeps_configured_words() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-loading-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let extra = dir.join("extra.txt");
+        std::fs::write(&extra, "kubernetes\n").unwrap();
+
+        let shared = SharedDictionary::loading();
+        assert!(!shared.is_loaded());
+        // Settings arrive while the dictionary is still loading
+        shared.update(|dict| dict.load_extra_dictionaries(std::slice::from_ref(&extra)));
+
+        let mut loaded = SimpleDictionary::new();
+        loaded.add_word("dom", false);
+        shared.finish_loading(loaded);
+        shared.loaded().await;
+
+        let dict = shared.load();
+        assert!(dict.contains(&"dom".chars().collect::<Vec<_>>()));
+        assert!(dict.contains(&"kubernetes".chars().collect::<Vec<_>>()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_levenshtein_same() {
         let a: Vec<char> = "hello".chars().collect();
         let b: Vec<char> = "hello".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 0);
+        assert_eq!(osa_distance(&a, &b), 0);
     }
 
     #[test]
     fn test_levenshtein_one_char_diff() {
         let a: Vec<char> = "hello".chars().collect();
         let b: Vec<char> = "hallo".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 1);
+        assert_eq!(osa_distance(&a, &b), 1);
     }
 
     #[test]
     fn test_levenshtein_insertion() {
         let a: Vec<char> = "helo".chars().collect();
         let b: Vec<char> = "hello".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 1);
+        assert_eq!(osa_distance(&a, &b), 1);
     }
 
     #[test]
     fn test_levenshtein_deletion() {
         let a: Vec<char> = "hello".chars().collect();
         let b: Vec<char> = "helo".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 1);
+        assert_eq!(osa_distance(&a, &b), 1);
     }
 
     #[test]
     fn test_levenshtein_empty() {
         let a: Vec<char> = "".chars().collect();
         let b: Vec<char> = "hello".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 5);
+        assert_eq!(osa_distance(&a, &b), 5);
+    }
+
+    #[test]
+    fn test_osa_transposition() {
+        let a: Vec<char> = "śiwat".chars().collect();
+        let b: Vec<char> = "świat".chars().collect();
+        assert_eq!(osa_distance(&a, &b), 1);
+
+        // Each letter is edited at most once, so "ca" -> "abc" stays 3
+        let a: Vec<char> = "ca".chars().collect();
+        let b: Vec<char> = "abc".chars().collect();
+        assert_eq!(osa_distance(&a, &b), 3);
     }
 
     #[test]
@@ -312,10 +1376,11 @@ mod tests {
     fn test_case_insensitive() {
         let a: Vec<char> = "Hello".chars().collect();
         let b: Vec<char> = "hello".chars().collect();
-        assert_eq!(levenshtein_distance(&a, &b), 0);
+        assert_eq!(osa_distance(&a, &b), 0);
     }
 
     #[test]
+    #[cfg(feature = "embedded-dict")]
     fn test_embedded_dictionary() {
         let dict = SimpleDictionary::embedded();
         // Should have words from slowa.txt
@@ -328,6 +1393,105 @@ mod tests {
     }
 
     #[test]
+    fn test_word_info() {
+        let mut dict = SimpleDictionary::new();
+        dict.add_word("dzień", true);
+        dict.add_word("kot", false);
+        let path =
+            std::env::temp_dir().join(format!("polski-ls-word-info-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        dict.user_dict_path = Some(path.clone());
+        dict.add_user_word("Kraków").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let info = |word: &str| dict.word_info(&word.chars().collect::<Vec<char>>());
+        let known = |is_common, is_user| {
+            Some(WordInfo {
+                is_common,
+                rank: None,
+                is_user,
+                is_project: false,
+            })
+        };
+        assert_eq!(info("DZIEŃ"), known(true, false));
+        assert_eq!(info("kot"), known(false, false));
+        assert_eq!(info("kraków"), known(false, true));
+        assert_eq!(info("pies"), None);
+    }
+
+    #[test]
+    fn test_validate_word_list() {
+        let content = "# comment\n*dzień\nkot, pies\n*\n\ndobry!\nświat\n*w związku z tym\n";
+        let errors = validate_word_list(content);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 6]);
+        assert!(validate_word_list("*kot 152\npies\t9000\n").is_empty());
+        assert_eq!(validate_word_list("kot 0\n").len(), 1);
+        assert!(validate_word_list("biało-czerwony\nBielsko-Biała 4000\nJohnny'ego\n").is_empty());
+        assert_eq!(validate_word_list("-kot\npies-\n").len(), 2);
+    }
+
+    #[test]
+    fn test_frequency_ranks() {
+        let mut dict = SimpleDictionary::new();
+        dict.parse_word_list("kotek 9000\n*kot\nkotlet 1200\nkotwica\nKotlet 800\n");
+
+        let prefixed: Vec<String> = dict
+            .prefix_match(&"kot".chars().collect::<Vec<_>>(), 10)
+            .iter()
+            .map(|m| m.word.iter().collect())
+            .collect();
+        assert_eq!(prefixed, ["kot", "Kotlet", "kotlet", "kotek", "kotwica"]);
+
+        // Spellings differing in case share the best rank
+        let info = dict.word_info(&"kotlet".chars().collect::<Vec<_>>()).unwrap();
+        assert_eq!(info.rank, Some(800));
+        let fuzzy = dict.fuzzy_match(&"kotwca".chars().collect::<Vec<_>>(), 1, 1);
+        assert_eq!(fuzzy[0].rank, None);
+    }
+
+    #[test]
+    fn test_phrases() {
+        let mut dict = SimpleDictionary::new();
+        dict.parse_word_list("w\n*w związku z tym\nw  zasadzie 900\nzwiązku\nna przykład\n");
+
+        let typed = |text: &str| text.chars().collect::<Vec<_>>();
+        let phrases: Vec<String> = dict
+            .phrase_match(&typed("W z"), 10)
+            .iter()
+            .map(|m| m.word.iter().collect())
+            .collect();
+        assert_eq!(phrases, ["w związku z tym", "w zasadzie"]);
+        assert_eq!(dict.phrase_match(&typed("w zasadzie"), 10).len(), 0);
+        assert_eq!(dict.phrase_match(&typed("w zasadzie 900"), 10).len(), 0);
+        assert_eq!(dict.phrase_match(&typed("w zas"), 1)[0].rank, Some(900));
+
+        // Phrases stay out of word lookups
+        assert!(!dict.contains(&typed("na przykład")));
+        assert_eq!(dict.prefix_match(&typed("w"), 10).len(), 1);
+        assert!(dict.fuzzy_match(&typed("na przykad"), 2, 10).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-dict")]
+    fn test_validate_embedded_word_list() {
+        assert!(validate_word_list(include_str!("../slowa.txt")).is_empty());
+    }
+
+    #[test]
+    fn test_baseline_missing_system_dict() {
+        let options = DictionaryOptions {
+            use_embedded: false,
+            system_dict_path: PathBuf::from("/nonexistent/polski-ls/slowa.txt"),
+            backend: DictionaryBackend::Simple,
+        };
+        let dict = SimpleDictionary::baseline(&options);
+        let word: Vec<char> = "dzień".chars().collect();
+        assert!(!dict.contains(&word));
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-dict")]
     fn test_contains() {
         let dict = SimpleDictionary::embedded();
         // Common word should be found
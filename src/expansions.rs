@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use crate::dictionary::{user_config_dir, WordListError};
+
+/// File name of the user's abbreviation expansions in ~/.config/polski-ls/.
+pub const EXPANSIONS_FILE: &str = "skroty.tsv";
+
+/// Abbreviations completed to what they stand for ("np." to "na przykład"): the table
+/// shipped in skroty.tsv, extended by the user's own.
+///
+/// Source format is one entry per line: `abbreviation<TAB>expansion`, with `#` comments;
+/// a later entry for the same abbreviation replaces the earlier one.
+#[derive(Debug, Default)]
+pub struct Expansions {
+    /// Lowercase abbreviation -> its spelling and expansion.
+    entries: BTreeMap<String, (String, String)>,
+}
+
+impl Expansions {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the shipped table and ~/.config/polski-ls/skroty.tsv, if it exists.
+    pub fn load() -> Self {
+        let mut expansions = Self::new();
+        expansions.parse(include_str!("../skroty.tsv"));
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(EXPANSIONS_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading abbreviation expansions: {:?}", path);
+                expansions.parse(&content);
+            }
+        }
+
+        expansions
+    }
+
+    /// Add entries from expansion source text.
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let Some((abbreviation, expansion)) = trimmed.split_once('\t') else {
+                continue;
+            };
+            let (abbreviation, expansion) = (abbreviation.trim(), expansion.trim());
+            if !abbreviation.is_empty() && !expansion.is_empty() {
+                self.entries.insert(
+                    abbreviation.to_lowercase(),
+                    (abbreviation.to_string(), expansion.to_string()),
+                );
+            }
+        }
+    }
+
+    /// Abbreviations starting with `typed` (ignoring case), with their expansions.
+    pub fn matching(&self, typed: &[char]) -> Vec<(&str, &str)> {
+        let key: String = typed.iter().flat_map(|c| c.to_lowercase()).collect();
+        self.entries
+            .range(key.clone()..)
+            .take_while(|(lower, _)| lower.starts_with(&key))
+            .map(|(_, (abbreviation, expansion))| (abbreviation.as_str(), expansion.as_str()))
+            .collect()
+    }
+}
+
+/// Validate expansion source text, reporting lines without an expansion.
+pub fn validate_expansions(content: &str) -> Vec<WordListError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .filter_map(|(idx, line)| {
+            let (abbreviation, expansion) =
+                line.trim().split_once('\t').unwrap_or((line.trim(), ""));
+            if !abbreviation.trim().is_empty() && !expansion.trim().is_empty() {
+                return None;
+            }
+            Some(WordListError {
+                line: idx + 1,
+                message: format!(
+                    "missing tab-separated expansion for '{}'",
+                    abbreviation.trim()
+                ),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_matching() {
+        let mut expansions = Expansions::new();
+        expansions.parse("# comment\ntzn.\tto znaczy\ntzw.\ttak zwany\nnp.\tnp\n");
+        expansions.parse("NP.\tna przykład\n");
+
+        assert_eq!(
+            expansions.matching(&chars("Tz")),
+            vec![("tzn.", "to znaczy"), ("tzw.", "tak zwany")]
+        );
+        assert_eq!(
+            expansions.matching(&chars("np.")),
+            vec![("NP.", "na przykład")]
+        );
+        assert!(expansions.matching(&chars("np.x")).is_empty());
+    }
+
+    #[test]
+    fn test_shipped_table() {
+        assert!(validate_expansions(include_str!("../skroty.tsv")).is_empty());
+        let mut expansions = Expansions::new();
+        expansions.parse(include_str!("../skroty.tsv"));
+        assert_eq!(
+            expansions.matching(&chars("m.in")),
+            vec![("m.in.", "między innymi")]
+        );
+    }
+
+    #[test]
+    fn test_validate_expansions() {
+        let errors = validate_expansions("# comment\nnp.\tna przykład\ntzn.\n\tto znaczy\n");
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+}
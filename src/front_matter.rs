@@ -0,0 +1,111 @@
+use std::ops::Range;
+
+use crate::pos_conv::line_ranges;
+
+/// Whether documents in `language_id` may start with front matter.
+pub fn is_markdown(language_id: &str) -> bool {
+    language_id == "markdown"
+}
+
+/// Character ranges of the YAML (`---`) or TOML (`+++`) front matter at the top of a
+/// Markdown document, except the values of `checked_keys` ("title", "description"), which
+/// are prose without the quotes around them. Keys, dates, slugs and tags are metadata and
+/// aren't checked.
+pub fn non_prose(source: &[char], checked_keys: &[String]) -> Vec<Range<usize>> {
+    let mut lines = line_ranges(source);
+    let Some(first) = lines.next() else {
+        return Vec::new();
+    };
+    let fence: String = source[first.clone()].iter().collect();
+    let fence = fence.trim_end();
+    if fence != "---" && fence != "+++" {
+        return Vec::new();
+    }
+
+    let mut spans = vec![first];
+    // Whether the value of a checked key continues on indented lines
+    let mut in_checked_value = false;
+    for line in lines {
+        let text: String = source[line.clone()].iter().collect();
+        if text.trim_end() == fence {
+            spans.push(line);
+            return spans;
+        }
+        if text.starts_with([' ', '\t']) && in_checked_value {
+            continue;
+        }
+        in_checked_value = false;
+
+        let separator = if fence == "---" { ':' } else { '=' };
+        let Some((raw_key, value)) = text.split_once(separator) else {
+            spans.push(line);
+            continue;
+        };
+        let key = raw_key.trim().trim_matches(['"', '\'']);
+        if checked_keys.iter().any(|checked| checked == key) {
+            // Only the key and its separator are skipped, and the quotes of a string
+            let value_start = line.start + raw_key.chars().count() + 1;
+            spans.push(line.start..value_start);
+            let trimmed = value.trim();
+            if trimmed.len() > 1
+                && (trimmed.starts_with('"') && trimmed.ends_with('"')
+                    || trimmed.starts_with('\'') && trimmed.ends_with('\''))
+            {
+                let open = value_start + value.chars().take_while(|c| c.is_whitespace()).count();
+                let close = open + trimmed.chars().count() - 1;
+                spans.extend([open..open + 1, close..close + 1]);
+            }
+            in_checked_value = true;
+        } else {
+            spans.push(line);
+        }
+    }
+    // Without a closing fence, this is a horizontal rule rather than front matter
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of `document` left for spell-checking, with skipped characters dropped.
+    fn checked(document: &str, keys: &[&str]) -> String {
+        let source: Vec<char> = document.chars().collect();
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        let spans = non_prose(&source, &keys);
+        source
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !spans.iter().any(|span| span.contains(idx)))
+            .map(|(_, ch)| *ch)
+            .collect()
+    }
+
+    #[test]
+    fn test_yaml_front_matter() {
+        let document = "---\n\
+            title: \"Mój wpis\"\n\
+            date: 2024-05-01\n\
+            slug: moj-wpis\n\
+            description: >\n  Długi opis\n\
+            tags:\n  - notatki\n\
+            ---\n\
+            Treść";
+        assert_eq!(
+            checked(document, &["title", "description"]),
+            "\n Mój wpis\n\n\n >\n  Długi opis\n\n\n\nTreść"
+        );
+    }
+
+    #[test]
+    fn test_toml_front_matter() {
+        let document = "+++\ntitle = 'Wpis'\ndraft = true\n+++\nTreść";
+        assert_eq!(checked(document, &["title"]), "\n Wpis\n\n\nTreść");
+        // A rule in the middle of the text, or one never closed, isn't front matter
+        assert_eq!(
+            checked("Tekst\n---\nklucz: x\n---", &[]),
+            "Tekst\n---\nklucz: x\n---"
+        );
+        assert_eq!(checked("---\nklucz: x", &[]), "---\nklucz: x");
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use fst::automaton::{AlwaysMatch, Levenshtein, Str};
+use fst::{Automaton, IntoStreamer, Map, Streamer};
+
+use crate::dictionary::{lowercase_key, osa_distance, Dictionary, FuzzyMatchResult};
+
+/// Dictionary backend storing the lowercase forms in a finite state transducer: shared
+/// prefixes and suffixes of inflected forms are stored once, fuzzy queries walk it with a
+/// Levenshtein automaton and prefix queries with a byte-level prefix automaton.
+///
+/// The transducer can't change once built, so words added later (the user and project
+/// dictionaries) are kept in a small list that is searched directly.
+#[derive(Clone)]
+pub struct FstDictionary {
+    /// Lowercase form -> bit 0: common, other bits: 1 + index into `spellings`, or 0 when
+    /// the word is spelled in lowercase.
+    map: Arc<Map<Vec<u8>>>,
+    /// Spellings that differ from their lowercase form, e.g. proper nouns.
+    spellings: Vec<Vec<char>>,
+    added: Vec<(Vec<char>, bool)>,
+}
+
+impl FstDictionary {
+    /// Build the transducer from `entries`. Spellings differing only in case are merged,
+    /// keeping the first one.
+    pub fn build<'a>(entries: impl Iterator<Item = (&'a str, bool)>) -> Self {
+        let mut forms: BTreeMap<String, (&'a str, bool)> = BTreeMap::new();
+        for (word, is_common) in entries {
+            let key = word.chars().flat_map(char::to_lowercase).collect();
+            let form = forms.entry(key).or_insert((word, false));
+            form.1 |= is_common;
+        }
+
+        let mut spellings = Vec::new();
+        let values: Vec<(String, u64)> = forms
+            .into_iter()
+            .map(|(key, (word, is_common))| {
+                let mut value = u64::from(is_common);
+                if word != key {
+                    spellings.push(word.chars().collect());
+                    value |= (spellings.len() as u64) << 1;
+                }
+                (key, value)
+            })
+            .collect();
+        // Keys come sorted and unique from the BTreeMap, which is all building needs
+        let map = Map::from_iter(values).expect("sorted unique keys");
+
+        Self {
+            map: Arc::new(map),
+            spellings,
+            added: Vec::new(),
+        }
+    }
+
+    /// Add a word after the transducer was built.
+    pub fn add_word(&mut self, word: &str, is_common: bool) {
+        self.added.push((word.chars().collect(), is_common));
+    }
+
+    /// Call `visit` with every word whose lowercase form `automaton` accepts.
+    fn search<A: Automaton>(&self, automaton: A, mut visit: impl FnMut(Vec<char>, bool)) {
+        let mut stream = self.map.search(automaton).into_stream();
+        while let Some((key, value)) = stream.next() {
+            let word = match (value >> 1) as usize {
+                0 => String::from_utf8_lossy(key).chars().collect(),
+                spelling => self.spellings[spelling - 1].clone(),
+            };
+            visit(word, value & 1 == 1);
+        }
+    }
+}
+
+impl Dictionary for FstDictionary {
+    fn contains(&self, word: &[char]) -> bool {
+        let key = lowercase_key(word);
+        self.map.contains_key(&key) || self.added.iter().any(|(w, _)| lowercase_key(w) == key)
+    }
+
+    fn prefix_match(&self, prefix: &[char], max_results: usize) -> Vec<FuzzyMatchResult> {
+        let key = lowercase_key(prefix);
+        let mut results = Vec::new();
+        let mut visit = |word: Vec<char>, is_common| {
+            results.push(FuzzyMatchResult {
+                word,
+                edit_distance: 0,
+                is_common,
+                rank: None,
+            });
+        };
+        self.search(Str::new(&key).starts_with(), &mut visit);
+        for (word, is_common) in &self.added {
+            if lowercase_key(word).starts_with(&key) {
+                visit(word.clone(), *is_common);
+            }
+        }
+
+        results.sort_by_key(|m| (!m.is_common, m.word.len()));
+        results.truncate(max_results);
+        results
+    }
+
+    fn fuzzy_match(
+        &self,
+        prefix: &[char],
+        max_edit_distance: u8,
+        max_results: usize,
+    ) -> Vec<FuzzyMatchResult> {
+        let mut results = Vec::new();
+        let mut seen = HashSet::new();
+        // The automata work on lowercase forms; the distance is measured the same way as
+        // the other backends do, so all of them agree
+        let mut visit = |word: Vec<char>, is_common| {
+            let edit_distance = osa_distance(prefix, &word);
+            if edit_distance <= max_edit_distance && seen.insert(word.clone()) {
+                results.push(FuzzyMatchResult {
+                    word,
+                    edit_distance,
+                    is_common,
+                    rank: None,
+                });
+            }
+        };
+
+        let key: Vec<char> = lowercase_key(prefix).chars().collect();
+        let automata: Option<Vec<Levenshtein>> = if max_edit_distance <= MAX_AUTOMATON_DISTANCE {
+            transposed_queries(&key, max_edit_distance)
+                .iter()
+                .map(|(query, distance)| Levenshtein::new(query, u32::from(*distance)).ok())
+                .collect()
+        } else {
+            None
+        };
+        match automata {
+            Some(automata) => {
+                for automaton in automata {
+                    self.search(automaton, &mut visit);
+                }
+            }
+            // Automata for long queries or wide distances grow too large; walk every word
+            None => self.search(AlwaysMatch, &mut visit),
+        }
+        for (word, is_common) in &self.added {
+            visit(word.clone(), *is_common);
+        }
+
+        results.sort_by(|a, b| {
+            a.edit_distance
+                .cmp(&b.edit_distance)
+                .then_with(|| b.is_common.cmp(&a.is_common))
+        });
+        results.truncate(max_results);
+        results
+    }
+
+    fn word_count(&self) -> usize {
+        self.map.len() + self.added.len()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "fst"
+    }
+}
+
+/// Largest edit distance searched with automata; wider queries walk every word.
+const MAX_AUTOMATON_DISTANCE: u8 = 2;
+
+/// Levenshtein automata don't count an adjacent transposition as one edit, so besides the
+/// key itself this also queries the key with one or two transpositions already applied,
+/// with the distance left after them.
+fn transposed_queries(key: &[char], max_edit_distance: u8) -> Vec<(String, u8)> {
+    let mut queries = vec![(key.iter().collect(), max_edit_distance)];
+    if max_edit_distance == 0 {
+        return queries;
+    }
+
+    let swaps: Vec<usize> = (1..key.len()).filter(|&i| key[i - 1] != key[i]).collect();
+    for (n, &first) in swaps.iter().enumerate() {
+        let mut once = key.to_vec();
+        once.swap(first - 1, first);
+        queries.push((once.iter().collect(), max_edit_distance - 1));
+
+        if max_edit_distance >= 2 {
+            // Transpositions never overlap in an optimal string alignment
+            for &second in swaps[n + 1..].iter().filter(|&&second| second > first + 1) {
+                let mut twice = once.clone();
+                twice.swap(second - 1, second);
+                queries.push((twice.iter().collect(), max_edit_distance - 2));
+            }
+        }
+    }
+    queries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::SimpleDictionary;
+
+    const WORDS: &str = "kot kota kotek płot świat światło zażółć Warszawa warsztat \
+                         przedsiębiorstwo przedsiębiorca dom domek okno";
+
+    fn entries() -> impl Iterator<Item = (&'static str, bool)> {
+        WORDS
+            .split_whitespace()
+            .enumerate()
+            .map(|(idx, word)| (word, idx % 3 == 0))
+    }
+
+    #[test]
+    fn test_fuzzy_match_agrees_with_full_scan() {
+        let fst = FstDictionary::build(entries());
+        let mut simple = SimpleDictionary::new();
+        for (word, is_common) in entries() {
+            simple.add_word(word, is_common);
+        }
+
+        let words = |matches: Vec<FuzzyMatchResult>| -> Vec<(String, u8, bool)> {
+            let mut words: Vec<(String, u8, bool)> = matches
+                .into_iter()
+                .map(|m| (m.word.iter().collect(), m.edit_distance, m.is_common))
+                .collect();
+            words.sort();
+            words
+        };
+        let queries = "kto swiat śiwat wraszwaa przedsiebiorstwo dmoek xyz ok";
+        for query in queries.split_whitespace() {
+            let query: Vec<char> = query.chars().collect();
+            for distance in 0..=3 {
+                assert_eq!(
+                    words(fst.fuzzy_match(&query, distance, 100)),
+                    words(simple.fuzzy_match(&query, distance, 100)),
+                    "{:?} within {}",
+                    query,
+                    distance
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_added_words_and_spellings() {
+        let mut fst = FstDictionary::build(entries());
+        fst.add_word("Kotlina", false);
+
+        let word = |text: &str| -> Vec<char> { text.chars().collect() };
+        assert!(fst.contains(&word("WARSZAWA")));
+        assert!(fst.contains(&word("kotlina")));
+        assert!(!fst.contains(&word("przedsiębiorstwa")));
+
+        let prefixed: Vec<String> = fst
+            .prefix_match(&word("kot"), 10)
+            .iter()
+            .map(|m| m.word.iter().collect())
+            .collect();
+        assert_eq!(prefixed, ["kot", "kota", "kotek", "Kotlina"]);
+
+        // Proper nouns keep their capitalization
+        let matches = fst.fuzzy_match(&word("warszawa"), 0, 10);
+        assert_eq!(matches[0].word, word("Warszawa"));
+        assert_eq!(fst.word_count(), WORDS.split_whitespace().count() + 1);
+    }
+}
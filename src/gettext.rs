@@ -0,0 +1,85 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Placeholders filled in by the program (`%s`, `%1$d`, `%(name)s`, `{name}`, `{0}`)
+/// and escape sequences (`\n`), none of which is translated text.
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"%(?:\d+\$|\([^)]*\))?[-+ #0]*\d*(?:\.\d+)?[a-zA-Z%]|\{[^{}\s]*\}|\\.").unwrap()
+});
+
+/// Whether documents in `language_id` are gettext translation catalogs.
+pub fn is_po(language_id: &str) -> bool {
+    language_id == "po"
+}
+
+/// Character ranges of a .po catalog that aren't translations: everything but the
+/// `msgstr` strings (source texts, comments, keywords) and the placeholders within them.
+pub fn non_prose(source: &[char]) -> Vec<Range<usize>> {
+    let text: String = source.iter().collect();
+    let mut spans = Vec::new();
+    let mut in_msgstr = false;
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let line_len = line.chars().count();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("msgstr") {
+            in_msgstr = true;
+        } else if !trimmed.starts_with('"') {
+            // A comment, msgctxt or msgid ends the translation
+            in_msgstr = false;
+        }
+
+        if in_msgstr {
+            // The keyword up to the opening quote, then the placeholders
+            let quote = line.find('"').unwrap_or(line.len());
+            let char_index = |offset: usize| line_start + line[..offset].chars().count();
+            spans.push(line_start..char_index(quote));
+            for placeholder in PLACEHOLDER.find_iter(&line[quote..]) {
+                let start = char_index(quote + placeholder.start());
+                let end = char_index(quote + placeholder.end());
+                spans.push(start..end);
+            }
+        } else {
+            spans.push(line_start..line_start + line_len);
+        }
+        line_start += line_len + 1;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of `catalog` left for spell-checking.
+    fn checked(catalog: &str) -> String {
+        let source: Vec<char> = catalog.chars().collect();
+        let spans = non_prose(&source);
+        source
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !spans.iter().any(|span| span.contains(idx)))
+            .map(|(_, ch)| *ch)
+            .collect()
+    }
+
+    #[test]
+    fn test_only_translations_are_checked() {
+        let catalog = "# Translator comment\n\
+            #: src/main.rs:10\n\
+            msgid \"Hello, %s!\"\n\
+            msgstr \"Witaj, %s!\"\n\
+            \n\
+            msgid \"\"\n\
+            \"%(count)d files in {dir}\\n\"\n\
+            msgstr \"\"\n\
+            \"%(count)d plików w {dir}\\n\"\n\
+            \"i %1$s katalogów\"";
+        assert_eq!(
+            checked(catalog),
+            "\n\n\n\"Witaj, !\"\n\n\n\n\"\"\n\" plików w \"\n\"i  katalogów\""
+        );
+    }
+}
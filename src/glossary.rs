@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::dictionary::{user_config_dir, WordListError};
+use crate::morphology::Analysis;
+
+/// File name of the optional PL→EN glossary in ~/.config/polski-ls/.
+pub const GLOSSARY_FILE: &str = "glosariusz.tsv";
+
+/// Optional offline Polish→English glossary shown on hover.
+///
+/// Source format is one entry per line: `word<TAB>English translation`, with `#`
+/// comments. Entries are usually keyed by lemma; inflected forms are resolved
+/// through the morphology data.
+#[derive(Debug, Default)]
+pub struct Glossary {
+    entries: HashMap<String, String>,
+}
+
+impl Glossary {
+    /// Create an empty glossary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load ~/.config/polski-ls/glosariusz.tsv; the glossary stays empty if it doesn't exist.
+    pub fn load() -> Self {
+        let mut glossary = Self::new();
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(GLOSSARY_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading glossary: {:?}", path);
+                glossary.parse(&content);
+            }
+        }
+
+        glossary
+    }
+
+    /// Add entries from glossary source text; later entries override earlier ones.
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let Some((word, translation)) = trimmed.split_once('\t') else {
+                continue;
+            };
+            let (word, translation) = (word.trim(), translation.trim());
+            if !word.is_empty() && !translation.is_empty() {
+                self.entries
+                    .insert(word.to_lowercase(), translation.to_string());
+            }
+        }
+    }
+
+    /// Translations for a word: the form itself first, then each of its lemmas.
+    pub fn translate(&self, word: &[char], analyses: &[Analysis]) -> Vec<&str> {
+        let form: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+        let keys = std::iter::once(form).chain(analyses.iter().map(|a| a.lemma.to_lowercase()));
+
+        let mut translations: Vec<&str> = Vec::new();
+        for key in keys {
+            if let Some(translation) = self.entries.get(&key) {
+                if !translations.contains(&translation.as_str()) {
+                    translations.push(translation);
+                }
+            }
+        }
+        translations
+    }
+}
+
+/// Validate glossary source text, reporting lines without a translation.
+pub fn validate_glossary(content: &str) -> Vec<WordListError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .filter_map(|(idx, line)| {
+            let (word, translation) = line.trim().split_once('\t').unwrap_or((line.trim(), ""));
+            if !word.trim().is_empty() && !translation.trim().is_empty() {
+                return None;
+            }
+            Some(WordListError {
+                line: idx + 1,
+                message: format!("missing tab-separated translation for '{}'", word.trim()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    fn analysis(lemma: &str) -> Analysis {
+        Analysis {
+            lemma: lemma.to_string(),
+            part_of_speech: "rzeczownik".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_translate_form_and_lemma() {
+        let mut glossary = Glossary::new();
+        glossary.parse("# comment\ndzień\tday\ndnia\tof the day\n");
+
+        assert_eq!(glossary.translate(&chars("Dzień"), &[]), vec!["day"]);
+        assert_eq!(
+            glossary.translate(&chars("dnia"), &[analysis("dzień")]),
+            vec!["of the day", "day"]
+        );
+        assert_eq!(
+            glossary.translate(&chars("dniu"), &[analysis("dzień")]),
+            vec!["day"]
+        );
+        assert!(glossary.translate(&chars("kot"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_translations_are_merged() {
+        let mut glossary = Glossary::new();
+        glossary.parse("ciepło\twarm\nciepły\twarm\n");
+        let analyses = [analysis("ciepło"), analysis("ciepły")];
+        assert_eq!(
+            glossary.translate(&chars("ciepła"), &analyses),
+            vec!["warm"]
+        );
+    }
+
+    #[test]
+    fn test_validate_glossary() {
+        let content = "# c\ndom\thouse\nkot\n\tcat\n";
+        let lines: Vec<usize> = validate_glossary(content).iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+}
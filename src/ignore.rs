@@ -0,0 +1,274 @@
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Name of the per-workspace ignore file, looked up in the workspace root.
+pub const IGNORE_FILE_NAME: &str = ".polski-ls-ignore";
+
+/// A syntax problem in an ignore file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Glob patterns for files that are never checked, relative to a workspace root.
+///
+/// Syntax follows .prettierignore/.gitignore: one pattern per line, `#` comments,
+/// a leading `/` anchors the pattern to the root, a trailing `/` matches a directory
+/// and everything below it, and patterns without a `/` match at any depth.
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    root: PathBuf,
+    globs: GlobSet,
+}
+
+impl IgnoreFile {
+    /// Load `.polski-ls-ignore` from the workspace root.
+    /// Returns `None` if there is no such file; invalid lines are logged and skipped.
+    pub fn load(root: &Path) -> Option<Self> {
+        let path = root.join(IGNORE_FILE_NAME);
+        let content = std::fs::read_to_string(&path).ok()?;
+        eprintln!("[POLSKI-LS] Loading ignore file: {:?}", path);
+
+        let (ignore, errors) = Self::parse(root, &content);
+        for error in errors {
+            eprintln!(
+                "[POLSKI-LS] ERROR: {:?}:{}: {}",
+                path, error.line, error.message
+            );
+        }
+        Some(ignore)
+    }
+
+    /// Parse ignore file content, returning the valid patterns and errors for the rest.
+    pub fn parse(root: &Path, content: &str) -> (Self, Vec<IgnoreError>) {
+        let mut builder = GlobSetBuilder::new();
+        let mut errors = Vec::new();
+
+        for (idx, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            match pattern_globs(trimmed) {
+                Ok(globs) => {
+                    for glob in globs {
+                        builder.add(glob);
+                    }
+                }
+                Err(message) => errors.push(IgnoreError {
+                    line: idx + 1,
+                    message,
+                }),
+            }
+        }
+
+        let globs = builder.build().unwrap_or_else(|e| {
+            eprintln!("[POLSKI-LS] ERROR: Failed to build ignore patterns: {}", e);
+            GlobSet::empty()
+        });
+
+        let ignore = Self {
+            root: root.to_path_buf(),
+            globs,
+        };
+        (ignore, errors)
+    }
+
+    /// Check whether a file is excluded. Paths outside the workspace root are never ignored.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .is_ok_and(|relative| self.globs.is_match(relative))
+    }
+}
+
+/// Include and exclude patterns from the `files` settings, relative to the workspace
+/// root. Without a workspace, or for files outside it, only unanchored exclude patterns
+/// such as `*.log` can match.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    root: Option<PathBuf>,
+    /// `None` when every file is included.
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    /// Build the filter, returning invalid patterns as errors; those are skipped.
+    pub fn new(
+        root: Option<&Path>,
+        include: &[String],
+        exclude: &[String],
+    ) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut build = |patterns: &[String]| {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                match pattern_globs(pattern) {
+                    Ok(globs) => {
+                        for glob in globs {
+                            builder.add(glob);
+                        }
+                    }
+                    Err(message) => errors.push(message),
+                }
+            }
+            builder.build().unwrap_or_else(|e| {
+                errors.push(format!("failed to build patterns: {}", e));
+                GlobSet::empty()
+            })
+        };
+
+        let filter = Self {
+            root: root.map(Path::to_path_buf),
+            include: (!include.is_empty()).then(|| build(include)),
+            exclude: build(exclude),
+        };
+        (filter, errors)
+    }
+
+    /// Check whether a file is filtered out.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let relative = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok());
+        match relative {
+            Some(relative) => {
+                self.include
+                    .as_ref()
+                    .is_some_and(|include| !include.is_match(relative))
+                    || self.exclude.is_match(relative)
+            }
+            None => {
+                // Drop the root (and drive prefix) so `**/` patterns can match
+                let path: PathBuf = path
+                    .components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .collect();
+                self.exclude.is_match(path)
+            }
+        }
+    }
+}
+
+/// Translate one ignore pattern into globs matching the file itself and anything below it.
+fn pattern_globs(pattern: &str) -> Result<Vec<Glob>, String> {
+    if pattern.starts_with('!') {
+        return Err(format!("negated patterns are not supported: '{}'", pattern));
+    }
+
+    let directory_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return Err(format!("pattern matches nothing: '{}'", pattern));
+    }
+
+    let base = if anchored {
+        trimmed.to_string()
+    } else {
+        format!("**/{}", trimmed)
+    };
+
+    let mut sources = vec![format!("{}/**", base)];
+    if !directory_only {
+        sources.push(base);
+    }
+
+    sources
+        .iter()
+        .map(|source| {
+            GlobBuilder::new(source)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore(content: &str) -> IgnoreFile {
+        let (ignore, errors) = IgnoreFile::parse(Path::new("/ws"), content);
+        assert!(errors.is_empty(), "{:?}", errors);
+        ignore
+    }
+
+    #[test]
+    fn test_unanchored_pattern_matches_any_depth() {
+        let ignore = ignore("*.log\n");
+        assert!(ignore.is_ignored(Path::new("/ws/build.log")));
+        assert!(ignore.is_ignored(Path::new("/ws/a/b/build.log")));
+        assert!(!ignore.is_ignored(Path::new("/ws/notes.md")));
+    }
+
+    #[test]
+    fn test_directory_pattern() {
+        let ignore = ignore("# third-party\nvendor/\n");
+        assert!(ignore.is_ignored(Path::new("/ws/vendor/README.md")));
+        assert!(ignore.is_ignored(Path::new("/ws/lib/vendor/x/y.txt")));
+        assert!(!ignore.is_ignored(Path::new("/ws/vendor")));
+    }
+
+    #[test]
+    fn test_anchored_pattern() {
+        let ignore = ignore("/docs/en\n");
+        assert!(ignore.is_ignored(Path::new("/ws/docs/en/index.md")));
+        assert!(!ignore.is_ignored(Path::new("/ws/other/docs/en/index.md")));
+    }
+
+    #[test]
+    fn test_paths_outside_root() {
+        let ignore = ignore("*.md\n");
+        assert!(!ignore.is_ignored(Path::new("/elsewhere/notes.md")));
+    }
+
+    #[test]
+    fn test_file_filter() {
+        let patterns = |list: &[&str]| list.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let (filter, errors) = FileFilter::new(
+            Some(Path::new("/ws")),
+            &patterns(&["docs/", "*.md"]),
+            &patterns(&["docs/drafts/", "!x"]),
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(!filter.is_excluded(Path::new("/ws/docs/guide.txt")));
+        assert!(!filter.is_excluded(Path::new("/ws/README.md")));
+        assert!(filter.is_excluded(Path::new("/ws/src/main.rs")));
+        assert!(filter.is_excluded(Path::new("/ws/docs/drafts/a.md")));
+        assert!(!filter.is_excluded(Path::new("/elsewhere/main.rs")));
+
+        let (filter, _) = FileFilter::new(Some(Path::new("/ws")), &[], &patterns(&["*.log"]));
+        assert!(!filter.is_excluded(Path::new("/ws/src/main.rs")));
+        assert!(filter.is_excluded(Path::new("/ws/build.log")));
+    }
+
+    #[test]
+    fn test_file_filter_outside_workspace() {
+        let patterns = |list: &[&str]| list.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let (filter, _) = FileFilter::new(
+            None,
+            &patterns(&["*.md"]),
+            &patterns(&["*.log", "vendor/", "/docs/drafts"]),
+        );
+        assert!(filter.is_excluded(Path::new("/var/log/app.log")));
+        assert!(filter.is_excluded(Path::new("/src/proj/vendor/lib/README.md")));
+        assert!(!filter.is_excluded(Path::new("/home/user/docs/drafts/notes.md")));
+        assert!(!filter.is_excluded(Path::new("/home/user/main.rs")));
+    }
+
+    #[test]
+    fn test_errors_have_line_numbers() {
+        let (ignore, errors) = IgnoreFile::parse(Path::new("/ws"), "*.log\n!keep.log\n[z-a\n");
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3]);
+        assert!(ignore.is_ignored(Path::new("/ws/a.log")));
+    }
+}
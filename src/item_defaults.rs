@@ -0,0 +1,187 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use serde_json::Value;
+use tower_lsp_server::jsonrpc::{Request, Response};
+use tower_service::Service;
+
+/// Wraps the server so completion lists carry `itemDefaults` (LSP 3.17) for clients that
+/// support them. The protocol types the server is written against can't express them, so
+/// responses are rewritten on their way out: the edit range shared by the typed words is
+/// sent once on the list instead of on every item.
+pub struct ItemDefaults<S> {
+    inner: S,
+    /// Set on initialize when the client accepts a default edit range.
+    supported: Arc<AtomicBool>,
+}
+
+impl<S> ItemDefaults<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            supported: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl<S> Service<Request> for ItemDefaults<S>
+where
+    S: Service<Request, Response = Option<Response>>,
+    S::Future: Send + 'static,
+{
+    type Response = Option<Response>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if request.method() == "initialize" {
+            let supported = request.params().is_some_and(supports_edit_range);
+            self.supported.store(supported, Ordering::Relaxed);
+        }
+        let rewrite =
+            request.method() == "textDocument/completion" && self.supported.load(Ordering::Relaxed);
+
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            Ok(response.map(|response| {
+                if !rewrite {
+                    return response;
+                }
+                let (id, result) = response.into_parts();
+                let result = result.map(|mut list| {
+                    share_edit_range(&mut list);
+                    list
+                });
+                Response::from_parts(id, result)
+            }))
+        })
+    }
+}
+
+/// Whether initialize `params` say the client accepts a default edit range.
+fn supports_edit_range(params: &Value) -> bool {
+    params
+        .pointer("/capabilities/textDocument/completion/completionList/itemDefaults")
+        .and_then(Value::as_array)
+        .is_some_and(|defaults| defaults.iter().any(|d| d == "editRange"))
+}
+
+/// Move the edit range most items of the completion `list` share into its defaults.
+/// Items replacing other text (phrases, abbreviations) keep their own edit; the others
+/// keep only the text they insert, and not even that when it is their label.
+fn share_edit_range(list: &mut Value) {
+    let Some(items) = list.get_mut("items").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let mut ranges: Vec<(&Value, usize)> = Vec::new();
+    let edited = items
+        .iter()
+        .filter_map(|item| item.pointer("/textEdit/range"));
+    for range in edited {
+        match ranges.iter_mut().find(|(seen, _)| *seen == range) {
+            Some((_, count)) => *count += 1,
+            None => ranges.push((range, 1)),
+        }
+    }
+    // A range on a single item costs less there than on the list
+    let Some((range, _)) = ranges
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .max_by_key(|(_, count)| *count)
+    else {
+        return;
+    };
+    let range = range.clone();
+
+    for item in items.iter_mut() {
+        let Some(item) = item.as_object_mut() else {
+            continue;
+        };
+        if item.get("textEdit").and_then(|edit| edit.get("range")) != Some(&range) {
+            continue;
+        }
+        let Some(edit) = item.remove("textEdit") else {
+            continue;
+        };
+        let new_text = edit.get("newText").cloned().unwrap_or_default();
+        if item.get("label") != Some(&new_text) {
+            item.insert("textEditText".to_string(), new_text);
+        }
+    }
+    list["itemDefaults"] = serde_json::json!({ "editRange": range });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn range(start: u32, end: u32) -> Value {
+        json!({
+            "start": { "line": 0, "character": start },
+            "end": { "line": 0, "character": end },
+        })
+    }
+
+    fn item(label: &str, new_text: &str, range: Value) -> Value {
+        json!({ "label": label, "textEdit": { "range": range, "newText": new_text } })
+    }
+
+    #[test]
+    fn test_share_edit_range() {
+        let mut list = json!({
+            "isIncomplete": true,
+            "items": [
+                item("kot", "kot", range(4, 6)),
+                item("np.", "na przykład", range(4, 6)),
+                item("w związku z tym", "w związku z tym", range(0, 6)),
+            ],
+        });
+        share_edit_range(&mut list);
+
+        assert_eq!(list["itemDefaults"], json!({ "editRange": range(4, 6) }));
+        assert_eq!(list["items"][0], json!({ "label": "kot" }));
+        assert_eq!(
+            list["items"][1],
+            json!({ "label": "np.", "textEditText": "na przykład" })
+        );
+        assert_eq!(
+            list["items"][2],
+            item("w związku z tym", "w związku z tym", range(0, 6))
+        );
+    }
+
+    #[test]
+    fn test_single_item_keeps_its_edit() {
+        let mut list = json!({ "isIncomplete": true, "items": [item("kot", "kot", range(4, 6))] });
+        let unchanged = list.clone();
+        share_edit_range(&mut list);
+        assert_eq!(list, unchanged);
+
+        // Responses without a list are left alone
+        let mut empty = Value::Null;
+        share_edit_range(&mut empty);
+        assert_eq!(empty, Value::Null);
+    }
+
+    #[test]
+    fn test_supports_edit_range() {
+        let params = |defaults: Value| {
+            json!({ "capabilities": { "textDocument": { "completion": {
+                "completionList": { "itemDefaults": defaults }
+            } } } })
+        };
+        let defaults = json!(["commitCharacters", "editRange"]);
+        assert!(supports_edit_range(&params(defaults)));
+        assert!(!supports_edit_range(&params(json!(["data"]))));
+        assert!(!supports_edit_range(&json!({ "capabilities": {} })));
+    }
+}
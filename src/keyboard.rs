@@ -0,0 +1,172 @@
+use serde::Deserialize;
+
+/// Cost of typing one character in place of another, used to weigh substitutions when
+/// ranking corrections. Implement it for another layout and add a `KeyboardLayout`
+/// variant returning it to make that layout selectable.
+pub trait SubstitutionCost: Send + Sync {
+    /// Cost between 0 (same letter) and 1 (unrelated keys) of typing `typed` instead of
+    /// `intended`.
+    fn substitution_cost(&self, typed: char, intended: char) -> f32;
+}
+
+/// Keyboard layout the substitution costs are modelled on, set with `scoring.keyboardLayout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyboardLayout {
+    /// Polish programmers layout (QWERTY with diacritics on AltGr), the common one in Poland.
+    #[default]
+    PolishProgrammers,
+    /// Every substitution costs a full edit.
+    Uniform,
+}
+
+impl KeyboardLayout {
+    /// Substitution cost model of this layout.
+    pub fn costs(self) -> &'static dyn SubstitutionCost {
+        match self {
+            KeyboardLayout::PolishProgrammers => &PolishProgrammers,
+            KeyboardLayout::Uniform => &Uniform,
+        }
+    }
+}
+
+/// Substitutions cost a full edit whatever the keys.
+pub struct Uniform;
+
+impl SubstitutionCost for Uniform {
+    fn substitution_cost(&self, typed: char, intended: char) -> f32 {
+        if typed.to_lowercase().eq(intended.to_lowercase()) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Rows of the Polish programmers layout with their offset from the left edge in key widths.
+const ROWS: [(&str, f32); 4] = [
+    ("1234567890-=", 0.0),
+    ("qwertyuiop[]", 0.5),
+    ("asdfghjkl;'", 0.75),
+    ("zxcvbnm,./", 1.25),
+];
+
+/// Letters typed with AltGr and the key they share.
+const ALTGR: [(char, char); 9] = [
+    ('ą', 'a'),
+    ('ć', 'c'),
+    ('ę', 'e'),
+    ('ł', 'l'),
+    ('ń', 'n'),
+    ('ó', 'o'),
+    ('ś', 's'),
+    ('ż', 'z'),
+    ('ź', 'x'),
+];
+
+/// Missing or stray AltGr: the right key, only the diacritic is wrong ("zolw" for "żółw").
+const SAME_KEY_COST: f32 = 0.25;
+/// A finger landing on a neighbouring key, including diagonal ones.
+const NEIGHBOUR_COST: f32 = 0.5;
+/// Largest distance between key centres, in key widths, still counted as neighbours.
+const NEIGHBOUR_DISTANCE: f32 = 1.3;
+
+/// Substitution costs from key positions on the Polish programmers layout.
+pub struct PolishProgrammers;
+
+impl PolishProgrammers {
+    /// Base key of `ch` and its centre in key widths (column, row).
+    fn key(ch: char) -> Option<(char, f32, f32)> {
+        let ch = ch.to_lowercase().next()?;
+        let base = ALTGR
+            .iter()
+            .find(|(letter, _)| *letter == ch)
+            .map_or(ch, |(_, key)| *key);
+        ROWS.iter().enumerate().find_map(|(row, (keys, offset))| {
+            let column = keys.chars().position(|key| key == base)?;
+            Some((base, column as f32 + offset, row as f32))
+        })
+    }
+}
+
+impl SubstitutionCost for PolishProgrammers {
+    fn substitution_cost(&self, typed: char, intended: char) -> f32 {
+        if typed.to_lowercase().eq(intended.to_lowercase()) {
+            return 0.0;
+        }
+        let (Some(typed), Some(intended)) = (Self::key(typed), Self::key(intended)) else {
+            return 1.0;
+        };
+        if typed.0 == intended.0 {
+            SAME_KEY_COST
+        } else if (typed.1 - intended.1).hypot(typed.2 - intended.2) <= NEIGHBOUR_DISTANCE {
+            NEIGHBOUR_COST
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Optimal string alignment distance like `osa_distance`, with substitutions weighed by
+/// `costs` instead of all counting as 1.
+pub fn weighted_distance(typed: &[char], intended: &[char], costs: &dyn SubstitutionCost) -> f32 {
+    let n = intended.len();
+    let mut before_prev_row: Vec<f32> = vec![0.0; n + 1];
+    let mut prev_row: Vec<f32> = (0..=n).map(|j| j as f32).collect();
+    let mut curr_row: Vec<f32> = vec![0.0; n + 1];
+
+    for i in 1..=typed.len() {
+        curr_row[0] = i as f32;
+        for j in 1..=n {
+            let cost = costs.substitution_cost(typed[i - 1], intended[j - 1]);
+            curr_row[j] = (prev_row[j] + 1.0)
+                .min(curr_row[j - 1] + 1.0)
+                .min(prev_row[j - 1] + cost);
+
+            let swapped = i > 1
+                && j > 1
+                && costs.substitution_cost(typed[i - 1], intended[j - 2]) == 0.0
+                && costs.substitution_cost(typed[i - 2], intended[j - 1]) == 0.0;
+            if swapped {
+                curr_row[j] = curr_row[j].min(before_prev_row[j - 2] + 1.0);
+            }
+        }
+
+        std::mem::swap(&mut before_prev_row, &mut prev_row);
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    prev_row[n]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(typed: &str, intended: &str, layout: KeyboardLayout) -> f32 {
+        let typed: Vec<char> = typed.chars().collect();
+        let intended: Vec<char> = intended.chars().collect();
+        weighted_distance(&typed, &intended, layout.costs())
+    }
+
+    #[test]
+    fn test_substitution_costs() {
+        let costs = PolishProgrammers;
+        assert_eq!(costs.substitution_cost('a', 'A'), 0.0);
+        assert_eq!(costs.substitution_cost('z', 'ż'), SAME_KEY_COST);
+        assert_eq!(costs.substitution_cost('a', 's'), NEIGHBOUR_COST);
+        assert_eq!(costs.substitution_cost('w', 's'), NEIGHBOUR_COST);
+        assert_eq!(costs.substitution_cost('a', 'p'), 1.0);
+        assert_eq!(costs.substitution_cost('a', 'я'), 1.0);
+    }
+
+    #[test]
+    fn test_weighted_distance() {
+        let layout = KeyboardLayout::PolishProgrammers;
+        assert_eq!(distance("bardzp", "bardzo", layout), NEIGHBOUR_COST);
+        assert_eq!(distance("zolw", "żółw", layout), 3.0 * SAME_KEY_COST);
+        assert_eq!(distance("śiwat", "świat", layout), 1.0);
+        assert_eq!(distance("dom", "domek", layout), 2.0);
+        assert_eq!(distance("bardzp", "bardzo", KeyboardLayout::Uniform), 1.0);
+    }
+}
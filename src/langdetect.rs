@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+/// Frequent Polish function words that are not also English words.
+const POLISH_WORDS: &[&str] = &[
+    "w", "z", "na", "nie", "się", "jest", "że", "jak", "ale", "co", "od", "po", "za", "czy", "tak",
+    "już", "być", "który", "która", "które", "ten", "ta", "tego", "tylko", "może", "są", "jego",
+    "jej", "ich", "dla", "przez", "oraz", "lub", "albo", "gdy", "jeśli", "bardzo", "też", "także",
+    "więc", "ze", "mnie", "ja", "ty", "wy", "oni", "był", "była",
+];
+
+/// Frequent English function words.
+const ENGLISH_WORDS: &[&str] = &[
+    "the", "and", "of", "is", "in", "that", "it", "for", "with", "you", "this", "are", "be", "was",
+    "as", "have", "not", "but", "they", "at", "from", "by", "or", "we", "an", "will", "can", "if",
+    "would", "there", "what", "which", "their", "has", "been", "were", "should",
+];
+
+/// Letters that only occur in Polish text.
+const POLISH_LETTERS: &[char] = &['ą', 'ć', 'ę', 'ł', 'ń', 'ó', 'ś', 'ź', 'ż'];
+
+/// Guess whether a paragraph is written in Polish.
+///
+/// Counts Polish vs English function words, with words containing Polish-only
+/// letters counting towards Polish. Text without any signal is not Polish.
+pub fn is_polish(text: &[char]) -> bool {
+    let mut polish = 0;
+    let mut english = 0;
+
+    for word in text.split(|c| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+        let lower: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+        if POLISH_WORDS.contains(&lower.as_str()) || lower.contains(POLISH_LETTERS) {
+            polish += 1;
+        } else if ENGLISH_WORDS.contains(&lower.as_str()) {
+            english += 1;
+        }
+    }
+
+    polish > english
+}
+
+/// Split text into paragraphs separated by blank lines, as character ranges.
+pub fn paragraphs(source: &[char]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut line_start = 0;
+
+    for (idx, &ch) in source.iter().enumerate() {
+        if ch != '\n' {
+            continue;
+        }
+        if source[line_start..idx].iter().all(|c| c.is_whitespace()) {
+            if line_start > start {
+                result.push(start..line_start);
+            }
+            start = idx + 1;
+        }
+        line_start = idx + 1;
+    }
+
+    if source[start..].iter().any(|c| !c.is_whitespace()) {
+        result.push(start..source.len());
+    }
+
+    result
+}
+
+/// Ranges of the paragraphs detected as Polish.
+pub fn polish_paragraphs(source: &[char]) -> Vec<Range<usize>> {
+    paragraphs(source)
+        .into_iter()
+        .filter(|range| is_polish(&source[range.clone()]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn test_is_polish() {
+        assert!(is_polish(&chars("Dzień dobry, jak się masz?")));
+        assert!(is_polish(&chars("Mam kota, ale nie mam psa.")));
+        assert!(!is_polish(&chars(
+            "The quick brown fox jumps over the lazy dog."
+        )));
+        assert!(!is_polish(&chars("fn main() { println!(x); }")));
+        assert!(!is_polish(&chars("")));
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let source = chars("first\nstill first\n\n  \nsecond\n");
+        let ranges = paragraphs(&source);
+        assert_eq!(ranges.len(), 2);
+        let first: String = source[ranges[0].clone()].iter().collect();
+        assert_eq!(first, "first\nstill first\n");
+        let second: String = source[ranges[1].clone()].iter().collect();
+        assert_eq!(second, "second\n");
+    }
+
+    #[test]
+    fn test_polish_paragraphs_mixed() {
+        let source = chars("This is an English note.\n\nTo jest polska notatka, więc sprawdzamy.");
+        let ranges = polish_paragraphs(&source);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].start, 26);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default number of completion items returned to the client.
+pub const DEFAULT_MAX_RESULTS: usize = 50;
+/// Default number of fuzzy-match candidates scored per request.
+pub const DEFAULT_CANDIDATE_CAP: usize = 200;
+
+/// Fewest items returned while requests are slow, unless fewer are configured.
+const MIN_MAX_RESULTS: usize = 10;
+const MIN_CANDIDATE_CAP: usize = 40;
+
+/// Number of recent requests averaged when deciding whether to adapt.
+const WINDOW: usize = 8;
+/// Average latency above which limits shrink.
+const SLOW_THRESHOLD: Duration = Duration::from_millis(60);
+/// Average latency below which limits grow back towards the defaults.
+const FAST_THRESHOLD: Duration = Duration::from_millis(15);
+
+/// Result size limits for a single completion request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionLimits {
+    pub max_results: usize,
+    pub candidate_cap: usize,
+}
+
+impl Default for CompletionLimits {
+    fn default() -> Self {
+        Self {
+            max_results: DEFAULT_MAX_RESULTS,
+            candidate_cap: DEFAULT_CANDIDATE_CAP,
+        }
+    }
+}
+
+/// Tracks recent completion latencies and scales result limits to keep the editor responsive.
+/// Limits halve while requests are slow and recover gradually once they are fast again.
+#[derive(Debug, Default)]
+pub struct CompletionBudget {
+    state: Mutex<BudgetState>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    recent: VecDeque<Duration>,
+    limits: CompletionLimits,
+    /// Limits requests get while they are fast.
+    ceiling: CompletionLimits,
+}
+
+impl CompletionBudget {
+    /// Limits to use for the next completion request.
+    pub fn limits(&self) -> CompletionLimits {
+        self.state.lock().unwrap().limits
+    }
+
+    /// Use `max_results` as the number of items returned while requests are fast,
+    /// starting over from it.
+    pub fn set_max_results(&self, max_results: usize) {
+        let mut state = self.state.lock().unwrap();
+        if state.ceiling.max_results != max_results {
+            state.ceiling.max_results = max_results;
+            state.limits.max_results = max_results;
+            state.recent.clear();
+        }
+    }
+
+    /// Record how long a completion request took and adapt the limits if needed.
+    pub fn record(&self, elapsed: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.recent.push_back(elapsed);
+        if state.recent.len() < WINDOW {
+            return;
+        }
+        if state.recent.len() > WINDOW {
+            state.recent.pop_front();
+        }
+
+        let average = state.recent.iter().sum::<Duration>() / WINDOW as u32;
+        let (limits, ceiling) = (state.limits, state.ceiling);
+        let adapted = if average > SLOW_THRESHOLD {
+            CompletionLimits {
+                max_results: (limits.max_results / 2).max(MIN_MAX_RESULTS.min(ceiling.max_results)),
+                candidate_cap: (limits.candidate_cap / 2).max(MIN_CANDIDATE_CAP),
+            }
+        } else if average < FAST_THRESHOLD {
+            CompletionLimits {
+                max_results: (limits.max_results + limits.max_results / 4).min(ceiling.max_results),
+                candidate_cap: (limits.candidate_cap + limits.candidate_cap / 4)
+                    .min(ceiling.candidate_cap),
+            }
+        } else {
+            limits
+        };
+
+        if adapted != limits {
+            eprintln!(
+                "[POLSKI-LS] completion latency {:?}, limits now {} results / {} candidates",
+                average, adapted.max_results, adapted.candidate_cap
+            );
+            state.limits = adapted;
+            // Judge the new limits on fresh measurements only
+            state.recent.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_many(budget: &CompletionBudget, ms: u64) {
+        for _ in 0..WINDOW {
+            budget.record(Duration::from_millis(ms));
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let budget = CompletionBudget::default();
+        assert_eq!(budget.limits(), CompletionLimits::default());
+    }
+
+    #[test]
+    fn test_shrinks_when_slow() {
+        let budget = CompletionBudget::default();
+        record_many(&budget, 200);
+        let limits = budget.limits();
+        assert_eq!(limits.max_results, DEFAULT_MAX_RESULTS / 2);
+        assert_eq!(limits.candidate_cap, DEFAULT_CANDIDATE_CAP / 2);
+
+        for _ in 0..10 {
+            record_many(&budget, 200);
+        }
+        let limits = budget.limits();
+        assert_eq!(limits.max_results, MIN_MAX_RESULTS);
+        assert_eq!(limits.candidate_cap, MIN_CANDIDATE_CAP);
+    }
+
+    #[test]
+    fn test_recovers_when_fast() {
+        let budget = CompletionBudget::default();
+        record_many(&budget, 200);
+        for _ in 0..20 {
+            record_many(&budget, 1);
+        }
+        assert_eq!(budget.limits(), CompletionLimits::default());
+    }
+
+    #[test]
+    fn test_configured_max_results() {
+        let budget = CompletionBudget::default();
+        budget.set_max_results(200);
+        assert_eq!(budget.limits().max_results, 200);
+        record_many(&budget, 200);
+        assert_eq!(budget.limits().max_results, 100);
+        for _ in 0..20 {
+            record_many(&budget, 1);
+        }
+        assert_eq!(budget.limits().max_results, 200);
+
+        budget.set_max_results(5);
+        for _ in 0..10 {
+            record_many(&budget, 200);
+        }
+        assert_eq!(budget.limits().max_results, 5);
+    }
+
+    #[test]
+    fn test_moderate_latency_keeps_limits() {
+        let budget = CompletionBudget::default();
+        record_many(&budget, 200);
+        let shrunk = budget.limits();
+        record_many(&budget, 30);
+        assert_eq!(budget.limits(), shrunk);
+    }
+}
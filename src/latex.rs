@@ -0,0 +1,240 @@
+use std::ops::Range;
+
+/// Commands whose first arguments are names, keys or paths rather than prose, with how
+/// many of them. Their optional arguments are skipped as well.
+const KEY_ARGUMENTS: &[(&str, usize)] = &[
+    ("autoref", 1),
+    ("bibliography", 1),
+    ("bibliographystyle", 1),
+    ("cite", 1),
+    ("citeauthor", 1),
+    ("citep", 1),
+    ("citet", 1),
+    ("cref", 1),
+    ("documentclass", 1),
+    ("eqref", 1),
+    ("href", 1),
+    ("include", 1),
+    ("includegraphics", 1),
+    ("input", 1),
+    ("label", 1),
+    ("newcommand", 1),
+    ("newenvironment", 1),
+    ("pageref", 1),
+    ("ref", 1),
+    ("renewcommand", 1),
+    ("url", 1),
+    ("usepackage", 1),
+];
+
+/// Environments holding math or code, skipped up to their `\end`.
+const SKIPPED_ENVIRONMENTS: &[&str] = &[
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "comment",
+    "displaymath",
+    "eqnarray",
+    "eqnarray*",
+    "equation",
+    "equation*",
+    "flalign",
+    "flalign*",
+    "gather",
+    "gather*",
+    "lstlisting",
+    "math",
+    "minted",
+    "multline",
+    "multline*",
+    "tikzpicture",
+    "verbatim",
+    "Verbatim",
+];
+
+/// Whether documents in `language_id` are LaTeX sources.
+pub fn is_latex(language_id: &str) -> bool {
+    matches!(language_id, "latex" | "tex")
+}
+
+/// Character ranges of LaTeX source that aren't prose: command names, math, the
+/// environments in `SKIPPED_ENVIRONMENTS` and arguments of the commands in
+/// `KEY_ARGUMENTS` (labels, citation keys, packages...). Everything else, including
+/// arguments like the title in `\section{...}`, is checked as text.
+pub fn non_prose(source: &[char]) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let start = i;
+        match source[i] {
+            '\\' => i = command_end(source, i),
+            '$' => {
+                let delimiter: &[char] = if source.get(i + 1) == Some(&'$') {
+                    &['$', '$']
+                } else {
+                    &['$']
+                };
+                i = math_end(source, i + delimiter.len(), delimiter);
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        spans.push(start..i);
+    }
+    spans
+}
+
+/// End of the command starting with the backslash at `start`, including the math,
+/// environment or key arguments it opens.
+fn command_end(source: &[char], start: usize) -> usize {
+    let name_start = start + 1;
+    let name_len = source[name_start..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    if name_len == 0 {
+        // A control symbol: "\\", "\%", or "\[" and "\(" opening math
+        return match source.get(name_start) {
+            Some('[') => math_end(source, name_start + 1, &['\\', ']']),
+            Some('(') => math_end(source, name_start + 1, &['\\', ')']),
+            Some(_) => name_start + 1,
+            None => name_start,
+        };
+    }
+    let name: String = source[name_start..name_start + name_len].iter().collect();
+    let mut end = name_start + name_len;
+    if source.get(end) == Some(&'*') {
+        end += 1;
+    }
+
+    match name.as_str() {
+        "begin" | "end" => {
+            let Some(argument_end) = group_end(source, end, '{', '}') else {
+                return end;
+            };
+            let environment: String = source[end + 1..argument_end - 1].iter().collect();
+            if name == "begin" && SKIPPED_ENVIRONMENTS.contains(&environment.as_str()) {
+                let closing: Vec<char> = format!("\\end{{{}}}", environment).chars().collect();
+                return math_end(source, argument_end, &closing);
+            }
+            argument_end
+        }
+        _ => {
+            let Some(&(_, keys)) = KEY_ARGUMENTS.iter().find(|(command, _)| *command == name)
+            else {
+                return end;
+            };
+            for _ in 0..keys {
+                end = optional_arguments_end(source, end);
+                let argument = end + whitespace(&source[end..]);
+                match group_end(source, argument, '{', '}') {
+                    Some(argument_end) => end = argument_end,
+                    None => break,
+                }
+            }
+            end
+        }
+    }
+}
+
+/// End of math or a skipped environment whose content starts at `start`: just after
+/// the `closing` delimiter. Without one, only the opening delimiter is skipped, so a
+/// `$` being typed doesn't hide the rest of the document.
+fn math_end(source: &[char], start: usize, closing: &[char]) -> usize {
+    let mut i = start;
+    while i + closing.len() <= source.len() {
+        if source[i..].starts_with(closing) {
+            return i + closing.len();
+        }
+        // Escaped characters, like "\$" inside math, don't close it
+        i += if source[i] == '\\' { 2 } else { 1 };
+    }
+    start
+}
+
+/// End of the optional arguments ("[s.~5]") following `start`, or `start` if none do.
+fn optional_arguments_end(source: &[char], start: usize) -> usize {
+    let mut end = start;
+    while let Some(argument_end) = group_end(source, end + whitespace(&source[end..]), '[', ']') {
+        end = argument_end;
+    }
+    end
+}
+
+/// End of the group opened by `open` at `start`, after its matching `close`.
+fn group_end(source: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    if source.get(start) != Some(&open) {
+        return None;
+    }
+    let mut depth = 0;
+    let mut i = start;
+    while i < source.len() {
+        match source[i] {
+            '\\' => i += 1,
+            ch if ch == open => depth += 1,
+            ch if ch == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Length of the whitespace at the start of `source`.
+fn whitespace(source: &[char]) -> usize {
+    source.iter().take_while(|c| c.is_whitespace()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skipped(text: &str) -> Vec<String> {
+        let source: Vec<char> = text.chars().collect();
+        non_prose(&source)
+            .into_iter()
+            .map(|range| source[range].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_and_keys() {
+        assert_eq!(
+            skipped("\\section*{Wstęp} Jak w~\\cite[s.~5]{kowalski2020}, zob. \\ref{rys:kot}."),
+            vec!["\\section*", "\\cite[s.~5]{kowalski2020}", "\\ref{rys:kot}"]
+        );
+        assert_eq!(
+            skipped("\\href{https://sjp.pl}{słownik} i \\textbf{tekst} 50\\% \\\\"),
+            vec!["\\href{https://sjp.pl}", "\\textbf", "\\%", "\\\\"]
+        );
+    }
+
+    #[test]
+    fn test_math_and_environments() {
+        assert_eq!(
+            skipped("Wzór $a \\$ b$, $$x^2$$ i \\(y\\) oraz \\[z\\]."),
+            vec!["$a \\$ b$", "$$x^2$$", "\\(y\\)", "\\[z\\]"]
+        );
+        assert_eq!(
+            skipped(
+                "\\begin{itemize}\\item tak\\end{itemize}\\begin{equation*}e=mc^2\\end{equation*}"
+            ),
+            vec![
+                "\\begin{itemize}",
+                "\\item",
+                "\\end{itemize}",
+                "\\begin{equation*}e=mc^2\\end{equation*}"
+            ]
+        );
+        // Math still being typed only hides its delimiter
+        assert_eq!(skipped("Niech $x oznacza"), vec!["$"]);
+    }
+}
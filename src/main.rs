@@ -1,30 +1,163 @@
+// lsp_types::Uri caches its parsed form internally, which trips this lint when used as a
+// HashMap key (e.g. WorkspaceEdit::changes). Hashing only looks at the string, so it is safe.
+#![allow(clippy::mutable_key_type)]
+
+mod abbreviations;
+mod addresses;
+mod apostrophe;
 mod backend;
+mod bigrams;
+mod casing;
+mod check_config;
+mod commas;
+mod compiled_dict;
+mod config;
+mod diagnostics;
+mod dict_cache;
 mod dictionary;
+mod expansions;
+mod front_matter;
+mod fst_dictionary;
+mod gettext;
+mod glossary;
+mod ignore;
+mod item_defaults;
+mod keyboard;
+mod langdetect;
+mod latency;
+mod latex;
+mod morphology;
+mod numerals;
+mod org;
 mod pos_conv;
+mod progress;
+mod reflow;
+mod repeated_words;
+mod replacements;
+mod rules;
+mod scheduler;
+#[cfg(feature = "tree-sitter")]
+mod source_code;
+mod status;
+mod symbols;
+mod symspell;
+mod transport;
+mod typography;
+mod usage_stats;
+mod word_list;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
 use backend::Backend;
-use clap::Parser;
-use tower_lsp_server::{LspService, Server};
+use clap::{Parser, Subcommand};
+use dictionary::{DictionaryBackend, DictionaryOptions, SYSTEM_DICT_PATH};
+use item_defaults::ItemDefaults;
+use tower_lsp_server::LspService;
+use transport::{Stopped, Transport, DEFAULT_TCP_ADDR};
 
 #[derive(Debug, Parser)]
 #[command(version, about = "Polish language LSP server with completion support")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Listen on standard input/output rather than TCP.
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, group = "transport")]
     stdio: bool,
+
+    /// Listen for a single client on this TCP address (the default transport).
+    #[arg(long, value_name = "ADDR", group = "transport")]
+    tcp: Option<SocketAddr>,
+
+    /// Connect to a TCP port on localhost opened by the client.
+    #[arg(long, value_name = "PORT", group = "transport")]
+    socket: Option<u16>,
+
+    /// Connect to the named pipe (Windows) or Unix domain socket created by the client.
+    #[arg(long, value_name = "NAME", group = "transport")]
+    pipe: Option<String>,
+
+    /// Listen for a single WebSocket client on this address.
+    #[arg(long, value_name = "ADDR", group = "transport")]
+    websocket: Option<SocketAddr>,
+
+    /// Do not use the word list compiled into the binary; load --system-dict instead.
+    #[arg(long, global = true, default_value_t = false)]
+    no_embedded_dict: bool,
+
+    /// Word list used when the embedded dictionary is disabled or not compiled in.
+    #[arg(long, global = true, value_name = "PATH", default_value = SYSTEM_DICT_PATH)]
+    system_dict: PathBuf,
+
+    /// Index used for suggestions and typo-tolerant completion; symspell and fst answer
+    /// them faster on large dictionaries, symspell using more memory and fst less.
+    #[arg(long, global = true, value_enum, default_value_t = DictionaryBackend::Simple)]
+    dictionary_backend: DictionaryBackend,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Validate dictionaries and configuration, then exit.
+    CheckConfig,
+    /// Compile a word list for fast loading; the server picks up `slowa.bin` next to
+    /// the `slowa.txt` given with --system-dict.
+    CompileDictionary {
+        /// Word list to compile [default: the --system-dict path]
+        input: Option<PathBuf>,
+        /// Where to write it [default: the input with a .bin extension]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Args::parse();
+    let transport = args.transport();
+
+    let dictionary_options = DictionaryOptions {
+        use_embedded: !args.no_embedded_dict,
+        system_dict_path: args.system_dict,
+        backend: args.dictionary_backend,
+    };
 
-    let (service, socket) = LspService::new(Backend::new);
+    match args.command {
+        Some(Command::CheckConfig) => return check_config::run(&dictionary_options),
+        Some(Command::CompileDictionary { input, output }) => {
+            let input = input.unwrap_or(dictionary_options.system_dict_path);
+            let output = output.unwrap_or_else(|| compiled_dict::compiled_path(&input));
+            return compiled_dict::run(&input, &output);
+        }
+        None => {}
+    }
+
+    let (service, socket) = LspService::new(|client| Backend::new(client, &dictionary_options));
+    let session = service.inner().session();
+    match transport::serve(&transport, ItemDefaults::new(service), socket).await {
+        Ok(Stopped::Client) => session.exit_code(),
+        Ok(Stopped::Signal) => {
+            // The client never got to send `shutdown`, so save its state here
+            session.flush().await;
+            ExitCode::SUCCESS
+        }
+        Err(code) => code,
+    }
+}
 
-    if args.stdio {
-        let stdin = tokio::io::stdin();
-        let stdout = tokio::io::stdout();
-        Server::new(stdin, stdout, socket).serve(service).await;
-    } else {
-        eprintln!("TCP mode not implemented. Use --stdio");
+impl Args {
+    fn transport(&self) -> Transport {
+        if self.stdio {
+            Transport::Stdio
+        } else if let Some(port) = self.socket {
+            Transport::Socket(port)
+        } else if let Some(name) = &self.pipe {
+            Transport::Pipe(name.clone())
+        } else if let Some(addr) = self.websocket {
+            Transport::WebSocket(addr)
+        } else {
+            Transport::Tcp(self.tcp.unwrap_or_else(|| DEFAULT_TCP_ADDR.parse().unwrap()))
+        }
     }
 }
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use crate::dictionary::{user_config_dir, DictionaryOptions, WordListError};
+
+/// File name of the user morphology extension in ~/.config/polski-ls/.
+pub const USER_MORPHOLOGY_FILE: &str = "morfologia.tsv";
+
+/// One possible reading of an inflected form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// Base form (lemma), e.g. "dzień" for "dnia".
+    pub lemma: String,
+    /// Part of speech in Polish, e.g. "rzeczownik".
+    pub part_of_speech: String,
+}
+
+/// Maps inflected forms to their lemmas and parts of speech.
+///
+/// Source format is one lemma per line: `lemma<TAB>part of speech<TAB>forms...`,
+/// with space-separated forms and `#` comments.
+#[derive(Debug, Default)]
+pub struct Morphology {
+    forms: HashMap<String, Vec<Analysis>>,
+}
+
+impl Morphology {
+    /// Create empty morphology data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the embedded data (if enabled) plus ~/.config/polski-ls/morfologia.tsv.
+    pub fn load(options: &DictionaryOptions) -> Self {
+        let mut morphology = Self::new();
+
+        #[cfg(feature = "embedded-dict")]
+        if options.embedded_enabled() {
+            morphology.parse(include_str!("../morfologia.tsv"));
+        }
+        #[cfg(not(feature = "embedded-dict"))]
+        let _ = options;
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(USER_MORPHOLOGY_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading user morphology: {:?}", path);
+                morphology.parse(&content);
+            }
+        }
+
+        morphology
+    }
+
+    /// Add entries from morphology source text.
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = trimmed.split('\t');
+            let (Some(lemma), Some(part_of_speech)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let analysis = Analysis {
+                lemma: lemma.trim().to_string(),
+                part_of_speech: part_of_speech.trim().to_string(),
+            };
+
+            let forms = fields.next().unwrap_or_default().split_whitespace();
+            for form in std::iter::once(lemma.trim()).chain(forms) {
+                let entry = self.forms.entry(form.to_lowercase()).or_default();
+                if !entry.contains(&analysis) {
+                    entry.push(analysis.clone());
+                }
+            }
+        }
+    }
+
+    /// All known readings of a word (case-insensitive).
+    pub fn analyze(&self, word: &[char]) -> &[Analysis] {
+        let key: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+        self.forms.get(&key).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Validate morphology source text, reporting lines without a part of speech.
+pub fn validate_morphology(content: &str) -> Vec<WordListError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .filter_map(|(idx, line)| {
+            let mut fields = line.trim().split('\t');
+            let lemma = fields.next().unwrap_or_default().trim();
+            let part_of_speech = fields.next().unwrap_or_default().trim();
+            let message = if lemma.is_empty() || lemma.contains(char::is_whitespace) {
+                format!("expected a single-word lemma, found '{}'", lemma)
+            } else if part_of_speech.is_empty() {
+                format!("missing tab-separated part of speech for '{}'", lemma)
+            } else {
+                return None;
+            };
+            Some(WordListError {
+                line: idx + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_parse_and_analyze() {
+        let mut morphology = Morphology::new();
+        morphology.parse("# comment\ndzień\trzeczownik\tdnia dniu\n");
+
+        let analyses = morphology.analyze(&chars("Dnia"));
+        assert_eq!(analyses.len(), 1);
+        assert_eq!(analyses[0].lemma, "dzień");
+        assert_eq!(analyses[0].part_of_speech, "rzeczownik");
+
+        assert_eq!(morphology.analyze(&chars("dzień"))[0].lemma, "dzień");
+        assert!(morphology.analyze(&chars("kot")).is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_form() {
+        let mut morphology = Morphology::new();
+        morphology.parse("ciepło\trzeczownik\tciepła\nciepły\tprzymiotnik\tciepła\n");
+
+        let lemmas: Vec<&str> = morphology
+            .analyze(&chars("ciepła"))
+            .iter()
+            .map(|a| a.lemma.as_str())
+            .collect();
+        assert_eq!(lemmas, vec!["ciepło", "ciepły"]);
+    }
+
+    #[test]
+    fn test_malformed_lines_are_skipped() {
+        let mut morphology = Morphology::new();
+        morphology.parse("tylko-lemat\n");
+        assert!(morphology.analyze(&chars("tylko-lemat")).is_empty());
+    }
+
+    #[test]
+    fn test_validate_morphology() {
+        let content = "# c\ndom\trzeczownik\tdomu\nkot\nzły lemat\tprzymiotnik\n";
+        let lines: Vec<usize> = validate_morphology(content)
+            .iter()
+            .map(|e| e.line)
+            .collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-dict")]
+    fn test_embedded_morphology() {
+        assert!(validate_morphology(include_str!("../morfologia.tsv")).is_empty());
+
+        let mut morphology = Morphology::new();
+        morphology.parse(include_str!("../morfologia.tsv"));
+        let analyses = morphology.analyze(&chars("będą"));
+        assert_eq!(analyses[0].lemma, "być");
+        assert_eq!(analyses[0].part_of_speech, "czasownik");
+    }
+}
@@ -0,0 +1,356 @@
+/// Grammatical case a numeral is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    /// "dwadzieścia pięć"
+    Nominative,
+    /// "dwudziestu pięciu", e.g. after "do", "od" or "około".
+    Genitive,
+}
+
+const UNITS: [[&str; 10]; 2] = [
+    [
+        "",
+        "jeden",
+        "dwa",
+        "trzy",
+        "cztery",
+        "pięć",
+        "sześć",
+        "siedem",
+        "osiem",
+        "dziewięć",
+    ],
+    [
+        "",
+        "jednego",
+        "dwóch",
+        "trzech",
+        "czterech",
+        "pięciu",
+        "sześciu",
+        "siedmiu",
+        "ośmiu",
+        "dziewięciu",
+    ],
+];
+
+const TEENS: [[&str; 10]; 2] = [
+    [
+        "dziesięć",
+        "jedenaście",
+        "dwanaście",
+        "trzynaście",
+        "czternaście",
+        "piętnaście",
+        "szesnaście",
+        "siedemnaście",
+        "osiemnaście",
+        "dziewiętnaście",
+    ],
+    [
+        "dziesięciu",
+        "jedenastu",
+        "dwunastu",
+        "trzynastu",
+        "czternastu",
+        "piętnastu",
+        "szesnastu",
+        "siedemnastu",
+        "osiemnastu",
+        "dziewiętnastu",
+    ],
+];
+
+const TENS: [[&str; 10]; 2] = [
+    [
+        "",
+        "",
+        "dwadzieścia",
+        "trzydzieści",
+        "czterdzieści",
+        "pięćdziesiąt",
+        "sześćdziesiąt",
+        "siedemdziesiąt",
+        "osiemdziesiąt",
+        "dziewięćdziesiąt",
+    ],
+    [
+        "",
+        "",
+        "dwudziestu",
+        "trzydziestu",
+        "czterdziestu",
+        "pięćdziesięciu",
+        "sześćdziesięciu",
+        "siedemdziesięciu",
+        "osiemdziesięciu",
+        "dziewięćdziesięciu",
+    ],
+];
+
+const HUNDREDS: [[&str; 10]; 2] = [
+    [
+        "",
+        "sto",
+        "dwieście",
+        "trzysta",
+        "czterysta",
+        "pięćset",
+        "sześćset",
+        "siedemset",
+        "osiemset",
+        "dziewięćset",
+    ],
+    [
+        "",
+        "stu",
+        "dwustu",
+        "trzystu",
+        "czterystu",
+        "pięciuset",
+        "sześciuset",
+        "siedmiuset",
+        "ośmiuset",
+        "dziewięciuset",
+    ],
+];
+
+/// Powers of 1000 with their forms: nominative singular, nominative plural (2-4),
+/// genitive plural (5+), genitive singular.
+const SCALES: [[&str; 4]; 6] = [
+    ["tysiąc", "tysiące", "tysięcy", "tysiąca"],
+    ["milion", "miliony", "milionów", "miliona"],
+    ["miliard", "miliardy", "miliardów", "miliarda"],
+    ["bilion", "biliony", "bilionów", "biliona"],
+    ["biliard", "biliardy", "biliardów", "biliarda"],
+    ["trylion", "tryliony", "trylionów", "tryliona"],
+];
+
+/// Prepositions governing the genitive, so the numeral after them is declined.
+const GENITIVE_PREPOSITIONS: &[&str] = &[
+    "bez", "dla", "do", "koło", "ok", "około", "od", "spod", "sprzed", "u", "według", "wg",
+    "zamiast",
+];
+
+/// Write a number out in Polish words.
+pub fn to_words(number: u64, case: Case) -> String {
+    let c = case as usize;
+    if number == 0 {
+        return ["zero", "zera"][c].to_string();
+    }
+
+    let mut groups = Vec::new();
+    let mut rest = number;
+    while rest > 0 {
+        groups.push((rest % 1000) as usize);
+        rest /= 1000;
+    }
+
+    let mut words: Vec<&str> = Vec::new();
+    for (scale, &group) in groups.iter().enumerate().rev() {
+        if group == 0 {
+            continue;
+        }
+        // "tysiąc", not "jeden tysiąc"
+        if !(scale > 0 && group == 1) {
+            words.extend(group_words(group, case));
+        }
+        if scale > 0 {
+            words.push(SCALES[scale - 1][scale_form(group, case)]);
+        }
+    }
+
+    words.join(" ")
+}
+
+/// Words for a number below 1000.
+fn group_words(group: usize, case: Case) -> Vec<&'static str> {
+    let c = case as usize;
+    let (hundreds, tens, units) = (group / 100, group / 10 % 10, group % 10);
+
+    let mut words = vec![HUNDREDS[c][hundreds]];
+    if tens == 1 {
+        words.push(TEENS[c][units]);
+    } else {
+        words.push(TENS[c][tens]);
+        words.push(UNITS[c][units]);
+    }
+    words.retain(|w| !w.is_empty());
+    words
+}
+
+/// Index into `SCALES` for the noun following `group`.
+fn scale_form(group: usize, case: Case) -> usize {
+    let (tens, units) = (group / 10 % 10, group % 10);
+    match case {
+        Case::Nominative if group == 1 => 0,
+        Case::Nominative if (2..=4).contains(&units) && tens != 1 => 1,
+        Case::Nominative => 2,
+        Case::Genitive if group == 1 => 3,
+        Case::Genitive => 2,
+    }
+}
+
+/// Find a number touching `idx`, written with digits and optional space-separated
+/// groups of three ("1 234"). Returns its character range and value. Decimals and
+/// tokens mixing letters and digits ("A4") are not numbers.
+pub fn number_at(source: &[char], idx: usize) -> Option<(usize, usize, u64)> {
+    let is_group_separator = |c: char| matches!(c, ' ' | '\u{a0}' | '\u{202f}');
+    let idx = idx.min(source.len());
+
+    // Expand over the digit run under the cursor
+    let mut start = idx;
+    while start > 0 && source[start - 1].is_ascii_digit() {
+        start -= 1;
+    }
+    let mut end = idx;
+    while end < source.len() && source[end].is_ascii_digit() {
+        end += 1;
+    }
+    if start == end {
+        return None;
+    }
+
+    // Join neighbouring groups of three digits
+    loop {
+        let group = end + 1..end + 4;
+        let joins = source.get(end).is_some_and(|c| is_group_separator(*c))
+            && source
+                .get(group.clone())
+                .is_some_and(|g| g.iter().all(|c| c.is_ascii_digit()))
+            && !source.get(group.end).is_some_and(|c| c.is_ascii_digit());
+        if !joins {
+            break;
+        }
+        end = group.end;
+    }
+    loop {
+        // Only the leftmost group may be shorter than three digits
+        let leftmost = source[start..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let joins = leftmost == 3
+            && start >= 2
+            && is_group_separator(source[start - 1])
+            && source[start - 2].is_ascii_digit();
+        if !joins {
+            break;
+        }
+        let mut group_start = start - 2;
+        while group_start > 0 && source[group_start - 1].is_ascii_digit() {
+            group_start -= 1;
+        }
+        if start - 1 - group_start > 3 {
+            break;
+        }
+        start = group_start;
+    }
+
+    let attached = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+    let decimal = |c: Option<&char>, d: Option<&char>| {
+        matches!(c, Some(',' | '.')) && d.is_some_and(|d| d.is_ascii_digit())
+    };
+    let before = start.checked_sub(1).and_then(|i| source.get(i));
+    let before2 = start.checked_sub(2).and_then(|i| source.get(i));
+    if attached(before)
+        || attached(source.get(end))
+        || decimal(before, before2)
+        || decimal(source.get(end), source.get(end + 1))
+    {
+        return None;
+    }
+
+    let digits: String = source[start..end]
+        .iter()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    let value = digits.parse().ok()?;
+    Some((start, end, value))
+}
+
+/// Case for a number at `start`, judged from the preceding word.
+pub fn case_at(source: &[char], start: usize) -> Case {
+    let mut end = start;
+    while end > 0 && (source[end - 1].is_whitespace() || source[end - 1] == '.') {
+        end -= 1;
+    }
+    let mut word_start = end;
+    while word_start > 0 && source[word_start - 1].is_alphabetic() {
+        word_start -= 1;
+    }
+
+    let previous: String = source[word_start..end]
+        .iter()
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    if GENITIVE_PREPOSITIONS.contains(&previous.as_str()) {
+        Case::Genitive
+    } else {
+        Case::Nominative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn test_to_words_nominative() {
+        let words = |n| to_words(n, Case::Nominative);
+        assert_eq!(words(0), "zero");
+        assert_eq!(words(7), "siedem");
+        assert_eq!(words(13), "trzynaście");
+        assert_eq!(words(45), "czterdzieści pięć");
+        assert_eq!(words(200), "dwieście");
+        assert_eq!(words(1234), "tysiąc dwieście trzydzieści cztery");
+        assert_eq!(words(2000), "dwa tysiące");
+        assert_eq!(words(5000), "pięć tysięcy");
+        assert_eq!(words(12_000), "dwanaście tysięcy");
+        assert_eq!(words(22_000), "dwadzieścia dwa tysiące");
+        assert_eq!(words(21_000), "dwadzieścia jeden tysięcy");
+        assert_eq!(words(1_000_001), "milion jeden");
+        assert_eq!(words(3_500_000), "trzy miliony pięćset tysięcy");
+    }
+
+    #[test]
+    fn test_to_words_genitive() {
+        let words = |n| to_words(n, Case::Genitive);
+        assert_eq!(words(5), "pięciu");
+        assert_eq!(words(1000), "tysiąca");
+        assert_eq!(words(2500), "dwóch tysięcy pięciuset");
+        assert_eq!(words(1_000_000), "miliona");
+    }
+
+    #[test]
+    fn test_number_at() {
+        let source = chars("Kosztuje 1 234 zł, czyli 12 razy 3.");
+        assert_eq!(number_at(&source, 9), Some((9, 14, 1234)));
+        assert_eq!(number_at(&source, 13), Some((9, 14, 1234)));
+        assert_eq!(number_at(&source, 25), Some((25, 27, 12)));
+        assert_eq!(number_at(&source, 33), Some((33, 34, 3)));
+        assert_eq!(number_at(&source, 3), None);
+    }
+
+    #[test]
+    fn test_number_at_rejects_non_numbers() {
+        assert_eq!(number_at(&chars("format A4"), 8), None);
+        assert_eq!(number_at(&chars("ok. 3,5 kg"), 4), None);
+        assert_eq!(number_at(&chars("ok. 3,5 kg"), 6), None);
+        // "12 3456" is two numbers, not grouped digits
+        assert_eq!(number_at(&chars("12 3456"), 0), Some((0, 2, 12)));
+    }
+
+    #[test]
+    fn test_case_at() {
+        let source = chars("Od 5 do 10, ok. 20 i 30");
+        assert_eq!(case_at(&source, 3), Case::Genitive);
+        assert_eq!(case_at(&source, 8), Case::Genitive);
+        assert_eq!(case_at(&source, 16), Case::Genitive);
+        assert_eq!(case_at(&source, 21), Case::Nominative);
+    }
+}
@@ -0,0 +1,178 @@
+use std::ops::Range;
+
+use crate::pos_conv::line_ranges;
+
+/// Blocks holding code or verbatim text, skipped up to their `#+END_` line.
+const SKIPPED_BLOCKS: &[&str] = &["src", "example", "export"];
+
+/// Headline keywords marking tasks.
+const TODO_KEYWORDS: &[&str] = &["TODO", "DONE"];
+
+/// Whether documents in `language_id` are Org-mode notes.
+pub fn is_org(language_id: &str) -> bool {
+    language_id == "org"
+}
+
+/// Character ranges of an Org document that aren't prose: source and example blocks,
+/// drawers (`:PROPERTIES:` ... `:END:`), `#+KEYWORD:` names, the TODO keyword and tags
+/// of headlines, and link targets. Headlines, link descriptions and body text are
+/// checked.
+pub fn non_prose(source: &[char]) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    // Where the block or drawer being skipped started, with the line ending it
+    let mut skipping: Option<(usize, String)> = None;
+    for line in line_ranges(source) {
+        let text: String = source[line.clone()].iter().collect();
+        let trimmed = text.trim();
+        let lower = trimmed.to_lowercase();
+
+        if let Some((start, end_line)) = &skipping {
+            if lower == *end_line || lower.starts_with(&format!("{} ", end_line)) {
+                spans.push(*start..line.end);
+                skipping = None;
+            }
+            continue;
+        }
+
+        if let Some(block) = lower.strip_prefix("#+begin_") {
+            let name = block.split_whitespace().next().unwrap_or_default();
+            if SKIPPED_BLOCKS.contains(&name) {
+                skipping = Some((line.start, format!("#+end_{}", name)));
+                continue;
+            }
+        }
+        if is_drawer(trimmed) {
+            skipping = Some((line.start, ":end:".to_string()));
+            continue;
+        }
+        if trimmed.starts_with("#+") {
+            // "#+TITLE:" or "#+BEGIN_QUOTE"; what follows the colon is text
+            let indent = text.len() - text.trim_start().len();
+            let keyword_len = trimmed.find(':').map_or(trimmed.len(), |colon| colon + 1);
+            let start = line.start + text[..indent].chars().count();
+            spans.push(start..start + trimmed[..keyword_len].chars().count());
+            continue;
+        }
+        spans.extend(headline_markup(&source[line.clone()], line.start));
+        spans.extend(link_targets(&source[line.clone()], line.start));
+    }
+    // A block still being typed hides only its first line
+    if let Some((start, _)) = skipping {
+        let first_line = line_ranges(&source[start..]).next().unwrap_or(0..0);
+        spans.push(start..start + first_line.end);
+    }
+    spans
+}
+
+/// Whether `line` (trimmed) opens a drawer, like `:PROPERTIES:` or `:LOGBOOK:`.
+fn is_drawer(line: &str) -> bool {
+    line.len() > 2
+        && line.starts_with(':')
+        && line.ends_with(':')
+        && !line.eq_ignore_ascii_case(":end:")
+        && line[1..line.len() - 1]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The TODO keyword and trailing tags (`:praca:pilne:`) of a headline starting at
+/// `offset`; nothing if the line isn't a headline.
+fn headline_markup(line: &[char], offset: usize) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let stars = line.iter().take_while(|&&c| c == '*').count();
+    if stars == 0 || line.get(stars) != Some(&' ') {
+        return spans;
+    }
+    let title_start = offset + stars + 1;
+    let keyword: String = line[stars + 1..]
+        .iter()
+        .take_while(|c| !c.is_whitespace())
+        .collect();
+    if TODO_KEYWORDS.contains(&keyword.as_str()) {
+        spans.push(title_start..title_start + keyword.len());
+    }
+
+    let content_end = line.len() - line.iter().rev().take_while(|c| c.is_whitespace()).count();
+    let tags_start = line[..content_end]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |space| space + 1);
+    let tags = &line[tags_start..content_end];
+    if tags.len() > 2 && tags[0] == ':' && tags[tags.len() - 1] == ':' {
+        spans.push(offset + tags_start..offset + content_end);
+    }
+    spans
+}
+
+/// Targets of the links (`[[target][description]]`, `[[target]]`) on a line starting
+/// at `offset`.
+fn link_targets(line: &[char], offset: usize) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i + 1 < line.len() {
+        if line[i] != '[' || line[i + 1] != '[' {
+            i += 1;
+            continue;
+        }
+        let target_start = i + 2;
+        let Some(length) = line[target_start..].iter().position(|&c| c == ']') else {
+            break;
+        };
+        let target_end = target_start + length;
+        spans.push(offset + target_start..offset + target_end);
+        i = target_end;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skipped(text: &str) -> Vec<String> {
+        let source: Vec<char> = text.chars().collect();
+        non_prose(&source)
+            .into_iter()
+            .map(|range| source[range].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_blocks_and_drawers() {
+        let text = "#+TITLE: Notatki\n\
+            Tekst\n\
+            #+begin_src rust\n\
+            fn main() {}\n\
+            #+end_src\n\
+            :PROPERTIES:\n\
+            :ID: abc\n\
+            :END:\n\
+            #+BEGIN_QUOTE\n\
+            Cytat\n\
+            #+END_QUOTE";
+        assert_eq!(
+            skipped(text),
+            vec![
+                "#+TITLE:",
+                "#+begin_src rust\nfn main() {}\n#+end_src",
+                ":PROPERTIES:\n:ID: abc\n:END:",
+                "#+BEGIN_QUOTE",
+                "#+END_QUOTE"
+            ]
+        );
+        // A block being typed hides only its first line
+        assert_eq!(skipped("#+BEGIN_SRC\nkod"), vec!["#+BEGIN_SRC"]);
+    }
+
+    #[test]
+    fn test_headlines_and_links() {
+        assert_eq!(
+            skipped("** TODO Napisać [[https://sjp.pl][wstęp]] :praca:pilne:"),
+            vec!["TODO", ":praca:pilne:", "https://sjp.pl"]
+        );
+        assert_eq!(
+            skipped("*Zobacz* [[file:notatki.org]] :tu:"),
+            vec!["file:notatki.org"]
+        );
+    }
+}
@@ -1,4 +1,51 @@
-use tower_lsp_server::lsp_types::Position;
+use tower_lsp_server::lsp_types::{Position, PositionEncodingKind, Range};
+
+/// Unit in which `Position::character` counts columns, negotiated with the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// Bytes of the UTF-8 encoding.
+    Utf8,
+    /// UTF-16 code units (the LSP default).
+    #[default]
+    Utf16,
+    /// Unicode scalar values, i.e. `char`s.
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Pick the first encoding from the client's preference list that we support,
+    /// falling back to UTF-16 which every client must accept.
+    pub fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        offered
+            .unwrap_or_default()
+            .iter()
+            .find_map(|kind| match kind.as_str() {
+                "utf-8" => Some(Self::Utf8),
+                "utf-16" => Some(Self::Utf16),
+                "utf-32" => Some(Self::Utf32),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The LSP name of this encoding, for the server capabilities.
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    /// Number of column units a character occupies.
+    fn len(self, ch: char) -> usize {
+        match self {
+            Self::Utf8 => ch.len_utf8(),
+            Self::Utf16 => ch.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+}
 
 /// Pre-built index of line start positions for O(1) position conversion.
 /// Avoids O(N) scanning of the entire document on every position lookup.
@@ -6,24 +53,29 @@ use tower_lsp_server::lsp_types::Position;
 pub struct LineIndex {
     /// Indices of the first character of each line.
     /// line_starts[0] is always 0 (first line starts at index 0).
-    /// line_starts[1] is the index after the first '\n', etc.
+    /// line_starts[1] is the index after the first line break (`\n`, `\r\n` or `\r`), etc.
     line_starts: Vec<usize>,
+    /// Unit of `Position::character` columns.
+    encoding: PositionEncoding,
 }
 
 impl LineIndex {
     /// Build a new LineIndex from source characters.
     /// Scans the document once in O(N) to find all line boundaries.
-    pub fn new(source: &[char]) -> Self {
+    pub fn new(source: &[char], encoding: PositionEncoding) -> Self {
         let mut line_starts = vec![0]; // First line always starts at 0
 
-        for (idx, &ch) in source.iter().enumerate() {
-            if ch == '\n' {
+        for idx in 0..source.len() {
+            if ends_line(source, idx) {
                 // Next line starts after this newline
                 line_starts.push(idx + 1);
             }
         }
 
-        Self { line_starts }
+        Self {
+            line_starts,
+            encoding,
+        }
     }
 
     /// Convert a character index to an LSP Position.
@@ -37,10 +89,10 @@ impl LineIndex {
 
         let line_start = self.line_starts.get(line).copied().unwrap_or(0);
 
-        // Calculate column as UTF-16 code units from line start to index
+        // Calculate column in encoding units from line start to index
         let cols: usize = source[line_start..index.min(source.len())]
             .iter()
-            .map(|c| c.len_utf16())
+            .map(|&c| self.encoding.len(c))
             .sum();
 
         Position {
@@ -50,16 +102,81 @@ impl LineIndex {
     }
 
     /// Convert an LSP Position to a character index.
-    /// O(1) complexity using direct array access.
+    /// O(line length): columns are walked to convert encoding units to characters.
+    /// A column inside a multi-unit character (e.g. between the two UTF-16 surrogates of
+    /// an emoji) resolves to the start of that character; a column past the end of the line
+    /// resolves to the end of the line, before its newline.
     pub fn position_to_index(&self, source: &[char], position: Position) -> usize {
-        let line_idx = position.line as usize;
-
-        // Get the slice for the target line
-        let Some(&line_start) = self.line_starts.get(line_idx) else {
+        // Get the bounds of the target line
+        let Some((line_start, content_end)) = self.line_bounds(source, position.line) else {
             // Requested line doesn't exist - return last character
             return source.len().saturating_sub(1);
         };
 
+        // Walk the line until the requested column is reached
+        let target_col = position.character as usize;
+        let mut col = 0;
+        for (offset, &ch) in source[line_start..content_end].iter().enumerate() {
+            col += self.encoding.len(ch);
+            if col > target_col {
+                return line_start + offset;
+            }
+        }
+
+        // Column is at or past end of line - clamp to end
+        content_end
+    }
+
+    /// Check if a position is beyond the current document bounds.
+    pub fn is_position_out_of_bounds(&self, source: &[char], position: Position) -> bool {
+        let Some((line_start, content_end)) = self.line_bounds(source, position.line) else {
+            return true;
+        };
+
+        let line_len: usize = source[line_start..content_end]
+            .iter()
+            .map(|&c| self.encoding.len(c))
+            .sum();
+
+        position.character as usize > line_len
+    }
+
+    /// Replace `range` in `source` with `new_text` (an incremental `didChange` event) and
+    /// patch the line starts around it instead of rescanning the whole document.
+    pub fn apply_edit(&mut self, source: &mut Vec<char>, range: Range, new_text: &str) {
+        let start = self.edit_offset(source, range.start);
+        let end = self.edit_offset(source, range.end).max(start);
+        let inserted: Vec<char> = new_text.chars().collect();
+        let inserted_end = start + inserted.len();
+        source.splice(start..end, inserted);
+
+        // Lines starting at the edges or inside the replaced text are found again, since
+        // the edit can join or split a "\r\n"; the ones after it move
+        let from = start.max(1);
+        let first = self.line_starts.partition_point(|&line| line < from);
+        let last = self.line_starts.partition_point(|&line| line <= end);
+        for line_start in &mut self.line_starts[last..] {
+            *line_start = *line_start + inserted_end - end;
+        }
+        let new_starts = (from..=inserted_end).filter(|&line| ends_line(source, line - 1));
+        self.line_starts.splice(first..last, new_starts);
+    }
+
+    /// Character index of an edit boundary; positions past the last line mean the end of
+    /// the document.
+    fn edit_offset(&self, source: &[char], position: Position) -> usize {
+        if position.line as usize >= self.line_starts.len() {
+            source.len()
+        } else {
+            self.position_to_index(source, position)
+        }
+    }
+
+    /// Character range of a line's content, excluding its terminating newline.
+    fn line_bounds(&self, source: &[char], line: u32) -> Option<(usize, usize)> {
+        let line_idx = line as usize;
+        let line_start = *self.line_starts.get(line_idx)?;
+
         // Find where this line ends (at next line start or end of source)
         let line_end = self
             .line_starts
@@ -67,41 +184,43 @@ impl LineIndex {
             .copied()
             .unwrap_or(source.len());
 
-        let target_line = &source[line_start..line_end];
-
-        // Find character at requested column
-        let target_char_idx = position.character as usize;
-
-        if target_char_idx > target_line.len() {
-            // Column is past end of line - clamp to end
-            return line_end;
+        // Strip the line break: "\n", "\r\n" or a lone "\r"
+        let mut content_end = line_end;
+        if content_end > line_start && source.get(content_end - 1) == Some(&'\n') {
+            content_end -= 1;
+        }
+        if content_end > line_start && source.get(content_end - 1) == Some(&'\r') {
+            content_end -= 1;
         }
 
-        line_start + target_char_idx
+        Some((line_start, content_end))
     }
+}
 
-    /// Check if a position is beyond the current document bounds.
-    pub fn is_position_out_of_bounds(&self, source: &[char], position: Position) -> bool {
-        let line_idx = position.line as usize;
-
-        if let Some(&line_start) = self.line_starts.get(line_idx) {
-            let line_end = self
-                .line_starts
-                .get(line_idx + 1)
-                .copied()
-                .unwrap_or(source.len());
-
-            let raw_line_len = line_end - line_start;
-            let line_len = if raw_line_len > 0 && source.get(line_end - 1) == Some(&'\n') {
-                raw_line_len - 1
-            } else {
-                raw_line_len
-            };
-
-            position.character as usize > line_len
-        } else {
-            true
+/// Character ranges of the lines of `source`, without their `\n`.
+pub fn line_ranges(source: &[char]) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        if start > source.len() {
+            return None;
         }
+        let end = source[start..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(source.len(), |offset| start + offset);
+        let line = start..end;
+        start = end + 1;
+        Some(line)
+    })
+}
+
+/// Whether the character at `idx` ends a line: a `\n`, or a `\r` not followed by one
+/// (the line break of "\r\n" ends at its `\n`).
+fn ends_line(source: &[char], idx: usize) -> bool {
+    match source[idx] {
+        '\n' => true,
+        '\r' => source.get(idx + 1) != Some(&'\n'),
+        _ => false,
     }
 }
 
@@ -112,7 +231,7 @@ mod tests {
     #[test]
     fn test_line_index_single_line() {
         let source: Vec<char> = "hello world".chars().collect();
-        let index = LineIndex::new(&source);
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
 
         let pos = Position {
             line: 0,
@@ -124,7 +243,7 @@ mod tests {
     #[test]
     fn test_line_index_multiple_lines() {
         let source: Vec<char> = "hello\nworld\ntest".chars().collect();
-        let index = LineIndex::new(&source);
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
 
         // Line 1, char 2 -> index 8 (after "hello\nwo")
         let pos = Position {
@@ -134,10 +253,196 @@ mod tests {
         assert_eq!(index.position_to_index(&source, pos), 8);
     }
 
+    #[test]
+    fn test_negotiate_encoding() {
+        let offered = [PositionEncodingKind::UTF8, PositionEncodingKind::UTF16];
+        assert_eq!(PositionEncoding::negotiate(Some(&offered)), PositionEncoding::Utf8);
+
+        let offered = [PositionEncodingKind::new("utf-7"), PositionEncodingKind::UTF32];
+        assert_eq!(PositionEncoding::negotiate(Some(&offered)), PositionEncoding::Utf32);
+
+        assert_eq!(PositionEncoding::negotiate(None), PositionEncoding::Utf16);
+    }
+
+    #[test]
+    fn test_utf8_columns_with_diacritics() {
+        let source: Vec<char> = "zażółć gęślą".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf8);
+
+        // "zażółć " is 7 chars but 11 bytes
+        let pos = index.index_to_position(&source, 7);
+        assert_eq!(pos.character, 11);
+        assert_eq!(index.position_to_index(&source, pos), 7);
+    }
+
+    #[test]
+    fn test_utf32_columns() {
+        let source: Vec<char> = "a😀b".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf32);
+
+        let pos = index.index_to_position(&source, 2);
+        assert_eq!(pos.character, 2);
+        assert_eq!(index.position_to_index(&source, pos), 2);
+    }
+
+    #[test]
+    fn test_utf16_surrogate_pairs_round_trip() {
+        // '😀' is one char but two UTF-16 code units
+        let source: Vec<char> = "a😀b\n😀😀c".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
+
+        for idx in 0..source.len() {
+            let pos = index.index_to_position(&source, idx);
+            assert_eq!(index.position_to_index(&source, pos), idx, "index {}", idx);
+        }
+
+        // 'c' on the second line sits at UTF-16 column 4, char index 6
+        let pos = Position {
+            line: 1,
+            character: 4,
+        };
+        assert_eq!(index.position_to_index(&source, pos), 6);
+    }
+
+    #[test]
+    fn test_utf16_column_inside_surrogate_pair() {
+        let source: Vec<char> = "a😀b".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
+
+        let pos = Position {
+            line: 0,
+            character: 2,
+        };
+        assert_eq!(index.position_to_index(&source, pos), 1);
+    }
+
+    #[test]
+    fn test_column_past_end_clamps_before_newline() {
+        let source: Vec<char> = "ab\ncd".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
+
+        let pos = Position {
+            line: 0,
+            character: 10,
+        };
+        assert_eq!(index.position_to_index(&source, pos), 2);
+    }
+
+    #[test]
+    fn test_out_of_bounds_counts_utf16_units() {
+        let source: Vec<char> = "😀😀\nx".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
+
+        let end_of_line = Position {
+            line: 0,
+            character: 4,
+        };
+        assert!(!index.is_position_out_of_bounds(&source, end_of_line));
+
+        let past_end = Position {
+            line: 0,
+            character: 5,
+        };
+        assert!(index.is_position_out_of_bounds(&source, past_end));
+
+        let missing_line = Position {
+            line: 2,
+            character: 0,
+        };
+        assert!(index.is_position_out_of_bounds(&source, missing_line));
+    }
+
+    #[test]
+    fn test_apply_edit_matches_rebuild() {
+        let mut source: Vec<char> = "Ala ma\nkota\n\ni psa".chars().collect();
+        let mut index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let at = |line, character| Position { line, character };
+        let edits = [
+            // Insert a line break in the middle of a line
+            (at(0, 3), at(0, 3), "\n"),
+            // Join two lines
+            (at(1, 3), at(2, 0), " "),
+            // Replace across several lines with multi-line text
+            (at(1, 0), at(3, 2), "żółw\nma\n"),
+            // Delete everything up to the last line
+            (at(0, 0), at(3, 0), ""),
+            // Append past the end of the document
+            (at(9, 0), at(9, 0), "!\nkoniec"),
+        ];
+
+        for (start, end, text) in edits {
+            index.apply_edit(&mut source, Range { start, end }, text);
+            let rebuilt = LineIndex::new(&source, PositionEncoding::Utf16);
+            assert_eq!(index.line_starts, rebuilt.line_starts, "after {:?}", text);
+        }
+        let text: String = source.iter().collect();
+        assert_eq!(text, "psa!\nkoniec");
+    }
+
+    #[test]
+    fn test_all_line_endings() {
+        // "\n", "\r\n" and a lone "\r" each end one line
+        let source: Vec<char> = "ab\ncd\r\nef\rgh".chars().collect();
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
+        assert_eq!(index.line_starts, vec![0, 3, 7, 10]);
+
+        let g = index.index_to_position(&source, 10);
+        assert_eq!((g.line, g.character), (3, 0));
+        assert_eq!(index.position_to_index(&source, g), 10);
+
+        // Columns past the end stop before the whole line break
+        for line in 0..3 {
+            let pos = Position {
+                line,
+                character: 10,
+            };
+            let end = index.position_to_index(&source, pos);
+            assert_eq!(end, index.line_starts[line as usize] + 2, "line {}", line);
+        }
+
+        let end_of_crlf_line = Position {
+            line: 1,
+            character: 2,
+        };
+        assert!(!index.is_position_out_of_bounds(&source, end_of_crlf_line));
+        let past_crlf_line = Position {
+            line: 1,
+            character: 3,
+        };
+        assert!(index.is_position_out_of_bounds(&source, past_crlf_line));
+    }
+
+    #[test]
+    fn test_apply_edit_joins_and_splits_crlf() {
+        let mut source: Vec<char> = "ab\rcd".chars().collect();
+        let mut index = LineIndex::new(&source, PositionEncoding::Utf16);
+        let at = |line, character| Position { line, character };
+        let edits = [
+            // "\n" right after a lone "\r" turns it into one "\r\n" break
+            (at(1, 0), at(1, 0), "\n"),
+            // Replacing the "\r\n" with "\r" leaves a lone "\r" again
+            (at(0, 2), at(1, 0), "\r"),
+            // Two lone breaks in a row, then one of them removed
+            (at(0, 2), at(0, 2), "x\r"),
+            (at(1, 0), at(2, 0), ""),
+            // Text ending in "\r" inserted before a "\n"
+            (at(1, 2), at(1, 2), "\n"),
+            (at(1, 0), at(1, 2), "y\r"),
+        ];
+
+        for (start, end, text) in edits {
+            index.apply_edit(&mut source, Range { start, end }, text);
+            let rebuilt = LineIndex::new(&source, PositionEncoding::Utf16);
+            assert_eq!(index.line_starts, rebuilt.line_starts, "after {:?}", text);
+        }
+        let text: String = source.iter().collect();
+        assert_eq!(text, "abx\ry\r\n");
+    }
+
     #[test]
     fn test_index_to_position() {
         let source: Vec<char> = "hello\nworld".chars().collect();
-        let index = LineIndex::new(&source);
+        let index = LineIndex::new(&source, PositionEncoding::Utf16);
 
         let pos = index.index_to_position(&source, 8);
         assert_eq!(pos.line, 1);
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tower_lsp_server::lsp_types::notification::Notification;
+use tower_lsp_server::lsp_types::request::WorkDoneProgressCreate;
+use tower_lsp_server::lsp_types::{NumberOrString, ProgressToken, WorkDoneProgressCreateParams};
+use tower_lsp_server::{Bounded, Client, NotCancellable, OngoingProgress, Unbounded};
+
+/// Documents with at least this many characters report progress while being checked.
+pub const LARGE_DOCUMENT: usize = 100_000;
+
+/// `$/progress` carrying a batch of a partial result; lsp_types only models work-done
+/// progress values.
+pub enum PartialResult {}
+
+impl Notification for PartialResult {
+    type Params = PartialResultValue;
+    const METHOD: &'static str = "$/progress";
+}
+
+/// Payload of a partial result notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialResultValue {
+    pub token: ProgressToken,
+    pub value: serde_json::Value,
+}
+
+/// Send one batch of a result to the client under the request's `partialResultToken`.
+pub async fn send_partial_result<T: Serialize>(client: &Client, token: &ProgressToken, value: T) {
+    let params = PartialResultValue {
+        token: token.clone(),
+        value: serde_json::to_value(value).unwrap_or_default(),
+    };
+    client.send_notification::<PartialResult>(params).await;
+    // Responses and client messages are merged into one output stream; yielding lets this
+    // batch go out before the handler finishes and its (empty) response becomes ready
+    tokio::task::yield_now().await;
+}
+
+/// Server-initiated `$/progress` reporting, so the editor can show a spinner during long
+/// operations. Disabled until the client advertises `window.workDoneProgress`.
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    client: Option<Client>,
+    enabled: Arc<AtomicBool>,
+    next_token: Arc<AtomicU32>,
+}
+
+impl ProgressReporter {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client: Some(client),
+            ..Self::default()
+        }
+    }
+
+    /// Turn reporting on or off, from the client capabilities.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Start progress without a percentage, or `None` if the client can't show it.
+    pub async fn begin(&self, title: &str) -> Option<OngoingProgress<Unbounded, NotCancellable>> {
+        let (client, token) = self.create_token().await?;
+        Some(client.progress(token, title).begin().await)
+    }
+
+    /// Start progress at 0%, or `None` if the client can't show it.
+    pub async fn begin_percentage(
+        &self,
+        title: &str,
+    ) -> Option<OngoingProgress<Bounded, NotCancellable>> {
+        let (client, token) = self.create_token().await?;
+        let progress = client.progress(token, title).with_percentage(0);
+        Some(progress.begin().await)
+    }
+
+    /// Ask the client for a new progress token.
+    async fn create_token(&self) -> Option<(&Client, NumberOrString)> {
+        let client = self.client.as_ref()?;
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let id = self.next_token.fetch_add(1, Ordering::Relaxed);
+        let token = NumberOrString::String(format!("polski-ls/{}", id));
+        let params = WorkDoneProgressCreateParams {
+            token: token.clone(),
+        };
+        match client.send_request::<WorkDoneProgressCreate>(params).await {
+            Ok(()) => Some((client, token)),
+            Err(e) => {
+                eprintln!("[POLSKI-LS] ERROR: Failed to create progress: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disabled_without_client() {
+        let progress = ProgressReporter::default();
+        progress.set_enabled(true);
+        assert!(progress.begin("Loading dictionaries").await.is_none());
+        let percentage = progress.begin_percentage("Checking spelling").await;
+        assert!(percentage.is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use std::ops::Range;
+
+use crate::langdetect::paragraphs;
+
+/// Spaces that glue words together (e.g. after a hanging "w" or "i") and must never
+/// become line breaks.
+const NON_BREAKING_SPACES: [char; 2] = ['\u{a0}', '\u{202f}'];
+
+/// The paragraph containing `idx`, without trailing whitespace.
+pub fn paragraph_at(source: &[char], idx: usize) -> Option<Range<usize>> {
+    let paragraph = paragraphs(source)
+        .into_iter()
+        .find(|range| range.contains(&idx))?;
+
+    let mut end = paragraph.end;
+    while end > paragraph.start && source[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    Some(paragraph.start..end)
+}
+
+/// Re-wrap paragraph text to at most `width` characters per line, keeping the first
+/// line's indentation on every line. Words longer than `width` get a line of their own.
+pub fn reflow(text: &[char], width: usize) -> String {
+    let indent: String = text
+        .iter()
+        .take_while(|c| matches!(c, ' ' | '\t'))
+        .collect();
+    let words = text.split(|c| is_break(*c)).filter(|word| !word.is_empty());
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = indent.clone();
+    let mut line_len = indent.chars().count();
+    let mut line_empty = true;
+
+    for word in words {
+        let word: String = word.iter().collect();
+        let word_len = word.chars().count();
+
+        if !line_empty && line_len + 1 + word_len > width {
+            lines.push(std::mem::replace(&mut line, indent.clone()));
+            line_len = indent.chars().count();
+            line_empty = true;
+        }
+        if !line_empty {
+            line.push(' ');
+            line_len += 1;
+        }
+        line.push_str(&word);
+        line_len += word_len;
+        line_empty = false;
+    }
+    lines.push(line);
+
+    lines.join("\n")
+}
+
+/// Whitespace where a line may be broken.
+fn is_break(ch: char) -> bool {
+    ch.is_whitespace() && !NON_BREAKING_SPACES.contains(&ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn test_paragraph_at() {
+        let source = chars("Pierwszy akapit.\n\nDrugi akapit,\ndwie linie.\n\n");
+        assert_eq!(paragraph_at(&source, 3), Some(0..16));
+        assert_eq!(paragraph_at(&source, 20), Some(18..43));
+        assert_eq!(paragraph_at(&source, 17), None);
+    }
+
+    #[test]
+    fn test_reflow_wraps_and_joins() {
+        let text = chars("Ala ma kota,\na kot ma Alę i psa.");
+        assert_eq!(reflow(&text, 15), "Ala ma kota, a\nkot ma Alę i\npsa.");
+        assert_eq!(reflow(&text, 80), "Ala ma kota, a kot ma Alę i psa.");
+    }
+
+    #[test]
+    fn test_reflow_keeps_non_breaking_spaces() {
+        let text = chars("Idę do domu i\u{a0}kota w\u{a0}ogrodzie");
+        assert_eq!(
+            reflow(&text, 14),
+            "Idę do domu\ni\u{a0}kota\nw\u{a0}ogrodzie"
+        );
+    }
+
+    #[test]
+    fn test_reflow_keeps_indentation_and_long_words() {
+        let text = chars("  bardzo długie słowo: konstantynopolitańczykowianeczka");
+        assert_eq!(
+            reflow(&text, 20),
+            "  bardzo długie\n  słowo:\n  konstantynopolitańczykowianeczka"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+/// Words doubled on purpose, for emphasis or in set phrases ("dawno dawno temu").
+const REDUPLICATIONS: &[&str] = &["bardzo", "dawno", "długo", "hej", "ledwo", "no", "tak"];
+
+/// A word written twice in a row, like "się się".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repetition {
+    /// Start of the whitespace before the duplicate; deleting `start..end` fixes it.
+    pub start: usize,
+    /// End of the duplicate.
+    pub end: usize,
+    /// The duplicate as written.
+    pub word: String,
+}
+
+/// Words repeated with nothing but whitespace between them ("to to", "w w"). Any
+/// punctuation ("Nie, nie") or a blank line separates them, and numbers aren't words.
+pub fn find(source: &[char]) -> Vec<Repetition> {
+    let mut repetitions = Vec::new();
+    let mut previous: Option<(usize, usize)> = None;
+    let mut newlines = 0;
+    let mut i = 0;
+    while i < source.len() {
+        if !is_token_char(source[i]) {
+            if source[i] == '\n' {
+                newlines += 1;
+            }
+            // a new paragraph starts over, like in `commas::separated_by_space`
+            if !source[i].is_whitespace() || newlines >= 2 {
+                previous = None;
+            }
+            i += 1;
+            continue;
+        }
+        newlines = 0;
+        let start = i;
+        while i < source.len() && is_token_char(source[i]) {
+            i += 1;
+        }
+        let word = &source[start..i];
+        if !word.iter().all(|c| c.is_alphabetic()) {
+            previous = None;
+            continue;
+        }
+        if let Some((previous_start, previous_end)) = previous {
+            if same_word(&source[previous_start..previous_end], word) && !is_reduplication(word) {
+                repetitions.push(Repetition {
+                    start: previous_end,
+                    end: i,
+                    word: word.iter().collect(),
+                });
+            }
+        }
+        previous = Some((start, i));
+    }
+    repetitions
+}
+
+/// Characters of the tokens compared; "COVID-19" or "rock'n'roll" is one token.
+fn is_token_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '-' | '\'' | '’')
+}
+
+fn same_word(a: &[char], b: &[char]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(x, y)| x.to_lowercase().eq(y.to_lowercase()))
+}
+
+fn is_reduplication(word: &[char]) -> bool {
+    let word: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+    REDUPLICATIONS.contains(&word.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repeated(text: &str) -> Vec<String> {
+        let source: Vec<char> = text.chars().collect();
+        find(&source)
+            .into_iter()
+            .map(|repetition| source[repetition.start..repetition.end].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_repeated_words() {
+        assert_eq!(
+            repeated("To to jest w w domu, bo się\nsię zgubił."),
+            vec![" to", " w", "\nsię"]
+        );
+        assert_eq!(repeated("jest jest jest"), vec![" jest", " jest"]);
+    }
+
+    #[test]
+    fn test_separated_or_intended_repetitions() {
+        assert!(repeated("Nie, nie wiem. Dawno dawno temu, tak tak.").is_empty());
+        assert!(repeated("10 10 razy, A-to to, kot... kot").is_empty());
+        assert!(repeated("Dom\n\nDom stoi").is_empty());
+        assert!(repeated("# Dom\n  \n Dom stoi").is_empty());
+    }
+}
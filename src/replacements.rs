@@ -0,0 +1,218 @@
+use regex::Regex;
+
+use crate::dictionary::{user_config_dir, WordListError};
+
+/// File name of the user's replacement rules in ~/.config/polski-ls/.
+pub const REPLACEMENTS_FILE: &str = "zamiany.tsv";
+
+/// Text matching a rule that should be written differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Character indices of the matched text.
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+/// A pattern and what the text it matches should be replaced with.
+#[derive(Debug)]
+struct Rule {
+    pattern: Regex,
+    replacement: String,
+    /// Regular expression rules may refer to capture groups in the replacement.
+    expand: bool,
+}
+
+/// Rules catching errors a dictionary lookup can't, typically words written together
+/// or apart the wrong way ("wogóle" for "w ogóle", "na prawdę" for "naprawdę"): the
+/// rules shipped in zamiany.tsv plus the user's own.
+///
+/// Source format is one rule per line: `pattern<TAB>replacement`, with `#` comments. A
+/// pattern matches literally, ignoring case, as whole words with any whitespace between
+/// them; `/pattern/` is a regular expression whose replacement may use `$1`, `$2`...
+#[derive(Debug, Default)]
+pub struct Replacements {
+    rules: Vec<Rule>,
+}
+
+impl Replacements {
+    /// Create an empty rule set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the shipped rules and ~/.config/polski-ls/zamiany.tsv, if it exists.
+    pub fn load() -> Self {
+        let mut replacements = Self::new();
+        replacements.parse(include_str!("../zamiany.tsv"));
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(REPLACEMENTS_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading replacement rules: {:?}", path);
+                replacements.parse(&content);
+            }
+        }
+
+        replacements
+    }
+
+    /// Add rules from source text, skipping invalid ones; `validate_replacements`
+    /// reports them.
+    pub fn parse(&mut self, content: &str) {
+        let rules = content
+            .lines()
+            .filter_map(parse_line)
+            .filter_map(Result::ok);
+        self.rules.extend(rules);
+    }
+
+    /// Every match of every rule in `source`, in rule order.
+    pub fn find(&self, source: &[char]) -> Vec<Replacement> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let text: String = source.iter().collect();
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        let char_index = |offset: usize| offsets.partition_point(|&o| o < offset);
+
+        let mut found = Vec::new();
+        for rule in &self.rules {
+            for captures in rule.pattern.captures_iter(&text) {
+                let matched = captures.get(0).expect("group 0 is the whole match");
+                if matched.is_empty() {
+                    continue;
+                }
+                let mut replacement = String::new();
+                if rule.expand {
+                    captures.expand(&rule.replacement, &mut replacement);
+                } else {
+                    replacement.push_str(&rule.replacement);
+                }
+                found.push(Replacement {
+                    start: char_index(matched.start()),
+                    end: char_index(matched.end()),
+                    replacement,
+                });
+            }
+        }
+        found
+    }
+}
+
+/// The rule on a line, `None` for comments and blank lines.
+fn parse_line(line: &str) -> Option<Result<Rule, String>> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let Some((pattern, replacement)) = trimmed.split_once('\t') else {
+        return Some(Err(format!(
+            "expected pattern<TAB>replacement, found '{}'",
+            trimmed
+        )));
+    };
+    let (pattern, replacement) = (pattern.trim(), replacement.trim().to_string());
+
+    let regex = pattern
+        .strip_prefix('/')
+        .and_then(|pattern| pattern.strip_suffix('/'))
+        .filter(|pattern| !pattern.is_empty());
+    let rule = match regex {
+        Some(pattern) => Regex::new(pattern).map(|pattern| Rule {
+            pattern,
+            replacement,
+            expand: true,
+        }),
+        None => Regex::new(&literal_pattern(pattern)).map(|pattern| Rule {
+            pattern,
+            replacement,
+            expand: false,
+        }),
+    };
+    Some(rule.map_err(|e| format!("invalid pattern '{}': {}", pattern, e)))
+}
+
+/// A regular expression matching `literal` ignoring case, as whole words separated by
+/// any whitespace.
+fn literal_pattern(literal: &str) -> String {
+    let words: Vec<String> = literal.split_whitespace().map(regex::escape).collect();
+    let word_edge = |ch: Option<char>| match ch {
+        Some(ch) if ch.is_alphanumeric() => r"\b",
+        _ => "",
+    };
+    format!(
+        "(?i){}{}{}",
+        word_edge(literal.chars().next()),
+        words.join(r"\s+"),
+        word_edge(literal.chars().last())
+    )
+}
+
+/// Validate replacement rule source text, reporting lines without a replacement and
+/// patterns that don't compile.
+pub fn validate_replacements(content: &str) -> Vec<WordListError> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| match parse_line(line)? {
+            Ok(_) => None,
+            Err(message) => Some(WordListError {
+                line: idx + 1,
+                message,
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(replacements: &Replacements, text: &str) -> Vec<(String, String)> {
+        let source: Vec<char> = text.chars().collect();
+        replacements
+            .find(&source)
+            .into_iter()
+            .map(|r| (source[r.start..r.end].iter().collect(), r.replacement))
+            .collect()
+    }
+
+    #[test]
+    fn test_literal_rules() {
+        let mut replacements = Replacements::new();
+        replacements.parse("# comment\nwogóle\tw ogóle\nna prawdę\tnaprawdę\n");
+
+        assert_eq!(
+            found(&replacements, "Żółw Wogóle tak, na\n prawdę."),
+            vec![
+                ("Wogóle".to_string(), "w ogóle".to_string()),
+                ("na\n prawdę".to_string(), "naprawdę".to_string()),
+            ]
+        );
+        // Only whole words match
+        assert!(found(&replacements, "wogólenie nieprawdę").is_empty());
+    }
+
+    #[test]
+    fn test_regex_rules() {
+        let mut replacements = Replacements::new();
+        replacements.parse("/\\b(\\d+) ?zl\\b/\t$1 zł\n");
+        assert_eq!(
+            found(&replacements, "Kosztuje 20zl, nie 30 zl."),
+            vec![
+                ("20zl".to_string(), "20 zł".to_string()),
+                ("30 zl".to_string(), "30 zł".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_replacements() {
+        let content = "# comment\nwogóle\tw ogóle\nnarazie\n/(\\d+/\tx\n";
+        let errors = validate_replacements(content);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+        assert!(validate_replacements(include_str!("../zamiany.tsv")).is_empty());
+    }
+}
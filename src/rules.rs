@@ -0,0 +1,373 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::diagnostics::Category;
+use crate::dictionary::{user_config_dir, WordListError};
+
+/// File name of the user's grammar and style rules in ~/.config/polski-ls/.
+pub const RULES_FILE: &str = "reguly.toml";
+
+/// `$1` or `${1}` in a replacement, with the group number captured.
+static GROUP_REFERENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{?(\d+)\}?").unwrap());
+
+/// Text matching a rule, with the rule's message and corrections.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    /// Character indices of the matched text.
+    pub start: usize,
+    pub end: usize,
+    pub id: String,
+    pub category: Category,
+    pub message: String,
+    /// Corrections, best first; empty if the rule only explains the problem.
+    pub replacements: Vec<String>,
+}
+
+impl RuleMatch {
+    /// The diagnostic code, the rule's id tagged with its category ("grammar/ta-te").
+    pub fn code(&self) -> String {
+        format!("{}/{}", self.category.name(), self.id)
+    }
+}
+
+/// A rule as written in a rules file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSource {
+    id: String,
+    #[serde(default = "default_category")]
+    category: Category,
+    /// Words matched one after another, ignoring case; `/regex/` matches a whole word.
+    tokens: Option<Vec<String>>,
+    /// A regular expression, for what tokens can't express.
+    pattern: Option<String>,
+    message: String,
+    #[serde(default)]
+    replacements: Vec<String>,
+}
+
+fn default_category() -> Category {
+    Category::Grammar
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<Spanned<RuleSource>>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    id: String,
+    category: Category,
+    pattern: Regex,
+    message: String,
+    /// Replacement templates, referring to capture groups as `${1}` or `${t1}`.
+    replacements: Vec<String>,
+}
+
+/// Grammar and style rules matched against the text, so new ones are added by editing
+/// a file rather than the code: the rules shipped in reguly.toml plus the user's own.
+///
+/// Each `[[rule]]` table has an `id`, a `message` and either `tokens`, a list of words
+/// matched ignoring case with any whitespace between them (`/regex/` matching a whole
+/// word), or a regular expression `pattern`. Optional `replacements` may refer to the
+/// tokens, or the pattern's capture groups, as `$1`, `$2`... The `category` ("grammar"
+/// by default) decides how the findings are reported. A rule reusing an earlier rule's
+/// id replaces it.
+#[derive(Debug, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    /// Create an empty rule set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the shipped rules and ~/.config/polski-ls/reguly.toml, if it exists.
+    pub fn load() -> Self {
+        let mut rules = Self::new();
+        rules.parse(include_str!("../reguly.toml"));
+
+        if let Some(dir) = user_config_dir() {
+            let path = dir.join(RULES_FILE);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                eprintln!("[POLSKI-LS] Loading grammar rules: {:?}", path);
+                rules.parse(&content);
+            }
+        }
+
+        rules
+    }
+
+    /// Add rules from source text, skipping invalid ones, or all of them if it isn't
+    /// valid TOML; `validate_rules` reports them.
+    pub fn parse(&mut self, content: &str) {
+        let Ok(file) = toml::from_str::<RulesFile>(content) else {
+            return;
+        };
+        for source in file.rule {
+            let Ok(rule) = compile(source.into_inner()) else {
+                continue;
+            };
+            match self
+                .rules
+                .iter_mut()
+                .find(|existing| existing.id == rule.id)
+            {
+                Some(existing) => *existing = rule,
+                None => self.rules.push(rule),
+            }
+        }
+    }
+
+    /// Every match of every rule in `source`, in rule order.
+    pub fn find(&self, source: &[char]) -> Vec<RuleMatch> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let text: String = source.iter().collect();
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        let char_index = |offset: usize| offsets.partition_point(|&o| o < offset);
+
+        let mut found = Vec::new();
+        for rule in &self.rules {
+            for captures in rule.pattern.captures_iter(&text) {
+                let matched = captures.get(0).expect("group 0 is the whole match");
+                if matched.is_empty() {
+                    continue;
+                }
+                let replacements = rule
+                    .replacements
+                    .iter()
+                    .map(|template| {
+                        let mut replacement = String::new();
+                        captures.expand(template, &mut replacement);
+                        replacement
+                    })
+                    .collect();
+                found.push(RuleMatch {
+                    start: char_index(matched.start()),
+                    end: char_index(matched.end()),
+                    id: rule.id.clone(),
+                    category: rule.category,
+                    message: rule.message.clone(),
+                    replacements,
+                });
+            }
+        }
+        found
+    }
+}
+
+/// Check a rule and compile its pattern.
+fn compile(source: RuleSource) -> Result<Rule, String> {
+    let valid_id = |ch: char| ch.is_lowercase() || ch.is_ascii_digit() || ch == '-';
+    if source.id.is_empty() || !source.id.chars().all(valid_id) {
+        return Err(format!(
+            "rule id '{}' should be lowercase words joined by hyphens",
+            source.id
+        ));
+    }
+    if source.message.trim().is_empty() {
+        return Err(format!("rule '{}' has no message", source.id));
+    }
+
+    let (pattern, token_count) = match (&source.tokens, &source.pattern) {
+        (Some(tokens), None) if !tokens.is_empty() => (token_pattern(tokens), Some(tokens.len())),
+        (None, Some(pattern)) if !pattern.is_empty() => (pattern.clone(), None),
+        _ => {
+            return Err(format!(
+                "rule '{}' needs either tokens or a pattern",
+                source.id
+            ))
+        }
+    };
+    let pattern = Regex::new(&pattern)
+        .map_err(|e| format!("invalid pattern in rule '{}': {}", source.id, e))?;
+    let groups = token_count.unwrap_or(pattern.captures_len() - 1);
+
+    for template in &source.replacements {
+        let unknown = GROUP_REFERENCE
+            .captures_iter(template)
+            .filter_map(|reference| reference[1].parse::<usize>().ok())
+            .find(|&group| group == 0 || group > groups);
+        if let Some(group) = unknown {
+            return Err(format!(
+                "rule '{}' refers to ${} but only has {} group(s)",
+                source.id, group, groups
+            ));
+        }
+    }
+
+    // Groups inside token regexes would shift the numbers, so tokens are named
+    let replacements = match token_count {
+        Some(_) => source
+            .replacements
+            .iter()
+            .map(|template| GROUP_REFERENCE.replace_all(template, "$${t$1}").into())
+            .collect(),
+        None => source.replacements,
+    };
+    Ok(Rule {
+        id: source.id,
+        category: source.category,
+        pattern,
+        message: source.message,
+        replacements,
+    })
+}
+
+/// A regular expression matching `tokens` as whole words ignoring case, separated by any
+/// whitespace, capturing token `n` as group `tn`.
+fn token_pattern(tokens: &[String]) -> String {
+    let groups: Vec<String> = tokens
+        .iter()
+        .enumerate()
+        .map(|(idx, token)| {
+            let regex = token
+                .strip_prefix('/')
+                .and_then(|token| token.strip_suffix('/'))
+                .filter(|token| !token.is_empty());
+            let token = match regex {
+                Some(regex) => regex.to_string(),
+                None => regex::escape(token),
+            };
+            format!("(?P<t{}>{})", idx + 1, token)
+        })
+        .collect();
+    format!(r"(?i)\b{}\b", groups.join(r"\s+"))
+}
+
+/// Validate rules file source text, reporting TOML errors and rules that don't
+/// compile, or repeat an id used earlier in the file.
+pub fn validate_rules(content: &str) -> Vec<WordListError> {
+    let line_of = |offset: usize| content[..offset].matches('\n').count() + 1;
+    let file = match toml::from_str::<RulesFile>(content) {
+        Ok(file) => file,
+        Err(e) => {
+            return vec![WordListError {
+                line: e.span().map_or(1, |span| line_of(span.start)),
+                message: e.message().to_string(),
+            }]
+        }
+    };
+
+    let mut errors = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+    for source in file.rule {
+        let line = line_of(source.span().start);
+        let source = source.into_inner();
+        if ids.contains(&source.id) {
+            errors.push(WordListError {
+                line,
+                message: format!("duplicate rule id '{}'", source.id),
+            });
+        }
+        ids.push(source.id.clone());
+        if let Err(message) = compile(source) {
+            errors.push(WordListError { line, message });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(rules: &Rules, text: &str) -> Vec<(String, Vec<String>)> {
+        let source: Vec<char> = text.chars().collect();
+        rules
+            .find(&source)
+            .into_iter()
+            .map(|m| (source[m.start..m.end].iter().collect(), m.replacements))
+            .collect()
+    }
+
+    #[test]
+    fn test_token_rules() {
+        let mut rules = Rules::new();
+        rules.parse(
+            r#"
+            [[rule]]
+            id = "ta-te"
+            tokens = ["tą", "/\\p{L}+ę/"]
+            message = "Biernik: tę"
+            replacements = ["tę $2"]
+            "#,
+        );
+        assert_eq!(
+            found(&rules, "Wziąłem Tą\nksiążkę, idę tą drogą."),
+            vec![("Tą\nksiążkę".to_string(), vec!["tę książkę".to_string()])]
+        );
+        // Tokens are whole words
+        assert!(found(&rules, "tąksiążkę tą książkęx").is_empty());
+    }
+
+    #[test]
+    fn test_pattern_rules_and_overrides() {
+        let mut rules = Rules::new();
+        rules.parse(
+            r#"
+            [[rule]]
+            id = "zl"
+            category = "typography"
+            pattern = '\b(\d+) ?zl\b'
+            message = "Złoty"
+            replacements = ["$1 zł"]
+            "#,
+        );
+        let matches = rules.find(&"Kosztuje 20zl.".chars().collect::<Vec<_>>());
+        assert_eq!(matches[0].code(), "typography/zl");
+        assert_eq!(matches[0].replacements, vec!["20 zł"]);
+
+        // A later rule with the same id replaces the earlier one
+        rules.parse("[[rule]]\nid = \"zl\"\ntokens = [\"zl\"]\nmessage = \"Skrót\"\n");
+        assert_eq!(
+            found(&rules, "Kosztuje 20 zl."),
+            vec![("zl".to_string(), vec![])]
+        );
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let content = r#"
+[[rule]]
+id = "dobra"
+tokens = ["w", "cudzysłowiu"]
+message = "Miejscownik: w cudzysłowie"
+replacements = ["$1 cudzysłowie"]
+
+[[rule]]
+id = "dobra"
+pattern = "(abc"
+message = "x"
+
+[[rule]]
+id = "Zła Nazwa"
+tokens = ["a"]
+message = "x"
+
+[[rule]]
+id = "grupy"
+tokens = ["a"]
+message = "x"
+replacements = ["$2"]
+
+[[rule]]
+id = "bez-wzorca"
+message = "x"
+"#;
+        let errors = validate_rules(content);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![8, 8, 13, 18, 24]);
+        assert!(validate_rules("[[rule]]\nid = 1\n")[0].line == 2);
+        assert!(validate_rules(include_str!("../reguly.toml")).is_empty());
+    }
+}
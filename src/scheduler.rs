@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tower_lsp_server::lsp_types::Uri;
+
+/// Boxed future returned by a queue handler.
+pub type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Background queue for diagnostics work, processed by a fixed number of workers.
+///
+/// The most recently queued document is handled first: when an editor restores a
+/// session, the last opened file is usually the one on screen. Queuing a document
+/// again replaces its pending entry, so each document is checked at most once.
+pub struct DiagnosticsQueue<T> {
+    inner: Arc<Inner<T>>,
+}
+
+struct Inner<T> {
+    pending: Mutex<Vec<(Uri, T)>>,
+    notify: Notify,
+}
+
+impl<T: Send + 'static> DiagnosticsQueue<T> {
+    /// Create the queue and spawn `workers` tasks running `handler` for each entry.
+    pub fn new<F>(workers: usize, handler: F) -> Self
+    where
+        F: Fn(Uri, T) -> Job + Send + Sync + 'static,
+    {
+        let inner = Arc::new(Inner {
+            pending: Mutex::new(Vec::new()),
+            notify: Notify::new(),
+        });
+        let handler = Arc::new(handler);
+
+        for _ in 0..workers {
+            let inner = Arc::clone(&inner);
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                loop {
+                    match inner.pop() {
+                        Some((uri, item)) => handler(uri, item).await,
+                        None => inner.notify.notified().await,
+                    }
+                }
+            });
+        }
+
+        Self { inner }
+    }
+
+    /// Queue a document with the highest priority, replacing any pending entry for it.
+    pub fn push(&self, uri: Uri, item: T) {
+        let mut pending = self.inner.pending.lock().unwrap();
+        pending.retain(|(queued, _)| *queued != uri);
+        pending.push((uri, item));
+        drop(pending);
+        self.inner.notify.notify_one();
+    }
+
+    /// Drop the pending entry for a document, e.g. when it was closed or re-checked directly.
+    pub fn remove(&self, uri: &Uri) {
+        let mut pending = self.inner.pending.lock().unwrap();
+        pending.retain(|(queued, _)| queued != uri);
+    }
+
+    /// Number of documents waiting to be processed.
+    pub fn len(&self) -> usize {
+        self.inner.pending.lock().unwrap().len()
+    }
+}
+
+impl<T> Inner<T> {
+    /// Take the newest entry.
+    fn pop(&self) -> Option<(Uri, T)> {
+        self.pending.lock().unwrap().pop()
+    }
+}
+
+/// Sends results through one task per document: while a document's last result is
+/// less than `interval` old, newer ones wait and only the latest of them is sent. Rapid
+/// successive diagnostic sets (paste, find/replace) are merged instead of flooding the
+/// client, and each document is published at most once per `interval`.
+pub struct DiagnosticsPublisher<T> {
+    inner: Arc<PublisherInner<T>>,
+}
+
+struct PublisherInner<T> {
+    /// Documents with a running publisher task, and the result waiting for it, if any.
+    pending: Mutex<HashMap<Uri, Option<T>>>,
+    interval: Duration,
+    handler: Box<dyn Fn(Uri, T) -> Job + Send + Sync>,
+}
+
+impl<T> Clone for DiagnosticsPublisher<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Send + 'static> DiagnosticsPublisher<T> {
+    /// Create a publisher that sends each result with `handler`.
+    pub fn new<F>(interval: Duration, handler: F) -> Self
+    where
+        F: Fn(Uri, T) -> Job + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(PublisherInner {
+                pending: Mutex::new(HashMap::new()),
+                interval,
+                handler: Box::new(handler),
+            }),
+        }
+    }
+
+    /// Send `item` now if the document is idle, otherwise replace whatever is waiting.
+    pub fn publish(&self, uri: Uri, item: T) {
+        let mut pending = self.inner.pending.lock().unwrap();
+        if let Some(waiting) = pending.get_mut(&uri) {
+            *waiting = Some(item);
+            return;
+        }
+        pending.insert(uri.clone(), None);
+        drop(pending);
+
+        let inner = Arc::clone(&self.inner);
+        tokio::spawn(async move {
+            let mut next = Some(item);
+            while let Some(item) = next {
+                (inner.handler)(uri.clone(), item).await;
+                tokio::time::sleep(inner.interval).await;
+
+                let mut pending = inner.pending.lock().unwrap();
+                next = pending.get_mut(&uri).and_then(Option::take);
+                if next.is_none() {
+                    pending.remove(&uri);
+                }
+            }
+        });
+    }
+}
+
+/// Per-document debounce: of the jobs scheduled for a document, only the last one runs,
+/// once `delay` has passed without another being scheduled.
+#[derive(Clone, Default)]
+pub struct Debouncer {
+    /// Latest ticket handed out for each document.
+    latest: Arc<Mutex<HashMap<Uri, u64>>>,
+    /// Source of tickets, shared by all documents so a ticket is never reused after
+    /// its entry was removed.
+    next_ticket: Arc<AtomicU64>,
+}
+
+impl Debouncer {
+    /// Run `job` after `delay` unless another job is scheduled for `uri` in the meantime
+    /// or the document is cancelled.
+    pub fn schedule<F>(&self, uri: Uri, delay: Duration, job: F)
+    where
+        F: FnOnce(Uri) + Send + 'static,
+    {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        self.latest.lock().unwrap().insert(uri.clone(), ticket);
+
+        let latest = Arc::clone(&self.latest);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let mut latest = latest.lock().unwrap();
+            if latest.get(&uri) == Some(&ticket) {
+                latest.remove(&uri);
+                drop(latest);
+                job(uri);
+            }
+        });
+    }
+
+    /// Drop the pending job for a document, e.g. when it was closed.
+    pub fn cancel(&self, uri: &Uri) {
+        self.latest.lock().unwrap().remove(uri);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(name: &str) -> Uri {
+        format!("file:///{}", name).parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_newest_first_and_replacement() {
+        let queue: DiagnosticsQueue<u32> = DiagnosticsQueue::new(0, |_, _| Box::pin(async {}));
+        queue.push(uri("a.md"), 1);
+        queue.push(uri("b.md"), 2);
+        queue.push(uri("a.md"), 3);
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.inner.pop(), Some((uri("a.md"), 3)));
+        assert_eq!(queue.inner.pop(), Some((uri("b.md"), 2)));
+        assert_eq!(queue.inner.pop(), None);
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let queue: DiagnosticsQueue<u32> = DiagnosticsQueue::new(0, |_, _| Box::pin(async {}));
+        queue.push(uri("a.md"), 1);
+        queue.push(uri("b.md"), 2);
+        queue.remove(&uri("a.md"));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.inner.pop(), Some((uri("b.md"), 2)));
+    }
+
+    #[tokio::test]
+    async fn test_workers_process_everything() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let queue = DiagnosticsQueue::new(2, move |uri, item: u32| {
+            let tx = tx.clone();
+            Box::pin(async move {
+                let _ = tx.send((uri, item));
+            })
+        });
+
+        for i in 0..5 {
+            queue.push(uri(&format!("{}.md", i)), i);
+        }
+
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            let received = rx.recv().await;
+            seen.push(received.unwrap().1);
+        }
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_debouncer_runs_last_job() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let debouncer = Debouncer::default();
+        let delay = Duration::from_millis(20);
+        for i in 0..3 {
+            let tx = tx.clone();
+            debouncer.schedule(uri("a.md"), delay, move |uri| {
+                let _ = tx.send((uri, i));
+            });
+        }
+        let tx_b = tx.clone();
+        debouncer.schedule(uri("b.md"), delay, move |uri| {
+            let _ = tx_b.send((uri, 10));
+        });
+        debouncer.schedule(uri("c.md"), delay, move |uri| {
+            let _ = tx.send((uri, 20));
+        });
+        debouncer.cancel(&uri("c.md"));
+
+        let mut seen = vec![rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        seen.sort_by_key(|(_, i)| *i);
+        assert_eq!(seen, vec![(uri("a.md"), 2), (uri("b.md"), 10)]);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debouncer_schedule_after_cancel() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let debouncer = Debouncer::default();
+        let delay = Duration::from_millis(20);
+        let tx_old = tx.clone();
+        debouncer.schedule(uri("a.md"), delay, move |uri| {
+            let _ = tx_old.send((uri, 1));
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        debouncer.cancel(&uri("a.md"));
+        debouncer.schedule(uri("a.md"), delay, move |uri| {
+            let _ = tx.send((uri, 2));
+        });
+
+        // the cancelled job must neither run nor swallow the new one
+        assert_eq!(rx.recv().await.unwrap(), (uri("a.md"), 2));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_publisher_merges_and_rate_limits() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let publisher = DiagnosticsPublisher::new(Duration::from_millis(50), move |uri, item| {
+            let tx = tx.clone();
+            Box::pin(async move {
+                let _ = tx.send((uri, item));
+            })
+        });
+
+        publisher.publish(uri("a.md"), 1);
+        publisher.publish(uri("a.md"), 2);
+        publisher.publish(uri("a.md"), 3);
+        publisher.publish(uri("b.md"), 10);
+
+        let mut first = vec![rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        first.sort_by_key(|(_, item)| *item);
+        assert_eq!(first, vec![(uri("a.md"), 1), (uri("b.md"), 10)]);
+        // 2 was replaced by 3 while the first result was fresh
+        assert_eq!(rx.recv().await.unwrap(), (uri("a.md"), 3));
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(publisher.inner.pending.lock().unwrap().is_empty());
+        publisher.publish(uri("a.md"), 4);
+        assert_eq!(rx.recv().await.unwrap(), (uri("a.md"), 4));
+    }
+}
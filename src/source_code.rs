@@ -0,0 +1,145 @@
+use std::ops::Range;
+
+use tree_sitter::{Language, Node, Parser};
+
+/// Node kinds holding comments and string literals, the prose of source code.
+const PROSE_KINDS: &[&str] = &[
+    "block_comment",
+    "comment",
+    "line_comment",
+    "raw_string_literal",
+    "string",
+    "string_literal",
+    "template_string",
+];
+
+/// Node kinds inside strings that are code again: escapes ("\n"), f-string and template
+/// interpolations.
+const CODE_IN_STRING_KINDS: &[&str] =
+    &["escape_sequence", "interpolation", "template_substitution"];
+
+/// The grammar for documents in `language_id`, if one is built in.
+fn language(language_id: &str) -> Option<Language> {
+    let language = match language_id {
+        "rust" => tree_sitter_rust::LANGUAGE,
+        "python" => tree_sitter_python::LANGUAGE,
+        "javascript" | "javascriptreact" => tree_sitter_javascript::LANGUAGE,
+        "c" => tree_sitter_c::LANGUAGE,
+        "cpp" => tree_sitter_cpp::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+/// Whether documents in `language_id` are source code with a built-in grammar.
+pub fn has_grammar(language_id: &str) -> bool {
+    language(language_id).is_some()
+}
+
+/// Character ranges of a source file that aren't comments or string literals, or
+/// `None` if there is no grammar for `language_id`. Code is skipped so that Polish
+/// comments and docstrings can be checked without flagging every identifier.
+pub fn non_prose(language_id: &str, source: &[char]) -> Option<Vec<Range<usize>>> {
+    let language = language(language_id)?;
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let text: String = source.iter().collect();
+    let tree = parser.parse(&text, None)?;
+
+    let mut prose = Vec::new();
+    let mut code_in_strings = Vec::new();
+    collect(tree.root_node(), &mut prose, &mut code_in_strings);
+
+    let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+    let char_index = |offset: usize| offsets.partition_point(|&o| o < offset);
+    let mut spans = Vec::new();
+    let mut code_start = 0;
+    for range in prose {
+        spans.push(code_start..char_index(range.start));
+        code_start = char_index(range.end);
+    }
+    spans.push(code_start..source.len());
+    spans.extend(
+        code_in_strings
+            .into_iter()
+            .map(|range| char_index(range.start)..char_index(range.end)),
+    );
+    spans.retain(|span| !span.is_empty());
+    Some(spans)
+}
+
+/// Byte ranges of the comments and strings under `node`, in order, and of the code
+/// inside those strings.
+fn collect(node: Node, prose: &mut Vec<Range<usize>>, code_in_strings: &mut Vec<Range<usize>>) {
+    if PROSE_KINDS.contains(&node.kind()) {
+        prose.push(node.byte_range());
+        collect_code(node, code_in_strings);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect(child, prose, code_in_strings);
+    }
+}
+
+/// Byte ranges of the escapes and interpolations under the string `node`.
+fn collect_code(node: Node, code: &mut Vec<Range<usize>>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if CODE_IN_STRING_KINDS.contains(&child.kind()) {
+            code.push(child.byte_range());
+        } else {
+            collect_code(child, code);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of `code` left for spell-checking, with skipped characters dropped.
+    fn checked(language_id: &str, code: &str) -> String {
+        let source: Vec<char> = code.chars().collect();
+        let spans = non_prose(language_id, &source).unwrap();
+        source
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !spans.iter().any(|span| span.contains(idx)))
+            .map(|(_, ch)| *ch)
+            .collect()
+    }
+
+    #[test]
+    fn test_rust() {
+        let code = "/// Zwraca wynik.\nfn main() { println!(\"Cześć\\n{}\", x); } // koniec";
+        assert_eq!(
+            checked("rust", code),
+            "/// Zwraca wynik.\n\"Cześć{}\"// koniec"
+        );
+    }
+
+    #[test]
+    fn test_python_and_javascript() {
+        let code = "def f():\n    \"\"\"Liczy sumę.\"\"\"\n    return f\"Wynik: {suma}\"  # uwaga";
+        assert_eq!(
+            checked("python", code),
+            "\"\"\"Liczy sumę.\"\"\"f\"Wynik: \"# uwaga"
+        );
+        let code = "const x = `Witaj ${imie}`; /* komentarz */";
+        assert_eq!(checked("javascript", code), "`Witaj `/* komentarz */");
+    }
+
+    #[test]
+    fn test_c_and_cpp() {
+        let code = "int x = 1; /* zmienna */\nconst char *s = \"tekst\";";
+        assert_eq!(checked("c", code), "/* zmienna */\"tekst\"");
+        let code = "auto s = R\"(surowy)\"; // opis";
+        assert_eq!(checked("cpp", code), "R\"(surowy)\"// opis");
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(non_prose("markdown", &['a']).is_none());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tower_lsp_server::lsp_types::notification::Notification;
+use tower_lsp_server::lsp_types::Uri;
+
+/// Custom `polski-ls/status` notification for editor status-bar integrations.
+///
+/// Sent whenever a document's diagnostics change, it is closed, or the dictionary changes.
+pub enum StatusNotification {}
+
+impl Notification for StatusNotification {
+    type Params = StatusParams;
+    const METHOD: &'static str = "polski-ls/status";
+}
+
+/// Payload of the status notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusParams {
+    /// Open documents that have been spell-checked.
+    pub documents_checked: usize,
+    /// Unknown words across those documents.
+    pub words_flagged: usize,
+    /// Number of words in the loaded dictionary.
+    pub dictionary_size: usize,
+    /// Dictionary backend in use.
+    pub backend: String,
+}
+
+/// Latest number of flagged words for each checked document.
+#[derive(Debug, Default)]
+pub struct CheckStats {
+    flagged: HashMap<Uri, usize>,
+}
+
+impl CheckStats {
+    /// Record the result of checking a document, replacing the previous one.
+    pub fn record(&mut self, uri: &Uri, flagged: usize) {
+        self.flagged.insert(uri.clone(), flagged);
+    }
+
+    /// Whether a document's result has been recorded and not forgotten since.
+    pub fn contains(&self, uri: &Uri) -> bool {
+        self.flagged.contains_key(uri)
+    }
+
+    /// Forget a closed document.
+    pub fn forget(&mut self, uri: &Uri) {
+        self.flagged.remove(uri);
+    }
+
+    /// Build the notification payload.
+    pub fn status(&self, dictionary_size: usize, backend: &str) -> StatusParams {
+        StatusParams {
+            documents_checked: self.flagged.len(),
+            words_flagged: self.flagged.values().sum(),
+            dictionary_size,
+            backend: backend.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(name: &str) -> Uri {
+        format!("file:///{}", name).parse().unwrap()
+    }
+
+    #[test]
+    fn test_stats_track_latest_check() {
+        let mut stats = CheckStats::default();
+        stats.record(&uri("a.md"), 3);
+        stats.record(&uri("b.md"), 2);
+        stats.record(&uri("a.md"), 1);
+        assert_eq!(stats.status(100, "simple").documents_checked, 2);
+        assert_eq!(stats.status(100, "simple").words_flagged, 3);
+
+        stats.forget(&uri("b.md"));
+        assert_eq!(stats.status(100, "simple").words_flagged, 1);
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let status = CheckStats::default().status(150, "simple");
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({
+                "documentsChecked": 0,
+                "wordsFlagged": 0,
+                "dictionarySize": 150,
+                "backend": "simple",
+            })
+        );
+    }
+}
@@ -0,0 +1,87 @@
+/// Unit symbols written after numbers in technical texts ("230 kV", "2,4 GHz"). Their
+/// case is part of the symbol ("mW" and "MW" differ), so they match exactly.
+pub const UNITS: &[&str] = &[
+    "Ah", "bps", "cal", "cl", "cm", "dB", "dl", "dm", "dpi", "fps", "GB", "Gbps", "GHz", "GPa",
+    "GW", "GWh", "ha", "hl", "hPa", "Hz", "kB", "kbps", "kcal", "kg", "kHz", "kJ", "km", "kN",
+    "kPa", "kV", "kW", "kWh", "mA", "mAh", "MB", "Mbps", "mg", "MHz", "MJ", "ml", "mm", "mmHg",
+    "mol", "MPa", "mph", "ms", "mV", "mW", "MW", "MWh", "nm", "ppm", "rpm", "TB", "Wh",
+];
+
+/// Roman numerals with their values, largest first, including the subtractive pairs.
+const ROMAN: [(&str, u32); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// Whether `word` is not a word but a symbol: a Roman numeral ("XXI wiek", "Jan
+/// Paweł II") or a unit ("kWh").
+pub fn is_symbol(word: &[char]) -> bool {
+    let word: String = word.iter().collect();
+    is_roman_numeral(&word) || UNITS.contains(&word.as_str())
+}
+
+/// Whether `word` is an uppercase Roman numeral from I to MMMCMXCIX in standard form,
+/// so that words made of the same letters ("DIL", "MILD") aren't taken for one.
+fn is_roman_numeral(word: &str) -> bool {
+    let mut rest = word;
+    let mut value = 0;
+    for (numeral, numeral_value) in ROMAN {
+        while let Some(stripped) = rest.strip_prefix(numeral) {
+            rest = stripped;
+            value += numeral_value;
+        }
+    }
+    rest.is_empty() && value > 0 && to_roman(value) == word
+}
+
+/// The standard Roman numeral for `value`.
+fn to_roman(mut value: u32) -> String {
+    let mut roman = String::new();
+    for (numeral, numeral_value) in ROMAN {
+        while value >= numeral_value {
+            roman.push_str(numeral);
+            value -= numeral_value;
+        }
+    }
+    roman
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_roman_numerals() {
+        for numeral in ["I", "IV", "XXI", "XLIX", "MCMXCIX", "MMXXIV"] {
+            assert!(is_symbol(&chars(numeral)), "{}", numeral);
+        }
+        for word in ["IIII", "VX", "IC", "MILD", "DIL", "xxi", "Xi", ""] {
+            assert!(!is_symbol(&chars(word)), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_units() {
+        assert!(is_symbol(&chars("kWh")));
+        assert!(is_symbol(&chars("MHz")));
+        assert!(!is_symbol(&chars("mhz")));
+        assert!(UNITS
+            .iter()
+            .all(|unit| UNITS.iter().filter(|u| *u == unit).count() == 1));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::dictionary::{lowercase_key, osa_distance, Dictionary, FuzzyMatchResult};
+use crate::word_list::WordList;
+
+/// Largest edit distance the deletes index answers; wider queries scan every word.
+pub const MAX_DISTANCE: u8 = 2;
+
+/// Only this many leading characters of a word produce deletion variants, which keeps
+/// the index small for long inflected forms; candidates are verified on the whole word.
+const PREFIX_LENGTH: usize = 7;
+
+/// Dictionary backend using symmetric deletes (SymSpell): every word's deletion variants
+/// are precomputed, so a fuzzy query only generates the variants of the query and looks
+/// them up instead of comparing it against every word.
+#[derive(Clone, Default)]
+pub struct SymSpellDictionary {
+    words: WordList,
+    /// Lowercase deletion variants of each word's prefix, mapped to the words producing them.
+    deletes: HashMap<String, Vec<u32>>,
+}
+
+impl SymSpellDictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a word and its deletion variants to the index.
+    pub fn add_word(&mut self, word: &str, is_common: bool) {
+        let id = self.words.len() as u32;
+        let chars: Vec<char> = word.chars().collect();
+        for variant in deletion_variants(&chars, MAX_DISTANCE) {
+            self.deletes.entry(variant).or_default().push(id);
+        }
+        self.words.push(word, is_common, None);
+    }
+
+    /// The word with id `id`.
+    fn chars(&self, id: usize) -> Vec<char> {
+        self.words.get(id).0.chars().collect()
+    }
+
+    /// Ids of the words sharing a deletion variant with `query`, i.e. possibly within
+    /// `max_edit_distance` of it.
+    fn candidates(&self, query: &[char], max_edit_distance: u8) -> HashSet<u32> {
+        deletion_variants(query, max_edit_distance)
+            .iter()
+            .filter_map(|variant| self.deletes.get(variant))
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+impl Dictionary for SymSpellDictionary {
+    fn contains(&self, word: &[char]) -> bool {
+        let key = lowercase_key(word);
+        self.candidates(word, 0)
+            .into_iter()
+            .any(|id| lowercase_key(&self.chars(id as usize)) == key)
+    }
+
+    fn prefix_match(&self, prefix: &[char], max_results: usize) -> Vec<FuzzyMatchResult> {
+        let prefix = lowercase_key(prefix);
+        let mut results: Vec<FuzzyMatchResult> = self
+            .words
+            .iter()
+            .map(|(word, is_common, _)| (word.chars().collect::<Vec<char>>(), is_common))
+            .filter(|(word, _)| lowercase_key(word).starts_with(&prefix))
+            .map(|(word, is_common)| FuzzyMatchResult {
+                word,
+                edit_distance: 0,
+                is_common,
+                rank: None,
+            })
+            .collect();
+
+        results.sort_by_key(|m| (!m.is_common, m.word.len()));
+        results.truncate(max_results);
+        results
+    }
+
+    fn fuzzy_match(
+        &self,
+        prefix: &[char],
+        max_edit_distance: u8,
+        max_results: usize,
+    ) -> Vec<FuzzyMatchResult> {
+        let within = |word: &[char]| {
+            let distance = osa_distance(prefix, word);
+            (distance <= max_edit_distance).then_some(distance)
+        };
+        let matches: Vec<(usize, u8)> = if max_edit_distance <= MAX_DISTANCE {
+            self.candidates(prefix, max_edit_distance)
+                .into_iter()
+                .filter_map(|id| Some((id as usize, within(&self.chars(id as usize))?)))
+                .collect()
+        } else {
+            (0..self.words.len())
+                .filter_map(|id| Some((id, within(&self.chars(id))?)))
+                .collect()
+        };
+
+        let mut results: Vec<FuzzyMatchResult> = matches
+            .into_iter()
+            .map(|(id, edit_distance)| FuzzyMatchResult {
+                word: self.chars(id),
+                edit_distance,
+                is_common: self.words.get(id).1,
+                rank: None,
+            })
+            .collect();
+
+        // Same order as a full scan: edit distance, then common words, then insertion order
+        results.sort_by(|a, b| {
+            a.edit_distance
+                .cmp(&b.edit_distance)
+                .then_with(|| b.is_common.cmp(&a.is_common))
+        });
+        results.truncate(max_results);
+        results
+    }
+
+    fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "symspell"
+    }
+}
+
+/// The lowercase prefix of `word` with up to `max_deletes` characters removed, including
+/// the prefix itself.
+fn deletion_variants(word: &[char], max_deletes: u8) -> HashSet<String> {
+    let prefix: Vec<char> = word
+        .iter()
+        .take(PREFIX_LENGTH)
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    let mut variants = HashSet::new();
+    let mut frontier = vec![prefix];
+    variants.insert(frontier[0].iter().collect());
+
+    for _ in 0..max_deletes {
+        let mut next = Vec::new();
+        for variant in &frontier {
+            for skip in 0..variant.len() {
+                let mut shorter = variant.clone();
+                shorter.remove(skip);
+                if variants.insert(shorter.iter().collect()) {
+                    next.push(shorter);
+                }
+            }
+        }
+        frontier = next;
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::SimpleDictionary;
+
+    const WORDS: &str = "kot kota kotek płot świat światło zażółć Warszawa warsztat \
+                         przedsiębiorstwo przedsiębiorca dom domek okno";
+
+    #[test]
+    fn test_fuzzy_match_agrees_with_full_scan() {
+        let mut symspell = SymSpellDictionary::new();
+        let mut simple = SimpleDictionary::new();
+        for (idx, word) in WORDS.split_whitespace().enumerate() {
+            symspell.add_word(word, idx % 3 == 0);
+            simple.add_word(word, idx % 3 == 0);
+        }
+
+        let words = |matches: Vec<FuzzyMatchResult>| -> Vec<(String, u8)> {
+            let mut words: Vec<(String, u8)> = matches
+                .into_iter()
+                .map(|m| (m.word.iter().collect(), m.edit_distance))
+                .collect();
+            words.sort();
+            words
+        };
+        let queries = "kto swiat śiwat wraszwaa przedsiebiorstwo przedsiębiortsow dmoek xyz ok";
+        for query in queries.split_whitespace() {
+            let query: Vec<char> = query.chars().collect();
+            for distance in 0..=3 {
+                assert_eq!(
+                    words(symspell.fuzzy_match(&query, distance, 100)),
+                    words(simple.fuzzy_match(&query, distance, 100)),
+                    "{:?} within {}",
+                    query,
+                    distance
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_contains_and_prefix() {
+        let mut symspell = SymSpellDictionary::new();
+        for word in WORDS.split_whitespace() {
+            symspell.add_word(word, false);
+        }
+
+        let word = |text: &str| -> Vec<char> { text.chars().collect() };
+        assert!(symspell.contains(&word("WARSZAWA")));
+        assert!(symspell.contains(&word("przedsiębiorstwo")));
+        assert!(!symspell.contains(&word("przedsiębiorstwa")));
+        assert_eq!(symspell.prefix_match(&word("Kot"), 10).len(), 3);
+        assert_eq!(symspell.word_count(), WORDS.split_whitespace().count());
+    }
+}
@@ -0,0 +1,273 @@
+use std::io;
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tower_lsp_server::jsonrpc::{Request, Response};
+use tower_lsp_server::{ClientSocket, Server};
+use tower_service::Service;
+
+/// Address used when no transport is given on the command line.
+pub const DEFAULT_TCP_ADDR: &str = "127.0.0.1:9257";
+
+/// Buffer between the WebSocket bridge tasks and the LSP server.
+const WEBSOCKET_BUFFER: usize = 64 * 1024;
+
+type Reader = Box<dyn AsyncRead + Unpin + Send>;
+type Writer = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// How the server talks to its client.
+///
+/// Exit codes: 0 when the client sends `exit` or disconnects after `shutdown`, or the
+/// process receives SIGTERM/ctrl-c; 1 when the client leaves without `shutdown` or the
+/// transport cannot be set up; 2 for command line errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// Standard input/output.
+    Stdio,
+    /// Listen on a TCP address and serve the first client that connects.
+    Tcp(SocketAddr),
+    /// Connect to a TCP port opened by the client (VS Code's `TransportKind.socket`).
+    Socket(u16),
+    /// Connect to a named pipe (Windows) or Unix domain socket created by the client.
+    Pipe(String),
+    /// Listen on a TCP address and serve the first WebSocket client, one JSON-RPC
+    /// message per text frame.
+    WebSocket(SocketAddr),
+}
+
+impl Transport {
+    /// Open the connection, waiting for the client where the server is the listener.
+    async fn connect(&self) -> io::Result<(Reader, Writer)> {
+        match self {
+            Transport::Stdio => Ok((Box::new(tokio::io::stdin()), Box::new(tokio::io::stdout()))),
+            Transport::Tcp(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                eprintln!("[POLSKI-LS] Listening on tcp://{}", listener.local_addr()?);
+                let (stream, peer) = listener.accept().await?;
+                eprintln!("[POLSKI-LS] Client connected from {}", peer);
+                Ok(split(stream))
+            }
+            Transport::Socket(port) => {
+                let stream = TcpStream::connect(("127.0.0.1", *port)).await?;
+                eprintln!("[POLSKI-LS] Connected to port {}", port);
+                Ok(split(stream))
+            }
+            Transport::Pipe(name) => connect_pipe(name).await,
+            Transport::WebSocket(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                eprintln!("[POLSKI-LS] Listening on ws://{}", listener.local_addr()?);
+                let (stream, peer) = listener.accept().await?;
+                let websocket = tokio_tungstenite::accept_async(stream)
+                    .await
+                    .map_err(io::Error::other)?;
+                eprintln!("[POLSKI-LS] WebSocket client connected from {}", peer);
+                Ok(bridge_websocket(websocket))
+            }
+        }
+    }
+}
+
+/// Why serving stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    /// The client sent `exit` or closed the connection.
+    Client,
+    /// SIGTERM or ctrl-c arrived first.
+    Signal,
+}
+
+/// Serve `service` over `transport` until the client goes away or a shutdown signal
+/// arrives. Shared by every transport so they all report errors and exit the same way.
+pub async fn serve<S>(
+    transport: &Transport,
+    service: S,
+    socket: ClientSocket,
+) -> Result<Stopped, ExitCode>
+where
+    S: Service<Request, Response = Option<Response>> + Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    S::Future: Send,
+{
+    let (read, write) = match transport.connect().await {
+        Ok(streams) => streams,
+        Err(e) => {
+            eprintln!(
+                "[POLSKI-LS] ERROR: Failed to set up {:?} transport: {}",
+                transport, e
+            );
+            return Err(ExitCode::FAILURE);
+        }
+    };
+
+    tokio::select! {
+        _ = Server::new(read, write, socket).serve(service) => Ok(Stopped::Client),
+        signal = shutdown_signal() => {
+            eprintln!("[POLSKI-LS] Received {}, shutting down", signal);
+            Ok(Stopped::Signal)
+        }
+    }
+}
+
+fn split<T: AsyncRead + AsyncWrite + Send + 'static>(stream: T) -> (Reader, Writer) {
+    let (read, write) = tokio::io::split(stream);
+    (Box::new(read), Box::new(write))
+}
+
+#[cfg(windows)]
+async fn connect_pipe(name: &str) -> io::Result<(Reader, Writer)> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let pipe = ClientOptions::new().open(name)?;
+    eprintln!("[POLSKI-LS] Connected to pipe {}", name);
+    Ok(split(pipe))
+}
+
+#[cfg(unix)]
+async fn connect_pipe(name: &str) -> io::Result<(Reader, Writer)> {
+    let stream = tokio::net::UnixStream::connect(name).await?;
+    eprintln!("[POLSKI-LS] Connected to socket {}", name);
+    Ok(split(stream))
+}
+
+/// Resolve on ctrl-c, or SIGTERM on Unix, returning the signal's name.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "ctrl-c",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                eprintln!("[POLSKI-LS] WARNING: Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "ctrl-c"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "ctrl-c"
+    }
+}
+
+/// Translate between WebSocket frames and the Content-Length framing the LSP server
+/// expects, using two tasks on either side of an in-memory pipe.
+fn bridge_websocket<S>(websocket: tokio_tungstenite::WebSocketStream<S>) -> (Reader, Writer)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (server_side, bridge_side) = tokio::io::duplex(WEBSOCKET_BUFFER);
+    let (mut sink, mut stream) = websocket.split();
+    let (bridge_read, mut bridge_write) = tokio::io::split(bridge_side);
+
+    tokio::spawn(async move {
+        while let Some(Ok(message)) = stream.next().await {
+            let body = match message {
+                Message::Text(text) => text.as_bytes().to_vec(),
+                Message::Binary(data) => data.to_vec(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            if write_message(&mut bridge_write, &body).await.is_err() {
+                break;
+            }
+        }
+        // Closing our end gives the server end of input, ending the session.
+        let _ = bridge_write.shutdown().await;
+    });
+
+    tokio::spawn(async move {
+        let mut reader = tokio::io::BufReader::new(bridge_read);
+        while let Ok(Some(body)) = read_message(&mut reader).await {
+            let text = String::from_utf8_lossy(&body).into_owned();
+            if sink.send(Message::text(text)).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    split(server_side)
+}
+
+/// Write one message with a Content-Length header.
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, body: &[u8]) -> io::Result<()> {
+    let header = format!("Content-Length: {}\r\n\r\n", body.len());
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await
+}
+
+/// Read one Content-Length framed message, or `None` at end of input.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_message_framing_round_trip() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        write_message(&mut client, br#"{"jsonrpc":"2.0"}"#)
+            .await
+            .unwrap();
+        write_message(&mut client, "zażółć".as_bytes())
+            .await
+            .unwrap();
+        drop(client);
+
+        let mut reader = tokio::io::BufReader::new(server);
+        let first = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(first, br#"{"jsonrpc":"2.0"}"#);
+        let second = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(String::from_utf8(second).unwrap(), "zażółć");
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_missing_content_length() {
+        let mut reader: &[u8] = b"Content-Type: application/json\r\n\r\n{}";
+        let err = read_message(&mut reader).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_bind_failure() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let transport = Transport::Tcp(taken.local_addr().unwrap());
+        assert!(transport.connect().await.is_err());
+    }
+}
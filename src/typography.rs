@@ -0,0 +1,383 @@
+use crate::config::NestedQuotes;
+use crate::diagnostics::{
+    DOUBLE_HYPHEN, DOUBLE_SPACE, HANGING_CONJUNCTION, HYPHEN_AS_DASH,
+    MISSING_SPACE_AFTER_PUNCTUATION, SPACE_BEFORE_PUNCTUATION, STRAIGHT_QUOTES,
+};
+
+/// Punctuation written right after the preceding word.
+const CLOSING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', '…'];
+
+/// Punctuation followed by a space before the next word. A period only counts before a
+/// capital letter, since "m.in." or "sjp.pl" are written without one.
+const SPACED_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?'];
+
+/// Characters after a colon or semicolon making it an emoticon (":)", ";P").
+const EMOTICON_FACES: &[char] = &[')', '(', 'D', 'P', 'p', 'O', 'o', '/'];
+
+/// Characters after which a straight quote opens a quotation rather than closing one.
+const BEFORE_OPENING_QUOTE: &[char] = &['(', '[', '„', '«', '‚', '—', '–', '-', '/'];
+
+/// One-letter words that mustn't end a line in typeset Polish text.
+const HANGING_WORDS: &[char] = &['a', 'i', 'o', 'u', 'w', 'z', 'A', 'I', 'O', 'U', 'W', 'Z'];
+
+/// A typesetting problem and the text fixing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub start: usize,
+    pub end: usize,
+    /// One of the diagnostic codes in `diagnostics`.
+    pub code: &'static str,
+    pub message: String,
+    /// What replaces `start..end`.
+    pub replacement: String,
+}
+
+/// Spacing problems in `source`: spaces doubled between words or written before
+/// punctuation, and punctuation not followed by a space. Indentation and trailing
+/// spaces are left alone.
+pub fn spacing(source: &[char]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let ch = source[i];
+        if ch == ' ' || ch == '\t' {
+            let start = i;
+            while i < source.len() && matches!(source[i], ' ' | '\t') {
+                i += 1;
+            }
+            findings.extend(spaces(source, start, i));
+            continue;
+        }
+        if SPACED_PUNCTUATION.contains(&ch) && lacks_space_after(source, i) {
+            findings.push(Finding {
+                start: i,
+                end: i + 1,
+                code: MISSING_SPACE_AFTER_PUNCTUATION,
+                message: format!("Missing space after '{}'", ch),
+                replacement: format!("{} ", ch),
+            });
+        }
+        i += 1;
+    }
+    findings
+}
+
+/// Quotations in straight quotes ("tak"), each rewritten with Polish quotes: „tak”, and
+/// the `nested` marks for a quote within a quote. Straight quotes without a pair, like
+/// inches (5"), are left alone, and quotes don't pair across paragraphs.
+pub fn quotes(source: &[char], nested: NestedQuotes) -> Vec<Finding> {
+    // Quotes still open, with whether their mark is straight
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    // Straight pairs: opening and closing mark, and how many quotes they are nested in
+    let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+    for (i, &ch) in source.iter().enumerate() {
+        match ch {
+            '"' => {
+                let before = i.checked_sub(1).map(|before| source[before]);
+                let opens = before
+                    .is_none_or(|c| c.is_whitespace() || BEFORE_OPENING_QUOTE.contains(&c))
+                    && source.get(i + 1).is_some_and(|c| !c.is_whitespace());
+                if opens {
+                    open.push((i, true));
+                } else if let Some(&(start, true)) = open.last() {
+                    open.pop();
+                    pairs.push((start, i, open.len()));
+                }
+            }
+            '„' | '«' => open.push((i, false)),
+            '”' | '»' if open.last().is_some_and(|&(_, straight)| !straight) => {
+                open.pop();
+            }
+            '\n' if is_paragraph_end(source, i) => open.clear(),
+            _ => {}
+        }
+    }
+
+    // One finding per outermost pair, fixing the pairs inside it as well
+    pairs.sort_unstable();
+    let mut findings: Vec<Finding> = Vec::new();
+    for &(start, end, _) in &pairs {
+        if findings.last().is_some_and(|finding| start < finding.end) {
+            continue;
+        }
+        let mut replacement = source[start..=end].to_vec();
+        for &(inner_start, inner_end, depth) in &pairs {
+            if start <= inner_start && inner_end <= end {
+                let (opening, closing) = if depth % 2 == 0 {
+                    ('„', '”')
+                } else {
+                    nested.marks()
+                };
+                replacement[inner_start - start] = opening;
+                replacement[inner_end - start] = closing;
+            }
+        }
+        findings.push(Finding {
+            start,
+            end: end + 1,
+            code: STRAIGHT_QUOTES,
+            message: format!(
+                "Straight quotes: write {}…{}",
+                replacement[0],
+                replacement[replacement.len() - 1]
+            ),
+            replacement: replacement.into_iter().collect(),
+        });
+    }
+    findings
+}
+
+/// Whether the line break at `idx` is followed by a blank line.
+fn is_paragraph_end(source: &[char], idx: usize) -> bool {
+    source[idx + 1..]
+        .iter()
+        .find(|&&c| c == '\n' || !c.is_whitespace())
+        .is_some_and(|&c| c == '\n')
+}
+
+/// Hyphens typed for a dash: alone between spaces ("tak - nie", not a list item) or
+/// doubled ("tak -- nie", "tak--nie"), each replaced with a półpauza between spaces
+/// ("tak – nie"). Subtraction ("5 - 3"), options ("--verbose") and rules ("---") are
+/// left alone.
+pub fn dashes(source: &[char]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        if source[i] != '-' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < source.len() && source[i] == '-' {
+            i += 1;
+        }
+        let before = start.checked_sub(1).map(|before| source[before]);
+        let after = source.get(i).copied();
+        let (Some(before), Some(after)) = (before, after) else {
+            continue;
+        };
+        let spaced = matches!(before, ' ' | '\t') && matches!(after, ' ' | '\t');
+        let finding = match i - start {
+            1 if spaced && separates_words(source, start, i) => Finding {
+                start,
+                end: i,
+                code: HYPHEN_AS_DASH,
+                message: "Hyphen used as a dash: write '–'".to_string(),
+                replacement: "–".to_string(),
+            },
+            2 if spaced || before.is_alphabetic() && after.is_alphabetic() => Finding {
+                start,
+                end: i,
+                code: DOUBLE_HYPHEN,
+                message: "Double hyphen: write '–'".to_string(),
+                replacement: if spaced { "–" } else { " – " }.to_string(),
+            },
+            _ => continue,
+        };
+        findings.push(finding);
+    }
+    findings
+}
+
+/// Whether the text around the spaced hyphen at `start..end` is words on the same line,
+/// rather than a list bullet or numbers.
+fn separates_words(source: &[char], start: usize, end: usize) -> bool {
+    let before = source[..start]
+        .iter()
+        .rev()
+        .find(|&&c| c != ' ' && c != '\t');
+    let after = source[end..].iter().find(|&&c| c != ' ' && c != '\t');
+    let (Some(&before), Some(&after)) = (before, after) else {
+        return false;
+    };
+    let numbers = before.is_ascii_digit() && after.is_ascii_digit();
+    ends_word(before) && (after.is_alphanumeric() || matches!(after, '„' | '(')) && !numbers
+}
+
+/// Spaces after single-letter words ("w domu", "i kot") where a line could break,
+/// leaving the word hanging at the end of the line; each is replaced with `nbsp`.
+pub fn hanging_conjunctions(source: &[char], nbsp: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (i, &ch) in source.iter().enumerate() {
+        if !HANGING_WORDS.contains(&ch) || source.get(i + 1) != Some(&' ') {
+            continue;
+        }
+        let starts_word = i.checked_sub(1).is_none_or(|before| {
+            matches!(source[before], '(' | '„' | '"') || source[before].is_whitespace()
+        });
+        let next_word = source.get(i + 2).is_some_and(|next| !next.is_whitespace());
+        if starts_word && next_word {
+            findings.push(Finding {
+                start: i + 1,
+                end: i + 2,
+                code: HANGING_CONJUNCTION,
+                message: format!("'{}' may end a line: bind it to the next word", ch),
+                replacement: nbsp.to_string(),
+            });
+        }
+    }
+    findings
+}
+
+/// The problem with the spaces at `start..end`, if any.
+fn spaces(source: &[char], start: usize, end: usize) -> Option<Finding> {
+    let before = *source.get(start.checked_sub(1)?)?;
+    let after = *source.get(end)?;
+    if before.is_whitespace() || after.is_whitespace() {
+        return None;
+    }
+    if CLOSING_PUNCTUATION.contains(&after) && ends_word(before) && !is_emoticon(source, end) {
+        return Some(Finding {
+            start,
+            end,
+            code: SPACE_BEFORE_PUNCTUATION,
+            message: format!("Space before '{}'", after),
+            replacement: String::new(),
+        });
+    }
+    let doubled = end - start > 1 && source[start..end].iter().all(|&c| c == ' ');
+    doubled.then(|| Finding {
+        start,
+        end,
+        code: DOUBLE_SPACE,
+        message: "Double space".to_string(),
+        replacement: " ".to_string(),
+    })
+}
+
+/// Whether the punctuation at `idx` runs into the next word, as in "kot,pies" or
+/// "kot ,pies".
+fn lacks_space_after(source: &[char], idx: usize) -> bool {
+    let before = source[..idx].iter().rev().find(|&&c| c != ' ' && c != '\t');
+    let (Some(&before), Some(&after)) = (before, source.get(idx + 1)) else {
+        return false;
+    };
+    if !ends_word(before) || !after.is_alphabetic() {
+        return false;
+    }
+    match source[idx] {
+        '.' => before.is_lowercase() && after.is_uppercase(),
+        ':' | ';' => !is_emoticon(source, idx),
+        _ => true,
+    }
+}
+
+/// Whether `ch` can end the word a punctuation mark belongs to.
+fn ends_word(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, ')' | '"' | '”' | '»' | '\'')
+}
+
+/// Whether the colon or semicolon at `idx` starts an emoticon.
+fn is_emoticon(source: &[char], idx: usize) -> bool {
+    matches!(source[idx], ':' | ';')
+        && source
+            .get(idx + 1)
+            .is_some_and(|next| EMOTICON_FACES.contains(next))
+        && source
+            .get(idx + 2)
+            .is_none_or(|next| !next.is_alphanumeric())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// `text` with every finding of `find` fixed.
+    pub(crate) fn apply(text: &str, find: impl Fn(&[char]) -> Vec<Finding>) -> String {
+        let source: Vec<char> = text.chars().collect();
+        let mut result = String::new();
+        let mut last = 0;
+        for finding in find(&source) {
+            result.extend(&source[last..finding.start]);
+            result.push_str(&finding.replacement);
+            last = finding.end;
+        }
+        result.extend(&source[last..]);
+        result
+    }
+
+    #[test]
+    fn test_spacing_findings() {
+        let source: Vec<char> = "Kot  i pies ,a".chars().collect();
+        let codes: Vec<&str> = spacing(&source).iter().map(|f| f.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                DOUBLE_SPACE,
+                SPACE_BEFORE_PUNCTUATION,
+                MISSING_SPACE_AFTER_PUNCTUATION
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spacing_fixes() {
+        assert_eq!(
+            apply("Ala  ma kota ,psa i rybkę .Koniec!Dalej?Tak;nie", spacing),
+            "Ala ma kota, psa i rybkę. Koniec! Dalej? Tak; nie"
+        );
+        assert_eq!(
+            apply("To był kot.Pies też", spacing),
+            "To był kot. Pies też"
+        );
+    }
+
+    fn quoted(text: &str, nested: NestedQuotes) -> Vec<String> {
+        let source: Vec<char> = text.chars().collect();
+        quotes(&source, nested)
+            .into_iter()
+            .map(|finding| finding.replacement)
+            .collect()
+    }
+
+    #[test]
+    fn test_straight_quotes() {
+        assert_eq!(
+            quoted("Powiedział \"tak\" i (\"nie\").", NestedQuotes::Single),
+            vec!["„tak”", "„nie”"]
+        );
+        assert_eq!(
+            quoted("\"Mówi \"tak\" zawsze\"", NestedQuotes::Guillemets),
+            vec!["„Mówi «tak» zawsze”"]
+        );
+        assert_eq!(
+            quoted("„Mówi \"tak\" zawsze”", NestedQuotes::Single),
+            vec!["‚tak’"]
+        );
+    }
+
+    #[test]
+    fn test_unpaired_quotes_are_left_alone() {
+        assert!(quoted("Ekran 27\" i \"cytat\n\nakapit\"", NestedQuotes::Single).is_empty());
+    }
+
+    #[test]
+    fn test_dashes() {
+        assert_eq!(
+            apply("Kot - jak zwykle - spał, pies -- też, a ryba--nie.", dashes),
+            "Kot – jak zwykle – spał, pies – też, a ryba – nie."
+        );
+    }
+
+    #[test]
+    fn test_hyphens_left_alone() {
+        let text = "- lista\n  - punkt\nbiało-czerwony, 5 - 3, opcja --verbose\n---\nx -\n";
+        assert_eq!(apply(text, dashes), text);
+    }
+
+    #[test]
+    fn test_hanging_conjunctions() {
+        let source: Vec<char> = "Kot i pies (w domu), a Z  tyłu o\nx-a b".chars().collect();
+        let bound: Vec<usize> = hanging_conjunctions(&source, "~")
+            .iter()
+            .map(|finding| finding.start)
+            .collect();
+        assert_eq!(bound, vec![5, 13, 22]);
+    }
+
+    #[test]
+    fn test_correct_spacing_is_left_alone() {
+        let text = "  Wcięcie, m.in. sjp.pl, 3,14 zł, 12:30 i Nie wiem :) ;P\ttab  \n- lista";
+        assert_eq!(apply(text, spacing), text);
+    }
+}
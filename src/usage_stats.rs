@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// File name of the usage store in the user data directory (~/.local/share/polski-ls/).
+pub const USAGE_FILE: &str = "uzycie.tsv";
+
+/// Words kept in the store; beyond this the least used ones are dropped when saving.
+const MAX_WORDS: usize = 5000;
+/// Uses from which a word earns the whole usage bonus.
+const SATURATION: u32 = 20;
+/// Unsaved uses after which the store is written without waiting for shutdown.
+const SAVE_EVERY: u32 = 10;
+
+/// How often the user accepted each completion, kept across sessions so the words they
+/// actually write rank higher.
+///
+/// Stored as one `word<TAB>count` line per lowercase word. It is written to a temporary
+/// file that is then renamed into place, so a crash never leaves it half-written.
+#[derive(Debug, Default)]
+pub struct UsageStats {
+    counts: HashMap<String, u32>,
+    /// Where the store is saved; `None` keeps it in memory only.
+    path: Option<PathBuf>,
+    /// Uses recorded since the store was last written.
+    unsaved: u32,
+}
+
+impl UsageStats {
+    /// Create an empty store that is never saved.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the store from the user data directory; it starts empty if there is none.
+    pub fn load() -> Self {
+        let path = dirs::data_dir().map(|dir| dir.join("polski-ls").join(USAGE_FILE));
+        Self::load_from(path)
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let mut stats = Self::new();
+        if let Some(content) = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+            eprintln!("[POLSKI-LS] Loading usage statistics: {:?}", path);
+            stats.parse(&content);
+        }
+        stats.path = path;
+        stats
+    }
+
+    /// Add counts from store text, skipping malformed lines.
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let Some((word, count)) = line.trim().split_once('\t') else {
+                continue;
+            };
+            if let Ok(count) = count.trim().parse::<u32>() {
+                let total = self.counts.entry(word.trim().to_lowercase()).or_default();
+                *total = total.saturating_add(count);
+            }
+        }
+    }
+
+    /// Record one use of `word`, writing the store every few uses.
+    pub fn record(&mut self, word: &str) {
+        let count = self.counts.entry(word.to_lowercase()).or_default();
+        *count = count.saturating_add(1);
+        self.unsaved += 1;
+        if self.unsaved >= SAVE_EVERY {
+            self.save();
+        }
+    }
+
+    /// Share of the usage bonus `word` (lowercase) earns: 0 when never used, growing
+    /// with diminishing returns to 1 at `SATURATION` uses.
+    pub fn weight(&self, word: &str) -> f32 {
+        let count = self.counts.get(word).copied().unwrap_or(0);
+        ((1.0 + count as f32).ln() / (1.0 + SATURATION as f32).ln()).min(1.0)
+    }
+
+    /// Write the store if uses were recorded since it was last written. Failing to write
+    /// only loses those uses, so errors are logged.
+    pub fn save(&mut self) {
+        let Some(path) = self.path.clone().filter(|_| self.unsaved > 0) else {
+            return;
+        };
+
+        self.trim(MAX_WORDS);
+        let mut words: Vec<(&String, &u32)> = self.counts.iter().collect();
+        words.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let content: String = words
+            .into_iter()
+            .map(|(word, count)| format!("{}\t{}\n", word, count))
+            .collect();
+
+        let temporary = path.with_extension("tsv.tmp");
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&temporary, content))
+            .and_then(|()| std::fs::rename(&temporary, &path));
+        match written {
+            Ok(()) => self.unsaved = 0,
+            Err(e) => eprintln!(
+                "[POLSKI-LS] ERROR: Cannot save usage statistics {:?}: {}",
+                path, e
+            ),
+        }
+    }
+
+    /// Drop the least used words beyond `max_words`.
+    fn trim(&mut self, max_words: usize) {
+        if self.counts.len() <= max_words {
+            return;
+        }
+        let mut counts: Vec<u32> = self.counts.values().copied().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let threshold = counts[max_words];
+        // Ties at the threshold go too, so the store never grows past the limit
+        self.counts.retain(|_, count| *count > threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weight_grows_with_use() {
+        let mut stats = UsageStats::new();
+        assert_eq!(stats.weight("kubernetes"), 0.0);
+        stats.record("Kubernetes");
+        let once = stats.weight("kubernetes");
+        assert!(once > 0.0 && once < 1.0);
+        for _ in 0..SATURATION {
+            stats.record("kubernetes");
+        }
+        assert_eq!(stats.weight("kubernetes"), 1.0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("polski-ls-usage-{}", std::process::id()));
+        let path = dir.join(USAGE_FILE);
+        let mut stats = UsageStats::load_from(Some(path.clone()));
+        stats.record("żółw");
+        stats.record("kot");
+        stats.record("żółw");
+        stats.save();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "żółw\t2\nkot\t1\n");
+        let loaded = UsageStats::load_from(Some(path));
+        assert_eq!(loaded.weight("żółw"), stats.weight("żółw"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_trim_keeps_most_used() {
+        let mut stats = UsageStats::new();
+        stats.parse("kot\t5\npies\t3\nżółw\t1\nmysz\t1\n# comment\n");
+        stats.trim(2);
+        assert_eq!(stats.counts.len(), 2);
+        assert!(stats.weight("pies") > 0.0);
+        assert_eq!(stats.weight("mysz"), 0.0);
+    }
+}
@@ -0,0 +1,121 @@
+/// Words stored back to back in one string with their end offsets, and whether each one
+/// is common in a bitset. A dictionary of a few million inflected forms takes a byte or
+/// two per letter this way, instead of a heap vector of four-byte chars per word.
+/// Frequency ranks only take room once a list has some.
+#[derive(Clone, Default)]
+pub struct WordList {
+    text: String,
+    /// End of each word in `text`; a word starts where the previous one ends.
+    ends: Vec<u32>,
+    /// Bit `i` is set when word `i` is common.
+    common: Vec<u64>,
+    /// Frequency rank of each word, 0 for none; empty until a ranked word is added.
+    ranks: Vec<u32>,
+}
+
+impl WordList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a word.
+    pub fn push(&mut self, word: &str, is_common: bool, rank: Option<u32>) {
+        let idx = self.ends.len();
+        if rank.is_some() || !self.ranks.is_empty() {
+            self.ranks.resize(idx, 0);
+            self.ranks.push(rank.unwrap_or(0));
+        }
+        if idx.is_multiple_of(64) {
+            self.common.push(0);
+        }
+        if is_common {
+            self.common[idx / 64] |= 1 << (idx % 64);
+        }
+        self.text.push_str(word);
+        let end = u32::try_from(self.text.len()).expect("word list shorter than 4 GiB");
+        self.ends.push(end);
+    }
+
+    /// Number of words.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// The word at `idx`, whether it is common and its frequency rank.
+    pub fn get(&self, idx: usize) -> (&str, bool, Option<u32>) {
+        let start = match idx {
+            0 => 0,
+            _ => self.ends[idx - 1] as usize,
+        };
+        let word = &self.text[start..self.ends[idx] as usize];
+        let is_common = self.common[idx / 64] & (1 << (idx % 64)) != 0;
+        let rank = self.ranks.get(idx).copied().filter(|&rank| rank > 0);
+        (word, is_common, rank)
+    }
+
+    /// Every word with whether it is common and its rank, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool, Option<u32>)> {
+        (0..self.len()).map(|idx| self.get(idx))
+    }
+
+    /// Whether the list has `word`, ignoring case.
+    pub fn contains_word(&self, word: &[char]) -> bool {
+        self.iter().any(|(w, _, _)| {
+            w.chars().count() == word.len()
+                && w.chars()
+                    .zip(word)
+                    .all(|(x, y)| x.to_lowercase().eq(y.to_lowercase()))
+        })
+    }
+}
+
+impl<'a> Extend<(&'a str, bool, Option<u32>)> for WordList {
+    fn extend<T: IntoIterator<Item = (&'a str, bool, Option<u32>)>>(&mut self, words: T) {
+        for (word, is_common, rank) in words {
+            self.push(word, is_common, rank);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_and_flags() {
+        let mut list = WordList::new();
+        assert_eq!(list.len(), 0);
+        // Enough words to need a second bitset block
+        for idx in 0..70 {
+            list.push(
+                if idx % 2 == 0 { "żółw" } else { "kot" },
+                idx % 3 == 0,
+                None,
+            );
+        }
+        list.push("", true, None);
+
+        assert_eq!(list.len(), 71);
+        assert_eq!(list.get(0), ("żółw", true, None));
+        assert_eq!(list.get(1), ("kot", false, None));
+        assert_eq!(list.get(69), ("kot", true, None));
+        assert_eq!(list.get(70), ("", true, None));
+        assert_eq!(
+            list.iter().filter(|(_, is_common, _)| *is_common).count(),
+            25
+        );
+        assert!(list.contains_word(&"ŻÓŁW".chars().collect::<Vec<_>>()));
+        assert!(!list.contains_word(&"koty".chars().collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn test_ranks() {
+        let mut list = WordList::new();
+        list.push("kot", false, None);
+        assert!(list.ranks.is_empty());
+        list.push("pies", false, Some(42));
+        list.push("żółw", true, None);
+        let ranks: Vec<Option<u32>> = list.iter().map(|(_, _, rank)| rank).collect();
+        assert_eq!(ranks, [None, Some(42), None]);
+    }
+}